@@ -0,0 +1,72 @@
+extern crate wyzoid;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::{self, Thread};
+use wyzoid::{high, utils};
+
+const DATA_LEN: usize = 64 * 1024;
+
+/// Wake the thread running `block_on`.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// The smallest executor there is: run `future` on this thread, sleeping while it is pending.
+/// Jobs don't depend on a runtime, so `tokio` or `async-std` would work the same.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+fn main() {
+    let first = utils::rand_vec::<f32>(DATA_LEN, 0.0, 1.0);
+    let second = utils::rand_vec::<f32>(DATA_LEN, -1.0, 0.0);
+
+    // A shader that multiply its input by two, with a local work size of 64.
+    let shader = PathBuf::from("examples/shaders/bin/examples/double.cs.spirv");
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+
+    let mut first_job = high::job::JobBuilder::new()
+        .add_buffer(&first, 0, 0)
+        .add_shader(&shader)
+        .dispatch_for(DATA_LEN as u32, 1, 1)
+        .build(vulkan.clone());
+    let mut second_job = high::job::JobBuilder::new()
+        .add_buffer(&second, 0, 0)
+        .add_shader(&shader)
+        .dispatch_for(DATA_LEN as u32, 1, 1)
+        .build(vulkan);
+
+    // Both jobs are submitted before we await any of them, so they run at the same time.
+    let (first_output, second_output) = block_on(async {
+        let first_future = first_job.execute_async();
+        let second_future = second_job.execute_async();
+        (first_future.await, second_future.await)
+    });
+    let first_output = first_output.expect("The first job failed.");
+    let second_output = second_output.expect("The second job failed.");
+
+    for (input, output) in [(&first, &first_output), (&second, &second_output)] {
+        for i in 0..DATA_LEN {
+            assert_eq!(input[i] * 2.0, output[0][i], "Wrong value at index {}.", i);
+        }
+    }
+
+    println!("First job timings:\n{}", first_job.get_timing());
+    println!("Second job timings:\n{}", second_job.get_timing());
+    println!("Both jobs were awaited without polling their status.");
+}
@@ -1,6 +1,6 @@
 extern crate wyzoid;
 use std::path::PathBuf;
-use std::rc::Rc;
+use std::sync::Arc;
 use wyzoid::{high, utils};
 
 const DATA_LEN: usize = 64;
@@ -12,19 +12,19 @@ fn main() {
     // We use a simple shader that multiply our input by two.
     let shader = PathBuf::from("examples/shaders/bin/examples/double.cs.spirv");
 
-    let vulkan = Rc::new(wyzoid::low::vkstate::init_vulkan());
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
 
     // We create the compute job.
-    // Since our shader has a local work size of 64, we divide the number of data by 64 for the dispatch.
+    // The number of work groups is computed from the local work size of the shader.
     let mut job = high::job::JobBuilder::new()
         .add_buffer(&input, 0, 0)
         .add_shader(&shader)
-        .add_dispatch(((DATA_LEN / 64) as u32, 1, 1))
+        .dispatch_for(DATA_LEN as u32, 1, 1)
         .build(vulkan);
 
     job.execute();
     while job.status() == wyzoid::high::job::JobStatus::EXECUTING {
-        job.wait_until_idle(1 * 1000 * 1000 * 1000);
+        job.wait_until_idle(1000 * 1000 * 1000);
     }
     let shader_output = job.get_output().unwrap();
     let timings = job.get_timing();
@@ -1,7 +1,9 @@
 extern crate wyzoid;
+use std::io::BufReader;
 use std::path::PathBuf;
-use std::rc::Rc;
+use std::sync::Arc;
 
+use wyzoid::utils::image::{Channels, Normalize};
 use wyzoid::{high, utils};
 
 fn main() {
@@ -10,39 +12,69 @@ fn main() {
     let fbm = PathBuf::from("examples/shaders/bin/examples/fbm.cs.spirv");
     let turbo = PathBuf::from("examples/shaders/bin/examples/turbo.cs.spirv");
 
-    let vulkan = Rc::new(wyzoid::low::vkstate::init_vulkan());
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+
+    // The noise stays on the device between the two jobs.
+    let noise = high::Buffer::<f32>::uninit(vulkan.clone(), 256 * 256);
 
-    // We create the compute job.
     // The first shader has a local size of (8,8), so we need to dispatch (32,32) job
     // fill our 256x256 image.
+    let mut noise_job = high::job::JobBuilder::<f32>::new()
+        .add_device_buffer(&noise, 0, 0)
+        .add_shader(&fbm)
+        .add_dispatch((32, 32, 1))
+        .build(vulkan.clone());
+
+    noise_job.execute();
+    while noise_job.status() == wyzoid::high::job::JobStatus::EXECUTING {
+        noise_job.wait_until_idle(1000 * 1000 * 1000);
+    }
+
     // The second one use a local size of 64 linearly over x, so we juste need to dispatch
     // the size of the image divided by 64 to cover the entire space.
     let mut job = high::job::JobBuilder::new()
-        .add_ro_buffer(256 * 256, 0, 0)
+        .add_device_buffer(&noise, 0, 0)
         .add_ro_buffer(256 * 256 * 4, 0, 1)
-        .add_shader(&fbm)
         .add_shader(&turbo)
-        .add_dispatch((32, 32, 1))
         .add_dispatch((256 * 256 / 64, 1, 1))
         .build(vulkan);
 
     job.execute();
     while job.status() == wyzoid::high::job::JobStatus::EXECUTING {
-        job.wait_until_idle(1 * 1000 * 1000 * 1000);
+        job.wait_until_idle(1000 * 1000 * 1000);
     }
+    // Only the buffers owned by the job are in the output.
     let shader_output = job.get_output().unwrap();
     let timings = job.get_timing();
 
     println!("Timings:\n{}", timings);
     // We oversize the img data vec but whatever
-    let mut img_data: Vec<f32> = Vec::with_capacity(shader_output[1].len());
+    let mut img_data: Vec<f32> = Vec::with_capacity(shader_output[0].len());
     // [vec4] -> [vec3]
-    for i in 0..(shader_output[1].len() / 4) {
-        img_data.push(shader_output[1][i * 4 + 0]);
-        img_data.push(shader_output[1][i * 4 + 1]);
-        img_data.push(shader_output[1][i * 4 + 2]);
+    for i in 0..(shader_output[0].len() / 4) {
+        img_data.push(shader_output[0][i * 4]);
+        img_data.push(shader_output[0][i * 4 + 1]);
+        img_data.push(shader_output[0][i * 4 + 2]);
     }
 
-    let ppm = utils::to_ppm(&img_data, 256, 256);
-    std::fs::write(PathBuf::from("./ex.ppm"), ppm.unwrap()).expect("Could not write image.");
+    // The colors of the colormap are already in [0, 1].
+    let mut file = std::fs::File::create("./ex.ppm").expect("Could not create image.");
+    utils::to_ppm(
+        &mut file,
+        &img_data,
+        256,
+        256,
+        Channels::Rgb,
+        Normalize::Clamp,
+    )
+    .expect("Could not write image.");
+
+    // Reading the image back gives the colors quantized to 8 bits.
+    let file = std::fs::File::open("./ex.ppm").expect("Could not open image.");
+    let image = utils::from_ppm(BufReader::new(file)).expect("Could not read image.");
+    assert_eq!((image.width, image.height), (256, 256));
+    assert_eq!(image.channels, Channels::Rgb);
+    for (read, written) in image.pixels.iter().zip(&img_data) {
+        assert!((read - written.clamp(0.0, 1.0)).abs() <= 1.0 / 255.0);
+    }
 }
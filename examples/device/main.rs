@@ -0,0 +1,36 @@
+extern crate wyzoid;
+
+/**
+ * Print what the device can do, and pick a local size from it.
+*/
+fn main() {
+    let vulkan = wyzoid::low::vkstate::init_vulkan();
+    let info = &vulkan.info;
+    println!("{}", info);
+
+    // A work group as large as the device allows on x, in whole subgroups.
+    let limits = &info.limits;
+    let max_local =
+        limits.max_compute_work_group_size[0].min(limits.max_compute_work_group_invocations);
+    let subgroup = info.subgroup.size.max(1);
+    println!(
+        "Largest local size on x in whole subgroups: {}",
+        max_local / subgroup * subgroup
+    );
+    println!(
+        "Subgroup arithmetic in compute shaders: {}",
+        info.subgroup
+            .supported_stages
+            .iter()
+            .any(|s| s == "COMPUTE")
+            && info
+                .subgroup
+                .supported_operations
+                .iter()
+                .any(|op| op == "ARITHMETIC")
+    );
+    println!(
+        "Timeline semaphores: {}",
+        info.has_feature("timeline_semaphore")
+    );
+}
@@ -0,0 +1,53 @@
+extern crate wyzoid;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wyzoid::low::vkfeatures::Feature;
+use wyzoid::low::vkstate::DeviceBuilder;
+use wyzoid::{high, utils};
+
+const DATA_LEN: usize = 1000;
+
+/**
+ * Evaluate a polynomial in double precision, on a device asked for `shaderFloat64`.
+*/
+fn main() {
+    let input = utils::rand_vec::<f64>(DATA_LEN, -10.0, 10.0);
+    let shader = PathBuf::from("examples/shaders/bin/examples/horner64.cs.spirv");
+
+    // Not every device has 64 bits floats in shaders, so we only ask for them.
+    let vulkan = Arc::new(
+        DeviceBuilder::new()
+            .optional_feature(Feature::ShaderFloat64)
+            .build(),
+    );
+    if !vulkan.has_feature(Feature::ShaderFloat64) {
+        println!("The device does not support shaderFloat64.");
+        return;
+    }
+
+    let mut job = high::job::JobBuilder::new()
+        .add_buffer(&input, 0, 0)
+        .add_shader(&shader)
+        .dispatch_for(DATA_LEN as u32, 1, 1)
+        .require_feature(Feature::ShaderFloat64)
+        .build(vulkan);
+    job.execute();
+    while job.status() == wyzoid::high::job::JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+    let output = &job.get_output().unwrap()[0];
+
+    let third = 1.0 / 3.0;
+    let mut max_error: f64 = 0.0;
+    for (x, y) in input.iter().zip(output) {
+        let expected = ((x - 3.0) * x + 0.25) * x - third;
+        max_error = max_error.max((y - expected).abs() / expected.abs().max(1.0));
+    }
+    println!("Max relative error: {:e}", max_error);
+    // Additions and multiplications are correctly rounded, only the constant is not exact.
+    assert!(
+        max_error < 1e-12,
+        "The GPU does not compute in double precision."
+    );
+    println!("The GPU computed in double precision.");
+}
@@ -0,0 +1,64 @@
+extern crate wyzoid;
+use std::sync::Arc;
+use std::time::Instant;
+use wyzoid::utils::random::Generator;
+use wyzoid::{high, utils};
+
+const DATA_LEN: usize = 1_000_000;
+
+fn main() {
+    // Unlike the "sorting" example, the data is bigger than a work group,
+    // so the sort needs several dispatches.
+    let keys = utils::rand_vec::<f32>(DATA_LEN, -1000.0, 1000.0);
+    let values: Vec<u32> = (0..DATA_LEN as u32).collect();
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+
+    let gpu_timer = Instant::now();
+    let (sorted_keys, sorted_values) = high::sort::sort_by_key(vulkan.clone(), &keys, &values);
+    let gpu_time = gpu_timer.elapsed();
+
+    let mut expected = keys.clone();
+    let cpu_timer = Instant::now();
+    expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let cpu_time = cpu_timer.elapsed();
+
+    assert_eq!(sorted_keys, expected, "GPU and CPU sort differ.");
+    for (key, value) in sorted_keys.iter().zip(sorted_values.iter()) {
+        assert_eq!(*key, keys[*value as usize], "A value lost its key.");
+    }
+
+    // Inputs known to be hard for some sorts, from a seed printed on failure.
+    let mut generator = Generator::from_env();
+    let len = DATA_LEN + 17;
+    let cases = vec![
+        ("uniform", generator.uniform::<i32>(len, i32::MIN, i32::MAX)),
+        ("sorted", generator.sorted(len, i32::MIN, i32::MAX)),
+        (
+            "reverse sorted",
+            generator.reverse_sorted(len, i32::MIN, i32::MAX),
+        ),
+        (
+            "nearly sorted",
+            generator.nearly_sorted(len, i32::MIN, i32::MAX, 100),
+        ),
+        ("duplicates", generator.duplicates(len, 16, -8, 8)),
+    ];
+    for (name, ints) in cases {
+        let mut expected_ints = ints.clone();
+        expected_ints.sort();
+        assert!(
+            high::sort::sort(vulkan.clone(), &ints) == expected_ints,
+            "GPU and CPU sort of {} integers differ, seed {}.",
+            name,
+            generator.seed()
+        );
+    }
+
+    println!(
+        "Sorted {} elements. GPU: {}ms, CPU: {}ms",
+        DATA_LEN,
+        utils::get_fract_s(gpu_time),
+        utils::get_fract_s(cpu_time)
+    );
+}
@@ -0,0 +1,103 @@
+extern crate wyzoid;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wyzoid::high::graph::{Graph, Node};
+use wyzoid::high::job::ShaderSource;
+use wyzoid::utils;
+
+const WIDTH: usize = 256;
+const HEIGHT: usize = 256;
+const N: usize = WIDTH * HEIGHT;
+
+fn cpu_edges(image: &[f32]) -> Vec<f32> {
+    let mut edges = vec![0.0; N];
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let at = |x: usize, y: usize| image[y * WIDTH + x];
+            let gx = at((x + 1).min(WIDTH - 1), y) - at(x.saturating_sub(1), y);
+            let gy = at(x, (y + 1).min(HEIGHT - 1)) - at(x, y.saturating_sub(1));
+            let magnitude = (gx * gx + gy * gy).sqrt();
+            let mut direction = gy.atan2(gx);
+            if direction < 0.0 {
+                direction += std::f32::consts::PI;
+            }
+            edges[y * WIDTH + x] = magnitude * direction.sin().abs();
+        }
+    }
+    edges
+}
+
+fn main() {
+    let image = utils::rand_vec::<f32>(N, 0.0, 1.0);
+
+    let gradient = PathBuf::from("examples/shaders/bin/examples/gradient.cs.spirv");
+    let magnitude = PathBuf::from("examples/shaders/bin/examples/magnitude.cs.spirv");
+    let direction = PathBuf::from("examples/shaders/bin/examples/direction.cs.spirv");
+    let combine = PathBuf::from("examples/shaders/bin/examples/combine.cs.spirv");
+
+    // The magnitude and the direction both only need the gradient, so they run at the same time.
+    // The gradient is not needed anymore when the edges are combined, so they share its memory.
+    let groups = (N / 64) as u32;
+    let graph = Graph::new()
+        .add_input("image", &image)
+        .add_transient::<f32>("gx", N)
+        .add_transient::<f32>("gy", N)
+        .add_transient::<f32>("magnitude", N)
+        .add_transient::<f32>("direction", N)
+        .add_transient::<f32>("combined", N)
+        .add_output::<f32>("edges", N)
+        .add_node(
+            Node::dispatch(
+                "gradient",
+                ShaderSource::File(&gradient),
+                ((WIDTH / 8) as u32, (HEIGHT / 8) as u32, 1),
+            )
+            .read(0, "image")
+            .write(1, "gx")
+            .write(2, "gy")
+            .constants(&[WIDTH as u32, HEIGHT as u32]),
+        )
+        .add_node(
+            Node::dispatch("magnitude", ShaderSource::File(&magnitude), (groups, 1, 1))
+                .read(0, "gx")
+                .read(1, "gy")
+                .write(2, "magnitude")
+                .constants(&[N as u32]),
+        )
+        .add_node(
+            Node::dispatch("direction", ShaderSource::File(&direction), (groups, 1, 1))
+                .read(0, "gx")
+                .read(1, "gy")
+                .write(2, "direction")
+                .constants(&[N as u32]),
+        )
+        .add_node(
+            Node::dispatch("combine", ShaderSource::File(&combine), (groups, 1, 1))
+                .read(0, "magnitude")
+                .read(1, "direction")
+                .write(2, "combined")
+                .constants(&[N as u32]),
+        )
+        .add_node(Node::copy("save", "combined", "edges"));
+
+    let schedule = graph.schedule();
+    println!("Levels: {:?}", schedule.levels);
+    println!("Barriers: {:?}", schedule.barriers);
+    std::fs::write(PathBuf::from("./graph.dot"), graph.to_dot()).expect("Could not write graph.");
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+    let output = graph.execute(vulkan);
+    let edges: Vec<f32> = output.get("edges").unwrap();
+
+    let expected = cpu_edges(&image);
+    for i in 0..N {
+        assert!(
+            (expected[i] - edges[i]).abs() < 1e-4,
+            "Wrong edge at index {}: expected {}, got {}.",
+            i,
+            expected[i],
+            edges[i]
+        );
+    }
+    println!("The edges match the CPU, the graph is in graph.dot.");
+}
@@ -0,0 +1,59 @@
+extern crate wyzoid;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wyzoid::high;
+
+const BINS: usize = 32;
+
+fn main() {
+    // We bin the FBM noise of the "dataviz" example.
+    let fbm = PathBuf::from("examples/shaders/bin/examples/fbm.cs.spirv");
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+
+    let mut job = high::job::JobBuilder::<f32>::new()
+        .add_ro_buffer(256 * 256, 0, 0)
+        .add_shader(&fbm)
+        .add_dispatch((32, 32, 1))
+        .build(vulkan.clone());
+    job.execute();
+    while job.status() == high::job::JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+    let noise = job.get_output().unwrap().swap_remove(0);
+
+    let histogram = high::histogram::histogram_auto(vulkan.clone(), &noise, BINS);
+
+    // Infinities and NaN are left out of the range and of the counts.
+    let mut with_specials = noise.clone();
+    with_specials.extend_from_slice(&[f32::INFINITY, f32::NEG_INFINITY, f32::NAN]);
+    let special_histogram = high::histogram::histogram_auto(vulkan, &with_specials, BINS);
+    assert_eq!(special_histogram, histogram);
+
+    // Same binning on the CPU. Values right on a bin edge may land in the
+    // neighbour bin depending on the GPU float precision, so we allow a small difference.
+    let scale = BINS as f32 / (histogram.high - histogram.low);
+    let mut expected = [0u32; BINS];
+    for value in &noise {
+        let bin = (((value - histogram.low) * scale) as usize).min(BINS - 1);
+        expected[bin] += 1;
+    }
+    assert_eq!(
+        histogram.counts.iter().sum::<u32>(),
+        noise.len() as u32,
+        "Some values were not counted."
+    );
+    for (gpu, cpu) in histogram.counts.iter().zip(expected.iter()) {
+        assert!((*gpu as i64 - *cpu as i64).abs() <= 2);
+    }
+
+    let max_count = *histogram.counts.iter().max().unwrap();
+    for (bin, count) in histogram.counts.iter().enumerate() {
+        println!(
+            "{:>8.4} | {:<60} {}",
+            histogram.bin_start(bin),
+            "#".repeat((*count as usize * 60) / max_count as usize),
+            count
+        );
+    }
+}
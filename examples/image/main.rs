@@ -0,0 +1,80 @@
+extern crate wyzoid;
+use ash::vk;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wyzoid::low::vkimage::SamplerConfig;
+use wyzoid::{high, utils};
+
+const WIDTH: u32 = 300;
+const HEIGHT: u32 = 200;
+
+/// The RGBA pixel at (x, y), clamped to the edges of the image.
+fn pixel(image: &[f32], x: i64, y: i64) -> &[f32] {
+    let x = x.clamp(0, WIDTH as i64 - 1) as usize;
+    let y = y.clamp(0, HEIGHT as i64 - 1) as usize;
+    let start = (y * WIDTH as usize + x) * 4;
+    &image[start..start + 4]
+}
+
+/**
+ * Blur a sampled image into an output image, and invert a storage image in place,
+ * then check both against the CPU.
+*/
+fn main() {
+    let len = (WIDTH * HEIGHT * 4) as usize;
+    let source = utils::rand_vec::<f32>(len, 0.0, 1.0);
+    let colors = utils::rand_vec::<f32>(len, 0.0, 1.0);
+    let shader = PathBuf::from("examples/shaders/bin/examples/blur_image.cs.spirv");
+    // texelFetch reads exact pixels, which doesn't need linear filtering.
+    let sampler = SamplerConfig {
+        filter: vk::Filter::NEAREST,
+        ..Default::default()
+    };
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+    let format = vk::Format::R32G32B32A32_SFLOAT;
+    let mut job = high::job::JobBuilder::new()
+        .add_sampled_image(&source, (WIDTH, HEIGHT), format, sampler, 0, 0)
+        .add_output_image((WIDTH, HEIGHT), format, 0, 1)
+        .add_storage_image(&colors, (WIDTH, HEIGHT), format, 0, 2)
+        .add_shader(&shader)
+        .dispatch_for(WIDTH, HEIGHT, 1)
+        .build(vulkan);
+    job.execute();
+    while job.status() == wyzoid::high::job::JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+    // The sampled image is read only, so only the two storage images come back.
+    let images = job.get_image_output().unwrap();
+    assert_eq!(images.len(), 2);
+
+    for y in 0..HEIGHT as i64 {
+        for x in 0..WIDTH as i64 {
+            let mut expected = [0.0f32; 4];
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    for (e, v) in expected.iter_mut().zip(pixel(&source, x + dx, y + dy)) {
+                        *e += v;
+                    }
+                }
+            }
+            for (e, v) in expected.iter().zip(pixel(&images[0], x, y)) {
+                assert!(
+                    (e / 9.0 - v).abs() <= 1e-5,
+                    "blurred ({}, {}) is {}",
+                    x,
+                    y,
+                    v
+                );
+            }
+
+            let color = pixel(&colors, x, y);
+            let inverted = pixel(&images[1], x, y);
+            for c in 0..3 {
+                assert!((1.0 - color[c] - inverted[c]).abs() <= 1e-6);
+            }
+            assert_eq!(color[3], inverted[3]);
+        }
+    }
+    println!("{}x{} image blurred and inverted.", WIDTH, HEIGHT);
+}
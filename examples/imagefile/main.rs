@@ -0,0 +1,131 @@
+extern crate wyzoid;
+use ash::vk;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wyzoid::high;
+use wyzoid::low::vkimage::SamplerConfig;
+use wyzoid::utils::image::{Channels, ColorMap, Image, Normalize};
+
+/// A 256x256 RGB test pattern: two gradients and a disc.
+fn test_pattern() -> Image<u8> {
+    let mut pixels = Vec::with_capacity(256 * 256 * 3);
+    for y in 0..256i32 {
+        for x in 0..256i32 {
+            let disc = (x - 128).pow(2) + (y - 128).pow(2) < 64 * 64;
+            pixels.extend_from_slice(&[x as u8, y as u8, if disc { 255 } else { 0 }]);
+        }
+    }
+    Image::new(256, 256, Channels::Rgb, pixels)
+}
+
+/**
+ * Load an image file, blur and invert it on the GPU, check the result against the CPU,
+ * and save the blurred and inverted images, and a color map of what the blur removed.
+ * Give it a PPM, PGM, PFM, or PNG (with the `png` feature) file, or it uses a test pattern:
+ * `cargo run --example imagefile -- picture.ppm`
+*/
+fn main() {
+    let out_dir = std::env::temp_dir();
+    let path = match std::env::args().nth(1) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let path = out_dir.join("wyzoid_pattern.ppm");
+            test_pattern()
+                .save(&path, Normalize::Clamp)
+                .expect("[ERR] Could not save the test pattern.");
+            path
+        }
+    };
+    // Storage images seldom support RGB formats, so the shader works on RGBA.
+    let source: Image<f32> =
+        Image::load_as(&path, Channels::Rgba).expect("[ERR] Could not load the image.");
+    let format = source.format().unwrap();
+    let extent = source.extent();
+    let shader = PathBuf::from("examples/shaders/bin/examples/blur_image.cs.spirv");
+    let sampler = SamplerConfig {
+        filter: vk::Filter::NEAREST,
+        ..Default::default()
+    };
+
+    // Headers of empty images are rejected.
+    let empty_pfm = out_dir.join("wyzoid_empty.pfm");
+    std::fs::write(&empty_pfm, "Pf\n0 4\n-1.0\n").expect("[ERR] Could not write the file.");
+    assert!(Image::<f32>::load(&empty_pfm).is_err());
+    assert!(Image::<u8>::read_pnm(&b"P5\n4 0\n255\n"[..]).is_err());
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+    let mut job = high::job::JobBuilder::new()
+        .add_sampled_image(&source.pixels, extent, format, sampler, 0, 0)
+        .add_output_image(extent, format, 0, 1)
+        .add_storage_image(&source.pixels, extent, format, 0, 2)
+        .add_shader(&shader)
+        .dispatch_for(extent.0, extent.1, 1)
+        .build(vulkan);
+    job.execute();
+    while job.status() == wyzoid::high::job::JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+    let mut images = job.get_image_output().unwrap();
+    let inverted = Image::new(
+        source.width,
+        source.height,
+        Channels::Rgba,
+        images.pop().unwrap(),
+    );
+    let blurred = Image::new(
+        source.width,
+        source.height,
+        Channels::Rgba,
+        images.pop().unwrap(),
+    );
+
+    // Check the blur against the CPU, with the edges clamped as the shader does.
+    let (w, h) = (source.width as i64, source.height as i64);
+    for y in 0..h {
+        for x in 0..w {
+            for c in 0..4 {
+                let mut sum = 0.0;
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        let (sx, sy) = ((x + dx).clamp(0, w - 1), (y + dy).clamp(0, h - 1));
+                        sum += source.pixels[((sy * w + sx) * 4 + c) as usize];
+                    }
+                }
+                let i = ((y * w + x) * 4 + c) as usize;
+                assert!((sum / 9.0 - blurred.pixels[i]).abs() <= 1e-5);
+                let expected = if c == 3 {
+                    source.pixels[i]
+                } else {
+                    1.0 - source.pixels[i]
+                };
+                assert!((expected - inverted.pixels[i]).abs() <= 1e-6);
+            }
+        }
+    }
+
+    // What the blur removed is large on the edges of the image.
+    let detail: Vec<f32> = source
+        .to_channels(Channels::R)
+        .pixels
+        .iter()
+        .zip(&blurred.to_channels(Channels::R).pixels)
+        .map(|(a, b)| (a - b).abs())
+        .collect();
+    let detail = Image::new(source.width, source.height, Channels::R, detail);
+
+    let outputs = [
+        (blurred, "wyzoid_blurred.ppm"),
+        (inverted, "wyzoid_inverted.ppm"),
+        (
+            detail.colormap(ColorMap::Inferno, Normalize::MinMax),
+            "wyzoid_detail.ppm",
+        ),
+    ];
+    for (image, name) in outputs.iter() {
+        let path = out_dir.join(name);
+        image
+            .save(&path, Normalize::Clamp)
+            .expect("[ERR] Could not save the image.");
+        println!("Saved {}", path.display());
+    }
+}
@@ -1,49 +1,76 @@
 extern crate wyzoid;
 use std::path::PathBuf;
-use std::rc::Rc;
-use wyzoid::{high, utils};
+use std::sync::Arc;
+use wyzoid::high;
+use wyzoid::high::verify::Verification;
+use wyzoid::utils;
+use wyzoid::utils::compare::Tolerance;
+use wyzoid::utils::random::Generator;
 
 const DATA_LEN: usize = 64;
 
 fn main() {
     // We generate 64 random float between 0.0 and 1.0.
-    let input1 = utils::rand_vec::<f32>(DATA_LEN, 0.0, 1.0);
-    let input2 = utils::rand_vec::<f32>(DATA_LEN, 0.0, 1.0);
+    // Run with WYZOID_SEED=<seed> to get the inputs of a previous run again.
+    let mut generator = Generator::from_env();
+    let input1 = generator.uniform::<f32>(DATA_LEN, 0.0, 1.0);
+    let input2 = generator.uniform::<f32>(DATA_LEN, 0.0, 1.0);
 
     // We use a shader that compute sinus and cosinus using taylor series.
     // Buffer one will be sinus, buffer two will be cosinus.
     let shader = PathBuf::from("examples/shaders/bin/examples/taylor.cs.spirv");
 
-    let vulkan = Rc::new(wyzoid::low::vkstate::init_vulkan());
+    // Integers are compared exactly.
+    let numbers = utils::rand_vec_with(&mut generator, DATA_LEN, 1u32, 10_000);
+    let collatz = PathBuf::from("examples/shaders/bin/examples/collatz.cs.spirv");
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
 
     // We create the compute job.
-    // Since our shader has a local work size of 64, we divide the number of data by 64 for the dispatch.
+    // The number of work groups is computed from the local work size of the shader.
     let mut job = high::job::JobBuilder::new()
         .add_buffer(&input1, 0, 0)
         .add_buffer(&input2, 0, 1)
         .add_shader(&shader)
-        .add_dispatch(((DATA_LEN / 64) as u32, 1, 1))
-        .build(vulkan);
+        .dispatch_for(DATA_LEN as u32, 1, 1)
+        .build(vulkan.clone());
 
-    job.execute();
-    while job.status() == wyzoid::high::job::JobStatus::EXECUTING {
-        job.wait_until_idle(1 * 1000 * 1000 * 1000);
-    }
-    let shader_output = job.get_output().unwrap();
-    let timings = job.get_timing();
-
-    for i in 0..DATA_LEN {
-        println!(
-            "[{}] in1: {}, sin_cpu: {}, sin_gpu: {}, in2: {}, cos_cpu: {}, cos_gpu: {}",
-            i,
-            input1[i],
-            f32::sin(input1[i]),
-            shader_output[0][i],
-            input2[i],
-            f32::cos(input2[i]),
-            shader_output[1][i]
-        );
-    }
-
-    println!("Timings:\n{}", timings);
+    // The CPU computes the same sinus and cosinus, the series and `pow` of the GPU
+    // are a few ULPs away from them.
+    let report = Verification::new(|| {
+        vec![
+            input1.iter().map(|x| x.sin()).collect(),
+            input2.iter().map(|x| x.cos()).collect(),
+        ]
+    })
+    .tolerance(Tolerance::abs(1e-5))
+    .seed(generator.seed())
+    .run(&mut job);
+    print!("{}", report);
+    println!("Timings:\n{}", report.job_timings);
+    report.assert();
+
+    let mut collatz_job = high::job::JobBuilder::new()
+        .add_buffer(&numbers, 0, 0)
+        .add_shader(&collatz)
+        .dispatch_for(DATA_LEN as u32, 1, 1)
+        .build(vulkan);
+    let report = Verification::new(|| {
+        let steps = numbers
+            .iter()
+            .map(|&n| {
+                let (mut n, mut steps) = (n, 0u32);
+                while n > 1 {
+                    n = if n % 2 == 0 { n / 2 } else { 3 * n + 1 };
+                    steps += 1;
+                }
+                steps
+            })
+            .collect();
+        vec![steps]
+    })
+    .seed(generator.seed())
+    .run(&mut collatz_job);
+    print!("{}", report);
+    report.assert();
 }
@@ -1,6 +1,6 @@
 extern crate wyzoid;
 use std::path::PathBuf;
-use std::rc::Rc;
+use std::sync::Arc;
 use wyzoid::{high, utils};
 
 const DATA_LEN: usize = 64;
@@ -15,7 +15,7 @@ fn main() {
     let taylor = PathBuf::from("examples/shaders/bin/examples/taylor.cs.spirv");
     let add_sub = PathBuf::from("examples/shaders/bin/examples/add_sub.cs.spirv");
 
-    let vulkan = Rc::new(wyzoid::low::vkstate::init_vulkan());
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
 
     // We create the compute job.
     // Since our shader has a local work size of 64, we divide the number of data by 64 for the dispatch.
@@ -30,7 +30,7 @@ fn main() {
 
     job.execute();
     while job.status() == wyzoid::high::job::JobStatus::EXECUTING {
-        job.wait_until_idle(1 * 1000 * 1000 * 1000);
+        job.wait_until_idle(1000 * 1000 * 1000);
     }
     let shader_output = job.get_output().unwrap();
     let timings = job.get_timing();
@@ -0,0 +1,58 @@
+extern crate wyzoid;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wyzoid::utils::npy::{self, NpzReader, NpzWriter};
+use wyzoid::{high, utils};
+
+const ROWS: usize = 16;
+const COLUMNS: usize = 64;
+
+/**
+ * Save the inputs of a job in a .npz archive, load them back as job inputs,
+ * and save the output in a .npy file, which NumPy reads with `numpy.load`.
+*/
+fn main() {
+    let out_dir = std::env::temp_dir();
+    let inputs_path = out_dir.join("wyzoid_inputs.npz");
+    let output_path = out_dir.join("wyzoid_doubled.npy");
+
+    let mut archive = NpzWriter::create(&inputs_path).expect("[ERR] Could not create archive.");
+    let values = utils::rand_vec::<f32>(ROWS * COLUMNS, -1.0, 1.0);
+    archive.add("values", &values, &[ROWS, COLUMNS]).unwrap();
+    let ids: Vec<u32> = (0..ROWS as u32).collect();
+    archive.add("ids", &ids, &[ROWS]).unwrap();
+    archive.finish().expect("[ERR] Could not write archive.");
+
+    let archive = NpzReader::open(&inputs_path).expect("[ERR] Could not read archive.");
+    println!("Arrays in {}: {:?}", inputs_path.display(), archive.names());
+    let input = archive.get::<f32>("values").unwrap();
+    assert_eq!(input.shape, vec![ROWS, COLUMNS]);
+    assert_eq!(input.data, values);
+    assert_eq!(archive.get::<u32>("ids").unwrap().data, ids);
+
+    // The shader multiplies each value by two.
+    let shader = PathBuf::from("examples/shaders/bin/examples/double.cs.spirv");
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+    let mut job = high::job::JobBuilder::new()
+        .add_buffer(&input.data, 0, 0)
+        .add_shader(&shader)
+        .dispatch_for(input.len() as u32, 1, 1)
+        .build(vulkan);
+    job.execute();
+    while job.status() == wyzoid::high::job::JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+    let output = job.get_output().unwrap().remove(0);
+
+    npy::save_npy(&output_path, &output, &input.shape).expect("[ERR] Could not save output.");
+    let saved = npy::load_npy::<_, f32>(&output_path).unwrap();
+    assert_eq!(saved.shape, input.shape);
+    for (x, y) in input.data.iter().zip(&saved.data) {
+        assert_eq!(x * 2.0, *y);
+    }
+    println!(
+        "Saved {}, compare it in Python with numpy.load(\"{}\")",
+        output_path.display(),
+        output_path.display()
+    );
+}
@@ -1,53 +1,80 @@
 extern crate wyzoid;
-use std::path::PathBuf;
-use std::rc::Rc;
-use wyzoid::{high, utils};
+use std::sync::Arc;
+use wyzoid::high::reduce::{self, ReduceOp};
+use wyzoid::high::Buffer;
+use wyzoid::utils;
 
-const DATA_LEN: usize = 64;
+const DATA_LEN: usize = 10_000_000;
 
 fn main() {
-    // We generate 64 random float between 0.0 and 1.0.
-    let input1 = utils::rand_vec::<f32>(256, 0.0, 1.0);
-    let input2 = utils::rand_vec::<f32>(64, 0.0, 1.0);
-
-    // We use a shader that compute sinus and cosinus using taylor series.
-    // Buffer one will be sinus, buffer two will be cosinus.
-    let shader = PathBuf::from("examples/shaders/bin/examples/reduce.cs.spirv");
-
-    let vulkan = Rc::new(wyzoid::low::vkstate::init_vulkan());
-
-    // We create the compute job.
-    // Since our shader has a local work size of 64, we divide the number of data by 64 for the dispatch.
-    let mut job = high::job::JobBuilder::new()
-        .add_buffer(&input1, 0, 0)
-        .add_buffer(&input2, 0, 1)
-        .add_shader(&shader)
-        .add_dispatch((4, 1, 1))
-        .build(vulkan);
-
-    job.execute();
-    while job.status() == wyzoid::high::job::JobStatus::EXECUTING {
-        job.wait_until_idle(1 * 1000 * 1000 * 1000);
-    }
-    let shader_output = job.get_output().unwrap();
-    let timings = job.get_timing();
-    println!("=======\ntimings:\n{}\n=======", timings);
-
-    for i in input1 {
-        print!("{};", i);
-    }
-
-    println!("");
-
-    for i in input2 {
-        print!("{};", i);
-    }
-
-    println!("");
-
-    for i in &shader_output[1] {
-        print!("{};", i);
-    }
-
-    println!("");
+    let floats = utils::rand_vec::<f32>(DATA_LEN, -1.0, 1.0);
+    let ints = utils::rand_vec::<i32>(DATA_LEN + 3, -1000, 1000);
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+
+    // The GPU sums with a tree, the CPU in f64, so we only expect them to be close.
+    let gpu_sum = reduce::reduce(vulkan.clone(), &floats, ReduceOp::Sum);
+    let cpu_sum: f64 = floats.iter().map(|&v| v as f64).sum();
+    println!("sum: gpu {}, cpu {}", gpu_sum, cpu_sum);
+    assert!((gpu_sum as f64 - cpu_sum).abs() < 1.0);
+
+    let (cpu_min, cpu_max) = utils::min_max(&floats).unwrap();
+    assert_eq!(
+        reduce::reduce(vulkan.clone(), &floats, ReduceOp::Min),
+        cpu_min
+    );
+    assert_eq!(
+        reduce::reduce(vulkan.clone(), &floats, ReduceOp::Max),
+        cpu_max
+    );
+
+    let int_sum: i32 = ints.iter().sum();
+    assert_eq!(
+        reduce::reduce(vulkan.clone(), &ints, ReduceOp::Sum),
+        int_sum
+    );
+
+    let (min_index, min_value) = reduce::argmin(vulkan.clone(), &ints).unwrap();
+    let cpu_min_value = *ints.iter().min().unwrap();
+    assert_eq!(min_value, cpu_min_value);
+    assert_eq!(
+        min_index,
+        ints.iter().position(|&v| v == cpu_min_value).unwrap()
+    );
+
+    let (max_index, max_value) = reduce::argmax(vulkan.clone(), &ints).unwrap();
+    let cpu_max_value = *ints.iter().max().unwrap();
+    assert_eq!(max_value, cpu_max_value);
+    assert_eq!(
+        max_index,
+        ints.iter().position(|&v| v == cpu_max_value).unwrap()
+    );
+
+    // The result can also stay on the device for the next jobs.
+    let buffer = Buffer::from_slice(vulkan.clone(), &ints);
+    assert_eq!(
+        reduce::argmax_buffer(&buffer).read(),
+        (max_value, max_index as u32)
+    );
+
+    // Infinities are real elements, and NaN only wins when there is nothing else.
+    let infinite = vec![f32::INFINITY; 3];
+    assert_eq!(
+        reduce::argmin(vulkan.clone(), &infinite),
+        Some((0, f32::INFINITY))
+    );
+    let some_nan = vec![f32::NAN, 2.0, f32::NAN, -1.0];
+    assert_eq!(reduce::argmax(vulkan.clone(), &some_nan), Some((1, 2.0)));
+    assert_eq!(
+        reduce::reduce(vulkan.clone(), &some_nan, ReduceOp::Min),
+        -1.0
+    );
+    let all_nan = vec![f32::NAN; 4];
+    let (nan_index, nan_value) = reduce::argmin(vulkan, &all_nan).unwrap();
+    assert!(nan_index == 0 && nan_value.is_nan());
+
+    println!(
+        "min: {} at {}, max: {} at {}",
+        min_value, min_index, max_value, max_index
+    );
 }
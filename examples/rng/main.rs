@@ -0,0 +1,64 @@
+extern crate wyzoid;
+use std::sync::Arc;
+use wyzoid::high::rng::Philox;
+use wyzoid::high::{self, Buffer};
+use wyzoid::utils;
+use wyzoid::utils::compare::{compare_buffers, Tolerance};
+
+const DATA_LEN: usize = 1_000_000;
+const SEED: u64 = 0x5EED;
+
+/**
+ * Fill device buffers with random numbers on the GPU, and check that the CPU
+ * implementation of the generator gives the same numbers.
+*/
+fn main() {
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+    let mut gpu = Philox::new(SEED);
+    let mut cpu = Philox::new(SEED);
+
+    let mut bits = Buffer::<u32>::device_local(vulkan.clone(), DATA_LEN);
+    let timings = gpu.fill_u32(&mut bits);
+    assert_eq!(bits.read(), cpu.gen_u32(DATA_LEN));
+    println!(
+        "{} random u32 in {}ms",
+        DATA_LEN,
+        utils::get_fract_s(timings.execution)
+    );
+
+    // Each fill continues the stream, the dice don't reuse the bits above.
+    let mut dice = Buffer::<u32>::device_local(vulkan.clone(), DATA_LEN);
+    gpu.fill_range_u32(&mut dice, 1, 7);
+    assert_eq!(dice.read(), cpu.gen_range_u32(DATA_LEN, 1, 7));
+
+    let mut uniform = Buffer::<f32>::device_local(vulkan.clone(), DATA_LEN);
+    gpu.fill_uniform(&mut uniform, -1.0, 1.0);
+    compare_buffers(
+        &cpu.gen_uniform(DATA_LEN, -1.0, 1.0),
+        &uniform.read(),
+        Tolerance::exact(),
+    )
+    .assert();
+
+    let mut normal = Buffer::<f32>::device_local(vulkan.clone(), DATA_LEN);
+    gpu.fill_normal(&mut normal, 0.0, 1.0);
+    let normal = normal.read();
+    compare_buffers(
+        &cpu.gen_normal(DATA_LEN, 0.0, 1.0),
+        &normal,
+        Tolerance::exact(),
+    )
+    .assert();
+    assert_eq!(gpu.offset(), cpu.offset());
+    println!("The GPU and the CPU generated the same numbers.");
+
+    let histogram = high::histogram::histogram(vulkan, &normal, 16, -4.0, 4.0);
+    let max = *histogram.counts.iter().max().unwrap();
+    for (bin, count) in histogram.counts.iter().enumerate() {
+        println!(
+            "{:>5.1} {}",
+            histogram.bin_start(bin),
+            "#".repeat((count * 60 / max) as usize)
+        );
+    }
+}
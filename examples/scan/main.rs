@@ -0,0 +1,42 @@
+extern crate wyzoid;
+use std::sync::Arc;
+use wyzoid::high::scan;
+use wyzoid::utils;
+
+const DATA_LEN: usize = 1_000_003;
+
+fn main() {
+    let input = utils::rand_vec::<u32>(DATA_LEN, 0, 16);
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+
+    let mut acc = 0;
+    let cpu_inclusive: Vec<u32> = input
+        .iter()
+        .map(|v| {
+            acc += v;
+            acc
+        })
+        .collect();
+    let gpu_inclusive = scan::inclusive_scan(vulkan.clone(), &input);
+    assert_eq!(gpu_inclusive, cpu_inclusive);
+
+    let mut cpu_exclusive = vec![0];
+    cpu_exclusive.extend_from_slice(&cpu_inclusive[..DATA_LEN - 1]);
+    let gpu_exclusive = scan::exclusive_scan(vulkan.clone(), &input);
+    assert_eq!(gpu_exclusive, cpu_exclusive);
+
+    // Keep the floats above 0.5.
+    let floats = utils::rand_vec::<f32>(DATA_LEN, 0.0, 1.0);
+    let keep: Vec<bool> = floats.iter().map(|&v| v > 0.5).collect();
+    let cpu_compacted: Vec<f32> = floats.iter().copied().filter(|&v| v > 0.5).collect();
+    let gpu_compacted = scan::compact(vulkan, &floats, &keep);
+    assert_eq!(gpu_compacted, cpu_compacted);
+
+    println!(
+        "Scanned {} elements, total: {}. Kept {} floats.",
+        DATA_LEN,
+        gpu_inclusive[DATA_LEN - 1],
+        gpu_compacted.len()
+    );
+}
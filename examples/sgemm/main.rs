@@ -0,0 +1,68 @@
+extern crate wyzoid;
+use std::sync::Arc;
+use wyzoid::high::linalg::{self, Matrix};
+use wyzoid::utils;
+
+// Not multiples of the tile sizes, to go through the edge cases.
+const M: usize = 1000;
+const N: usize = 1100;
+const K: usize = 900;
+
+fn main() {
+    let a_data = utils::rand_vec::<f32>(M * K, -1.0, 1.0);
+    let b_data = utils::rand_vec::<f32>(K * N, -1.0, 1.0);
+    let c_data = utils::rand_vec::<f32>(M * N, -1.0, 1.0);
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+
+    let a = Matrix::new(vulkan.clone(), M, K, &a_data);
+    let b = Matrix::new(vulkan.clone(), K, N, &b_data);
+    let mut c = Matrix::new(vulkan.clone(), M, N, &c_data);
+
+    let (alpha, beta) = (0.5, 2.0);
+    let timings = linalg::sgemm(alpha, &a, &b, beta, &mut c);
+    println!("Timings:\n{}", timings);
+    println!(
+        "SGEMM {}x{}x{}: {:.2} GFLOP/s",
+        M,
+        N,
+        K,
+        timings.gflops(linalg::sgemm_flop(M, N, K))
+    );
+
+    let gpu_c = c.to_vec();
+    let mut max_error: f32 = 0.0;
+    for i in 0..M {
+        for j in 0..N {
+            let mut acc = 0.0;
+            for k in 0..K {
+                acc += a_data[i * K + k] * b_data[k * N + j];
+            }
+            let expected = alpha * acc + beta * c_data[i * N + j];
+            max_error = max_error.max((expected - gpu_c[i * N + j]).abs());
+        }
+    }
+    println!("SGEMM max absolute error: {}", max_error);
+    assert!(max_error < 1e-3);
+
+    let mut a_t = Matrix::uninit(vulkan.clone(), K, M);
+    linalg::transpose(&a, &mut a_t);
+    let gpu_a_t = a_t.to_vec();
+    for i in 0..M {
+        for k in 0..K {
+            assert_eq!(gpu_a_t[k * M + i], a_data[i * K + k]);
+        }
+    }
+
+    let x_data = utils::rand_vec::<f32>(K, -1.0, 1.0);
+    let x = Matrix::new(vulkan.clone(), K, 1, &x_data);
+    let mut y = Matrix::uninit(vulkan, M, 1);
+    linalg::gemv(1.0, &a, &x, 0.0, &mut y);
+    let gpu_y = y.to_vec();
+    for i in 0..M {
+        let expected: f32 = (0..K).map(|k| a_data[i * K + k] * x_data[k]).sum();
+        assert!((expected - gpu_y[i]).abs() < 1e-3);
+    }
+
+    println!("Transpose and GEMV match the CPU.");
+}
@@ -0,0 +1,20 @@
+#version 450
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+layout(set = 0, binding = 0) uniform samplerBuffer data_in;
+layout(set = 0, binding = 1, r32f) uniform writeonly imageBuffer data_out;
+layout(std140, set = 0, binding = 2) uniform Params {
+  float scale;
+  float offset;
+  uint count;
+};
+
+void main() {
+  uint idx = gl_GlobalInvocationID.x;
+  if (idx >= count) {
+    return;
+  }
+  float x = texelFetch(data_in, int(idx)).r;
+  imageStore(data_out, int(idx), vec4(x * scale + offset));
+}
@@ -0,0 +1,35 @@
+#version 450
+
+// 1D box blur of radius 1 over a chunk of a stream, clamped at the ends of the dataset.
+// The chunk comes with one halo element on each side, except at the ends of the dataset.
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+layout(push_constant) uniform Chunk {
+  uint count;
+  uint halo_before;
+  uint input_len;
+  uint offset_low;
+  uint offset_high;
+};
+
+layout(std430, set = 0, binding = 0) readonly buffer Data {
+  float data[];
+};
+
+layout(std430, set = 0, binding = 1) writeonly buffer Result {
+  float result[];
+};
+
+void main() {
+  uint group = gl_WorkGroupID.y * gl_NumWorkGroups.x + gl_WorkGroupID.x;
+  uint idx = group * gl_WorkGroupSize.x + gl_LocalInvocationID.x;
+  if (idx >= count) {
+    return;
+  }
+
+  uint center = idx + halo_before;
+  uint left = center > 0 ? center - 1 : center;
+  uint right = center + 1 < input_len ? center + 1 : center;
+  result[idx] = (data[left] + data[center] + data[right]) / 3.0;
+}
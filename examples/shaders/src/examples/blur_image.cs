@@ -0,0 +1,28 @@
+#version 450
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+layout(set = 0, binding = 1, rgba32f) uniform writeonly image2D blurred;
+layout(set = 0, binding = 2, rgba32f) uniform image2D inverted;
+
+void main() {
+  ivec2 size = textureSize(source, 0);
+  ivec2 pixel = ivec2(gl_GlobalInvocationID.xy);
+  if (pixel.x >= size.x || pixel.y >= size.y) {
+    return;
+  }
+
+  // 3x3 box blur, clamped to the edges.
+  vec4 sum = vec4(0.0);
+  for (int dy = -1; dy <= 1; dy++) {
+    for (int dx = -1; dx <= 1; dx++) {
+      ivec2 p = clamp(pixel + ivec2(dx, dy), ivec2(0), size - 1);
+      sum += texelFetch(source, p, 0);
+    }
+  }
+  imageStore(blurred, pixel, sum / 9.0);
+
+  vec4 v = imageLoad(inverted, pixel);
+  imageStore(inverted, pixel, vec4(1.0 - v.rgb, v.a));
+}
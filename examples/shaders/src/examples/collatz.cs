@@ -0,0 +1,20 @@
+#version 450
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer Data { uint data[]; };
+
+// Replace each value by the number of steps of its Collatz sequence to reach 1.
+void main() {
+  uint idx = gl_GlobalInvocationID.x;
+  if (idx >= data.length()) {
+    return;
+  }
+  uint n = data[idx];
+  uint steps = 0;
+  while (n > 1) {
+    n = (n % 2 == 0) ? n / 2 : 3 * n + 1;
+    steps++;
+  }
+  data[idx] = steps;
+}
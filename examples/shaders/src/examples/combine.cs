@@ -0,0 +1,30 @@
+#version 450
+
+// Keep the edges that are mostly horizontal, weighted by their strength.
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+layout(push_constant) uniform Size {
+  uint n;
+};
+
+layout(std430, set = 0, binding = 0) readonly buffer Magnitude {
+  float magnitude[];
+};
+
+layout(std430, set = 0, binding = 1) readonly buffer Direction {
+  float direction[];
+};
+
+layout(std430, set = 0, binding = 2) writeonly buffer Edges {
+  float edges[];
+};
+
+void main() {
+  uint idx = gl_GlobalInvocationID.x;
+  if (idx >= n) {
+    return;
+  }
+
+  edges[idx] = magnitude[idx] * abs(sin(direction[idx]));
+}
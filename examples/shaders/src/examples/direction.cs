@@ -0,0 +1,31 @@
+#version 450
+
+// Direction of the gradient, folded in [0, pi] so opposite directions are the same edge.
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+layout(push_constant) uniform Size {
+  uint n;
+};
+
+layout(std430, set = 0, binding = 0) readonly buffer GradientX {
+  float gx[];
+};
+
+layout(std430, set = 0, binding = 1) readonly buffer GradientY {
+  float gy[];
+};
+
+layout(std430, set = 0, binding = 2) writeonly buffer Direction {
+  float direction[];
+};
+
+void main() {
+  uint idx = gl_GlobalInvocationID.x;
+  if (idx >= n) {
+    return;
+  }
+
+  float angle = atan(gy[idx], gx[idx]);
+  direction[idx] = angle < 0.0 ? angle + 3.14159265 : angle;
+}
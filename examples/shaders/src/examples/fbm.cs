@@ -9,10 +9,25 @@ layout(std430, set = 0, binding = 0) buffer Data {
   float data[]; 
 };
 
+// Philox4x32-10 of the lattice point, as in `high::rng`.
+uvec4 philox(uvec4 ctr, uvec2 key) {
+  for (uint i = 0; i < 10; i++) {
+    if (i > 0) {
+      key.x += 0x9E3779B9u;
+      key.y += 0xBB67AE85u;
+    }
+    uint hi0, lo0, hi1, lo1;
+    umulExtended(0xD2511F53u, ctr.x, hi0, lo0);
+    umulExtended(0xCD9E8D57u, ctr.z, hi1, lo1);
+    ctr = uvec4(hi1 ^ ctr.y ^ key.x, lo1, hi0 ^ ctr.w ^ key.y, lo0);
+  }
+  return ctr;
+}
+
 float random (in vec2 st) {
-    return fract(sin(dot(st.xy,
-                         vec2(12.9898,78.233)))*
-        43758.5453123);
+    uvec2 cell = uvec2(ivec2(st));
+    uint bits = philox(uvec4(cell, 0, 0), uvec2(0x5EED, 0)).x;
+    return float(bits >> 8) / 16777216.0;
 }
 
 // Based on Morgan McGuire @morgan3d
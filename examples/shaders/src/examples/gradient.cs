@@ -0,0 +1,37 @@
+#version 450
+
+// Central differences of an image, clamped at the borders.
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+
+layout(push_constant) uniform Size {
+  uint width;
+  uint height;
+};
+
+layout(std430, set = 0, binding = 0) readonly buffer Image {
+  float image[];
+};
+
+layout(std430, set = 0, binding = 1) writeonly buffer GradientX {
+  float gx[];
+};
+
+layout(std430, set = 0, binding = 2) writeonly buffer GradientY {
+  float gy[];
+};
+
+void main() {
+  uint x = gl_GlobalInvocationID.x;
+  uint y = gl_GlobalInvocationID.y;
+  if (x >= width || y >= height) {
+    return;
+  }
+
+  uint left = x > 0 ? x - 1 : x;
+  uint right = x + 1 < width ? x + 1 : x;
+  uint up = y > 0 ? y - 1 : y;
+  uint down = y + 1 < height ? y + 1 : y;
+  gx[y * width + x] = image[y * width + right] - image[y * width + left];
+  gy[y * width + x] = image[down * width + x] - image[up * width + x];
+}
@@ -0,0 +1,17 @@
+#version 450
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer Data { double data[]; };
+
+// Needs the shaderFloat64 feature.
+void main() {
+  uint idx = gl_GlobalInvocationID.x;
+  if (idx >= data.length()) {
+    return;
+  }
+  // `precise` keeps the compiler from fusing the operations, so the result matches the CPU.
+  precise double x = data[idx];
+  precise double y = ((x - 3.0LF) * x + 0.25LF) * x - 1.0LF / 3.0LF;
+  data[idx] = y;
+}
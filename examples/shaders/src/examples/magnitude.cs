@@ -0,0 +1,30 @@
+#version 450
+
+// Length of the gradient.
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+layout(push_constant) uniform Size {
+  uint n;
+};
+
+layout(std430, set = 0, binding = 0) readonly buffer GradientX {
+  float gx[];
+};
+
+layout(std430, set = 0, binding = 1) readonly buffer GradientY {
+  float gy[];
+};
+
+layout(std430, set = 0, binding = 2) writeonly buffer Magnitude {
+  float magnitude[];
+};
+
+void main() {
+  uint idx = gl_GlobalInvocationID.x;
+  if (idx >= n) {
+    return;
+  }
+
+  magnitude[idx] = sqrt(gx[idx] * gx[idx] + gy[idx] * gy[idx]);
+}
@@ -0,0 +1,38 @@
+extern crate wyzoid;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wyzoid::high::job::ShaderSource;
+use wyzoid::high::stream::Stream;
+use wyzoid::utils;
+
+const DATA_LEN: usize = 10_000_000;
+const CHUNK_LEN: usize = 1 << 20;
+
+fn main() {
+    let input = utils::rand_vec::<f32>(DATA_LEN, 0.0, 1.0);
+
+    // A blur reading one neighbour on each side, so chunks need a halo of one element.
+    let shader = PathBuf::from("examples/shaders/bin/examples/blur.cs.spirv");
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+
+    // Only three chunks live on the device at once, whatever the size of the input.
+    let output: Vec<f32> = Stream::new(ShaderSource::File(&shader), CHUNK_LEN)
+        .halo(1)
+        .buffering(3)
+        .run(vulkan, &input);
+
+    for i in 0..DATA_LEN {
+        let left = input[i.saturating_sub(1)];
+        let right = input[(i + 1).min(DATA_LEN - 1)];
+        let expected = (left + input[i] + right) / 3.0;
+        assert!(
+            (expected - output[i]).abs() < 1e-6,
+            "Wrong value at index {}: expected {}, got {}.",
+            i,
+            expected,
+            output[i]
+        );
+    }
+    println!("Blurred {} floats in chunks of {}.", DATA_LEN, CHUNK_LEN);
+}
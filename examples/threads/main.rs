@@ -0,0 +1,79 @@
+extern crate wyzoid;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use wyzoid::{high, utils};
+
+const DATA_LEN: usize = 64 * 1024;
+const WORKERS: usize = 4;
+
+fn main() {
+    // A shader that multiply its input by two, with a local work size of 64.
+    let shader = PathBuf::from("examples/shaders/bin/examples/double.cs.spirv");
+
+    // One device shared by every thread.
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+
+    let inputs: Vec<Vec<f32>> = (0..WORKERS)
+        .map(|_| utils::rand_vec::<f32>(DATA_LEN, 0.0, 1.0))
+        .collect();
+
+    // This job is built here but executed by another thread.
+    let main_input = utils::rand_vec::<f32>(DATA_LEN, -1.0, 0.0);
+    let mut main_job = high::job::JobBuilder::new()
+        .add_buffer(&main_input, 0, 0)
+        .add_shader(&shader)
+        .add_dispatch(((DATA_LEN / 64) as u32, 1, 1))
+        .build(vulkan.clone());
+
+    thread::scope(|scope| {
+        // Each worker prepares, records and submits its own job.
+        let workers: Vec<_> = inputs
+            .iter()
+            .map(|input| {
+                let vulkan = vulkan.clone();
+                let shader = &shader;
+                scope.spawn(move || {
+                    let mut job = high::job::JobBuilder::new()
+                        .add_buffer(input, 0, 0)
+                        .add_shader(shader)
+                        .add_dispatch(((DATA_LEN / 64) as u32, 1, 1))
+                        .build(vulkan);
+                    job.execute();
+                    while job.status() == high::job::JobStatus::EXECUTING {
+                        job.wait_until_idle(1000 * 1000 * 1000);
+                    }
+                    job.get_output()
+                        .expect("A worker job failed.")
+                        .swap_remove(0)
+                })
+            })
+            .collect();
+
+        let main_worker = scope.spawn(|| {
+            main_job.execute();
+            while main_job.status() == high::job::JobStatus::EXECUTING {
+                main_job.wait_until_idle(1000 * 1000 * 1000);
+            }
+        });
+
+        for (input, worker) in inputs.iter().zip(workers) {
+            let output = worker.join().unwrap();
+            for i in 0..DATA_LEN {
+                assert_eq!(input[i] * 2.0, output[i], "Wrong value at index {}.", i);
+            }
+        }
+        main_worker.join().unwrap();
+    });
+
+    let main_output = main_job.get_output().expect("The main job failed.");
+    for i in 0..DATA_LEN {
+        assert_eq!(main_input[i] * 2.0, main_output[0][i]);
+    }
+
+    println!(
+        "{} jobs were prepared and submitted from {} threads on the same device.",
+        WORKERS + 1,
+        WORKERS + 1
+    );
+}
@@ -0,0 +1,55 @@
+extern crate wyzoid;
+use ash::vk;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wyzoid::high::job::{JobBuilder, JOB_DONE};
+use wyzoid::{high, utils};
+
+const DATA_LEN: usize = 64 * 1024;
+
+fn main() {
+    let input = utils::rand_vec::<f32>(DATA_LEN, 0.0, 1.0);
+
+    // A shader that multiply its input by two, with a local work size of 64.
+    let shader = PathBuf::from("examples/shaders/bin/examples/double.cs.spirv");
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+    let data = high::Buffer::from_slice(vulkan.clone(), &input);
+
+    let mut first = JobBuilder::<f32>::new()
+        .add_device_buffer(&data, 0, 0)
+        .add_shader(&shader)
+        .add_dispatch(((DATA_LEN / 64) as u32, 1, 1))
+        .build(vulkan.clone());
+    let mut second = JobBuilder::<f32>::new()
+        .add_device_buffer(&data, 0, 0)
+        .add_shader(&shader)
+        .add_dispatch(((DATA_LEN / 64) as u32, 1, 1))
+        .build(vulkan);
+
+    // The second job is submitted first, but the GPU only starts it once the first one is done.
+    second.after(&first);
+    second.execute();
+    first.execute();
+
+    // We wait on the timeline semaphore of the last job rather than on its fence.
+    let done = second
+        .semaphore()
+        .expect("The device does not support timeline semaphores.");
+    loop {
+        match done.wait(JOB_DONE, 1000 * 1000 * 1000) {
+            vk::Result::SUCCESS => break,
+            vk::Result::TIMEOUT => {}
+            err => panic!("Could not wait for the jobs: {}.", err),
+        }
+    }
+
+    let output = data.read();
+    for i in 0..DATA_LEN {
+        assert_eq!(input[i] * 4.0, output[i], "Wrong value at index {}.", i);
+    }
+
+    println!("First job timings:\n{}", first.get_timing());
+    println!("Second job timings:\n{}", second.get_timing());
+    println!("Both jobs ran in order.");
+}
@@ -0,0 +1,64 @@
+extern crate wyzoid;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wyzoid::high::job::{JobBuilder, JobStatus};
+use wyzoid::low::vkstate::{init_vulkan_with_queues, QueueConfig};
+use wyzoid::{high, utils};
+
+const DATA_LEN: usize = 4 * 1024 * 1024;
+
+fn main() {
+    let first = utils::rand_vec::<f32>(DATA_LEN, 0.0, 1.0);
+    let second = utils::rand_vec::<f32>(DATA_LEN, -1.0, 0.0);
+
+    // A shader that multiply its input by two, with a local work size of 64.
+    let shader = PathBuf::from("examples/shaders/bin/examples/double.cs.spirv");
+
+    let vulkan = Arc::new(init_vulkan_with_queues(QueueConfig {
+        compute_queues: 2,
+        transfer_queue: true,
+    }));
+    println!(
+        "{} compute queue(s), dedicated transfer queue: {}",
+        vulkan.compute_queues.len(),
+        vulkan.transfer_queue.is_some()
+    );
+
+    let mut first_buffer = high::Buffer::device_local(vulkan.clone(), DATA_LEN);
+    let mut second_buffer = high::Buffer::device_local(vulkan.clone(), DATA_LEN);
+    first_buffer.upload(&first).wait();
+
+    // The second upload goes through the transfer queue while the first job runs.
+    let mut first_job = JobBuilder::<f32>::new()
+        .add_device_buffer(&first_buffer, 0, 0)
+        .add_shader(&shader)
+        .add_dispatch(((DATA_LEN / 64) as u32, 1, 1))
+        .on_queue(vulkan.compute_queues.len() - 1)
+        .build(vulkan.clone());
+    first_job.execute();
+    second_buffer.upload(&second).wait();
+
+    let mut second_job = JobBuilder::<f32>::new()
+        .add_device_buffer(&second_buffer, 0, 0)
+        .add_shader(&shader)
+        .add_dispatch(((DATA_LEN / 64) as u32, 1, 1))
+        .build(vulkan);
+    second_job.execute();
+
+    // The first result is downloaded while the second job runs.
+    while first_job.status() == JobStatus::EXECUTING {
+        first_job.wait_until_idle(1000 * 1000 * 1000);
+    }
+    let first_output = first_buffer.download().wait();
+    while second_job.status() == JobStatus::EXECUTING {
+        second_job.wait_until_idle(1000 * 1000 * 1000);
+    }
+    let second_output = second_buffer.download().wait();
+
+    for (input, output) in [(&first, &first_output), (&second, &second_output)] {
+        for i in 0..DATA_LEN {
+            assert_eq!(input[i] * 2.0, output[i], "Wrong value at index {}.", i);
+        }
+    }
+    println!("Both jobs are correct.");
+}
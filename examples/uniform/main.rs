@@ -0,0 +1,60 @@
+extern crate wyzoid;
+use ash::vk;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wyzoid::utils::compare::{compare_buffers, Tolerance};
+use wyzoid::{high, utils};
+
+const DATA_LEN: usize = 1000;
+
+/// The std140 parameter block of the shader.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Params {
+    scale: f32,
+    offset: f32,
+    count: u32,
+}
+
+/**
+ * Read texels from a uniform texel buffer, write them to a storage texel buffer,
+ * with parameters from a uniform block.
+*/
+fn main() {
+    let input = utils::rand_vec::<f32>(DATA_LEN, -1.0, 1.0);
+    let output: Vec<f32> = vec![0.0; DATA_LEN];
+    let params = Params {
+        scale: 3.0,
+        offset: 0.5,
+        count: DATA_LEN as u32,
+    };
+    let shader = PathBuf::from("examples/shaders/bin/examples/affine_texel.cs.spirv");
+
+    let vulkan = Arc::new(wyzoid::low::vkstate::init_vulkan());
+    let mut job = high::job::JobBuilder::new()
+        .add_uniform_texel_buffer(&input, vk::Format::R32_SFLOAT, 0, 0)
+        .add_texel_buffer(&output, vk::Format::R32_SFLOAT, 0, 1)
+        .add_uniform(&params, 0, 2)
+        .add_shader(&shader)
+        .dispatch_for(DATA_LEN as u32, 1, 1)
+        .build(vulkan);
+    job.execute();
+    while job.status() == wyzoid::high::job::JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+    // The uniform block is not part of the output.
+    let shader_output = job.get_output().unwrap();
+    assert_eq!(shader_output.len(), 2);
+
+    let expected: Vec<f32> = input
+        .iter()
+        .map(|x| x * params.scale + params.offset)
+        .collect();
+    // The GPU may fuse the multiply and the add, which rounds once instead of twice.
+    // Near 0 the results cancel, so the error is bounded in absolute terms there.
+    let tolerance = Tolerance::abs(1e-6).or_ulps(1);
+    let report = compare_buffers(&expected, &shader_output[1], tolerance);
+    print!("{}", report);
+    report.assert();
+    println!("{} texels transformed.", DATA_LEN);
+}
@@ -0,0 +1,61 @@
+#version 450
+
+// Histogram of `n` floats in `bins` bins of the same width over [low, high].
+// Values equal to `high` go in the last bin, values out of the range and NaN
+// are ignored. When the bins fit in shared memory, each work group counts in
+// shared memory and merges its counts once in the global histogram, otherwise
+// every value is counted directly in the global histogram.
+// Each invocation goes through the input strided by the whole dispatch.
+
+const uint THREADS = 256;
+const uint SHARED_BINS = 4096;
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer Data { float data[]; };
+layout(std430, set = 0, binding = 1) buffer Counts { uint counts[]; };
+
+layout(push_constant) uniform Params {
+  uint n;
+  uint bins;
+  float low;
+  float high;
+} params;
+
+shared uint s_counts[SHARED_BINS];
+
+void main() {
+  uint t = gl_LocalInvocationID.x;
+  uint stride = gl_NumWorkGroups.x * THREADS;
+  bool local_counts = params.bins <= SHARED_BINS;
+
+  if (local_counts) {
+    for (uint b = t; b < params.bins; b += THREADS) {
+      s_counts[b] = 0u;
+    }
+  }
+  barrier();
+
+  float scale = float(params.bins) / (params.high - params.low);
+  for (uint i = gl_GlobalInvocationID.x; i < params.n; i += stride) {
+    float value = data[i];
+    if (value >= params.low && value <= params.high) {
+      uint bin = min(uint((value - params.low) * scale), params.bins - 1u);
+      if (local_counts) {
+        atomicAdd(s_counts[bin], 1u);
+      } else {
+        atomicAdd(counts[bin], 1u);
+      }
+    }
+  }
+  barrier();
+
+  if (local_counts) {
+    for (uint b = t; b < params.bins; b += THREADS) {
+      uint count = s_counts[b];
+      if (count != 0u) {
+        atomicAdd(counts[b], count);
+      }
+    }
+  }
+}
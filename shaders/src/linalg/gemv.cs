@@ -0,0 +1,54 @@
+#version 450
+
+// y = alpha * A * x + beta * y with a row-major `rows` x `cols` matrix A.
+// Each work group computes one element of y, its invocations summing parts
+// of the row before a reduction in shared memory. Rows are numbered over a
+// 2D dispatch to go past the work group count limit.
+// When beta is 0, y is not read so it does not need to be initialized.
+
+const uint THREADS = 256;
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer A { float a[]; };
+layout(std430, set = 0, binding = 1) buffer X { float x[]; };
+layout(std430, set = 0, binding = 2) buffer Y { float y[]; };
+
+layout(push_constant) uniform Params {
+  uint rows;
+  uint cols;
+  float alpha;
+  float beta;
+} params;
+
+shared float s_sums[THREADS];
+
+void main() {
+  uint t = gl_LocalInvocationID.x;
+  uint row = gl_WorkGroupID.y * gl_NumWorkGroups.x + gl_WorkGroupID.x;
+  if (row >= params.rows) {
+    return;
+  }
+
+  float sum = 0.0;
+  for (uint col = t; col < params.cols; col += THREADS) {
+    sum += a[row * params.cols + col] * x[col];
+  }
+  s_sums[t] = sum;
+  barrier();
+
+  for (uint s = THREADS / 2u; s > 0u; s = s / 2u) {
+    if (t < s) {
+      s_sums[t] += s_sums[t + s];
+    }
+    barrier();
+  }
+
+  if (t == 0u) {
+    float value = params.alpha * s_sums[0];
+    if (params.beta != 0.0) {
+      value += params.beta * y[row];
+    }
+    y[row] = value;
+  }
+}
@@ -0,0 +1,94 @@
+#version 450
+
+// C = alpha * A * B + beta * C with row-major A (m x k), B (k x n) and C (m x n).
+// Each work group computes a TILE x TILE block of C, going through k by steps
+// of TILE_K with the needed parts of A and B in shared memory. Each invocation
+// computes WORK x WORK elements of the block, strided by the work group size
+// so neighbour invocations read neighbour shared memory values.
+// Out of range elements are loaded as 0 and never stored.
+// When beta is 0, C is not read so it does not need to be initialized.
+
+const uint GROUP = 16;
+const uint TILE = 64;
+const uint TILE_K = 16;
+const uint WORK = 4;
+
+layout(local_size_x = 16, local_size_y = 16, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer A { float a[]; };
+layout(std430, set = 0, binding = 1) buffer B { float b[]; };
+layout(std430, set = 0, binding = 2) buffer C { float c[]; };
+
+layout(push_constant) uniform Params {
+  uint m;
+  uint n;
+  uint k;
+  float alpha;
+  float beta;
+} params;
+
+shared float s_a[TILE_K][TILE];
+shared float s_b[TILE_K][TILE];
+
+void main() {
+  uint tx = gl_LocalInvocationID.x;
+  uint ty = gl_LocalInvocationID.y;
+  uint lid = ty * GROUP + tx;
+  uint row0 = gl_WorkGroupID.y * TILE;
+  uint col0 = gl_WorkGroupID.x * TILE;
+
+  float acc[WORK][WORK];
+  for (uint i = 0u; i < WORK; i++) {
+    for (uint j = 0u; j < WORK; j++) {
+      acc[i][j] = 0.0;
+    }
+  }
+
+  for (uint k0 = 0u; k0 < params.k; k0 += TILE_K) {
+    for (uint e = lid; e < TILE * TILE_K; e += GROUP * GROUP) {
+      uint r = e / TILE_K;
+      uint kk = e % TILE_K;
+      uint gr = row0 + r;
+      uint gk = k0 + kk;
+      s_a[kk][r] = (gr < params.m && gk < params.k) ? a[gr * params.k + gk] : 0.0;
+    }
+    for (uint e = lid; e < TILE * TILE_K; e += GROUP * GROUP) {
+      uint kk = e / TILE;
+      uint cc = e % TILE;
+      uint gk = k0 + kk;
+      uint gc = col0 + cc;
+      s_b[kk][cc] = (gk < params.k && gc < params.n) ? b[gk * params.n + gc] : 0.0;
+    }
+    barrier();
+
+    for (uint kk = 0u; kk < TILE_K; kk++) {
+      float ra[WORK];
+      float rb[WORK];
+      for (uint i = 0u; i < WORK; i++) {
+        ra[i] = s_a[kk][ty + i * GROUP];
+        rb[i] = s_b[kk][tx + i * GROUP];
+      }
+      for (uint i = 0u; i < WORK; i++) {
+        for (uint j = 0u; j < WORK; j++) {
+          acc[i][j] += ra[i] * rb[j];
+        }
+      }
+    }
+    barrier();
+  }
+
+  for (uint i = 0u; i < WORK; i++) {
+    uint r = row0 + ty + i * GROUP;
+    for (uint j = 0u; j < WORK; j++) {
+      uint col = col0 + tx + j * GROUP;
+      if (r < params.m && col < params.n) {
+        uint idx = r * params.n + col;
+        float value = params.alpha * acc[i][j];
+        if (params.beta != 0.0) {
+          value += params.beta * c[idx];
+        }
+        c[idx] = value;
+      }
+    }
+  }
+}
@@ -0,0 +1,43 @@
+#version 450
+
+// Write the transpose of the row-major `rows` x `cols` matrix `src` in `dst`.
+// Each work group goes through a TILE x TILE block in shared memory so that
+// both the reads and the writes are coalesced. The extra column of the tile
+// avoids shared memory bank conflicts when reading it by column.
+
+const uint TILE = 32;
+const uint ROWS = 8;
+
+layout(local_size_x = 32, local_size_y = 8, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer Src { float src[]; };
+layout(std430, set = 0, binding = 1) buffer Dst { float dst[]; };
+
+layout(push_constant) uniform Params {
+  uint rows;
+  uint cols;
+} params;
+
+shared float tile[TILE][TILE + 1];
+
+void main() {
+  uint tx = gl_LocalInvocationID.x;
+  uint ty = gl_LocalInvocationID.y;
+
+  uint x = gl_WorkGroupID.x * TILE + tx;
+  uint y = gl_WorkGroupID.y * TILE + ty;
+  for (uint j = 0u; j < TILE; j += ROWS) {
+    if (x < params.cols && y + j < params.rows) {
+      tile[ty + j][tx] = src[(y + j) * params.cols + x];
+    }
+  }
+  barrier();
+
+  x = gl_WorkGroupID.y * TILE + tx;
+  y = gl_WorkGroupID.x * TILE + ty;
+  for (uint j = 0u; j < TILE; j += ROWS) {
+    if (x < params.rows && y + j < params.cols) {
+      dst[(y + j) * params.rows + x] = tile[tx][ty + j];
+    }
+  }
+}
@@ -0,0 +1,121 @@
+#version 450
+
+#define T float
+#define T_ZERO 0.0
+#define T_ONE 1.0
+#define T_MAX uintBitsToFloat(0x7F800000u)
+#define T_LOWEST uintBitsToFloat(0xFF800000u)
+
+// Reduce `n` elements of `src` into one element per work group, written in
+// the other partial buffer. Each invocation first accumulates elements
+// strided by the whole dispatch, so a single pass can cover any length.
+// src: 0 = data, 1 = partial A, 2 = partial B. Partial A is written when
+// reading data or partial B, partial B when reading partial A.
+// op: 0 = sum, 1 = product, 2 = min, 3 = max, 4 = argmin, 5 = argmax.
+// Indices are only meaningful for argmin/argmax, ties keep the lowest index.
+// NaN comes after every number for min/max/argmin/argmax, so it is only the
+// result when all the elements are NaN. The sum and product propagate it.
+// An index of NONE marks the identity, which any element replaces.
+
+const uint THREADS = 256;
+const uint NONE = 0xFFFFFFFFu;
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer Data { T data[]; };
+layout(std430, set = 0, binding = 1) buffer PartialA { T partial_a[]; };
+layout(std430, set = 0, binding = 2) buffer PartialB { T partial_b[]; };
+layout(std430, set = 0, binding = 3) buffer IndexA { uint index_a[]; };
+layout(std430, set = 0, binding = 4) buffer IndexB { uint index_b[]; };
+
+layout(push_constant) uniform Params {
+  uint n;
+  uint src;
+  uint op;
+} params;
+
+shared T s_values[THREADS];
+shared uint s_indices[THREADS];
+
+T identity() {
+  if (params.op == 0u) {
+    return T_ZERO;
+  } else if (params.op == 1u) {
+    return T_ONE;
+  } else if (params.op == 2u || params.op == 4u) {
+    return T_MAX;
+  }
+  return T_LOWEST;
+}
+
+// Whether `a` comes before `b`: smaller when `smallest`, bigger otherwise,
+// and NaN last.
+bool before(T a, T b, bool smallest) {
+  if (isnan(a)) {
+    return false;
+  } else if (isnan(b)) {
+    return true;
+  }
+  return smallest ? a < b : a > b;
+}
+
+bool same(T a, T b) {
+  return a == b || (isnan(a) && isnan(b));
+}
+
+void combine(inout T value, inout uint index, T other, uint other_index) {
+  if (params.op == 0u) {
+    value = value + other;
+  } else if (params.op == 1u) {
+    value = value * other;
+  } else if (other_index != NONE) {
+    bool smallest = params.op == 2u || params.op == 4u;
+    if (index == NONE || before(other, value, smallest) ||
+        (same(other, value) && other_index < index)) {
+      value = other;
+      index = other_index;
+    }
+  }
+}
+
+void main() {
+  uint t = gl_LocalInvocationID.x;
+  uint stride = gl_NumWorkGroups.x * THREADS;
+
+  T value = identity();
+  uint index = NONE;
+  for (uint g = gl_GlobalInvocationID.x; g < params.n; g += stride) {
+    if (params.src == 0u) {
+      combine(value, index, data[g], g);
+    } else if (params.src == 1u) {
+      combine(value, index, partial_a[g], index_a[g]);
+    } else {
+      combine(value, index, partial_b[g], index_b[g]);
+    }
+  }
+  s_values[t] = value;
+  s_indices[t] = index;
+  barrier();
+
+  for (uint s = THREADS / 2u; s > 0u; s = s / 2u) {
+    if (t < s) {
+      T v = s_values[t];
+      uint i = s_indices[t];
+      combine(v, i, s_values[t + s], s_indices[t + s]);
+      s_values[t] = v;
+      s_indices[t] = i;
+    }
+    barrier();
+  }
+
+  if (t == 0u) {
+    uint group = gl_WorkGroupID.x;
+    if (params.src == 1u) {
+      partial_b[group] = s_values[0];
+      index_b[group] = s_indices[0];
+    } else {
+      partial_a[group] = s_values[0];
+      index_a[group] = s_indices[0];
+    }
+  }
+}
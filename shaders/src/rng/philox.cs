@@ -0,0 +1,169 @@
+#version 450
+
+// Fill a buffer with the Philox4x32-10 counter-based generator (Salmon et al.,
+// "Parallel random numbers: as easy as 1, 2, 3"). Element `n` of the stream is
+// lane `n % 4` of the block of counter `n / 4`, so it does not depend on the
+// dispatch, and `high::rng` computes the same values on the CPU.
+// Each invocation computes one block, the first one skipping `skip` lanes.
+// The floats only go through additions and multiplications, which are correctly
+// rounded and kept from being fused by `precise`, so they match the CPU bit
+// for bit: the normal distribution uses its own logarithm, square root, sine and
+// cosine instead of the builtins, whose precision depends on the driver.
+
+const uint THREADS = 256;
+
+const uint BITS = 0;
+const uint RANGE = 1;
+const uint UNIFORM = 2;
+const uint NORMAL = 3;
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer Data { uint data[]; };
+
+layout(push_constant) uniform Params {
+  uint key_lo;
+  uint key_hi;
+  uint block_lo;
+  uint block_hi;
+  uint skip;
+  uint len;
+  uint distribution;
+  // low and high - low for RANGE and UNIFORM, mean and standard deviation for
+  // NORMAL, floats are given as bits.
+  uint a;
+  uint b;
+} params;
+
+uvec4 philox(uvec4 ctr, uvec2 key) {
+  for (uint i = 0; i < 10; i++) {
+    if (i > 0) {
+      key.x += 0x9E3779B9u;
+      key.y += 0xBB67AE85u;
+    }
+    uint hi0, lo0, hi1, lo1;
+    umulExtended(0xD2511F53u, ctr.x, hi0, lo0);
+    umulExtended(0xCD9E8D57u, ctr.z, hi1, lo1);
+    ctr = uvec4(hi1 ^ ctr.y ^ key.x, lo1, hi0 ^ ctr.w ^ key.y, lo0);
+  }
+  return ctr;
+}
+
+// Natural logarithm of x in (0, 1], from the Cephes logf.
+float log_unit(float x) {
+  uint bits = floatBitsToUint(x);
+  int e = int(bits >> 23) - 126;
+  precise float m = uintBitsToFloat((bits & 0x007FFFFFu) | 0x3F000000u);
+  if (m < uintBitsToFloat(0x3F3504F3u)) {
+    e -= 1;
+    m = m + m - 1.0;
+  } else {
+    m = m - 1.0;
+  }
+  precise float z = m * m;
+  precise float y = uintBitsToFloat(0x3D9021BBu);
+  y = y * m + uintBitsToFloat(0xBDEBD1B8u);
+  y = y * m + uintBitsToFloat(0x3DEF251Au);
+  y = y * m + uintBitsToFloat(0xBDFE5D4Fu);
+  y = y * m + uintBitsToFloat(0x3E11E9BFu);
+  y = y * m + uintBitsToFloat(0xBE2AAE50u);
+  y = y * m + uintBitsToFloat(0x3E4CCEACu);
+  y = y * m + uintBitsToFloat(0xBE7FFFFCu);
+  y = y * m + uintBitsToFloat(0x3EAAAAAAu);
+  y = y * m * z;
+  precise float fe = float(e);
+  y = y + uintBitsToFloat(0xB95E8083u) * fe;
+  y = y - 0.5 * z;
+  precise float result = m + y;
+  result = result + uintBitsToFloat(0x3F318000u) * fe;
+  return result;
+}
+
+// Square root of x >= 0, from Newton iterations on its inverse.
+float sqrt_positive(float x) {
+  if (x == 0.0) {
+    return 0.0;
+  }
+  precise float y = uintBitsToFloat(0x5F3759DFu - (floatBitsToUint(x) >> 1));
+  for (uint i = 0; i < 3; i++) {
+    y = y * (1.5 - ((0.5 * x) * y) * y);
+  }
+  precise float result = x * y;
+  return result;
+}
+
+// Sine and cosine of 2 pi (quadrant + r) / 4, with r in [0, 1), from their
+// Taylor series on [0, pi / 2).
+vec2 sin_cos_turn(uint quadrant, float r) {
+  precise float t = r * uintBitsToFloat(0x3FC90FDBu);
+  precise float t2 = t * t;
+  precise float s = uintBitsToFloat(0x2F309231u);
+  s = s * t2 + uintBitsToFloat(0xB2D7322Bu);
+  s = s * t2 + uintBitsToFloat(0x3638EF1Du);
+  s = s * t2 + uintBitsToFloat(0xB9500D01u);
+  s = s * t2 + uintBitsToFloat(0x3C088889u);
+  s = s * t2 + uintBitsToFloat(0xBE2AAAABu);
+  s = (s * t2 + 1.0) * t;
+  precise float c = uintBitsToFloat(0xAD49CBA5u);
+  c = c * t2 + uintBitsToFloat(0x310F76C7u);
+  c = c * t2 + uintBitsToFloat(0xB493F27Eu);
+  c = c * t2 + uintBitsToFloat(0x37D00D01u);
+  c = c * t2 + uintBitsToFloat(0xBAB60B61u);
+  c = c * t2 + uintBitsToFloat(0x3D2AAAABu);
+  c = c * t2 - 0.5;
+  c = c * t2 + 1.0;
+  switch (quadrant) {
+    case 0: return vec2(s, c);
+    case 1: return vec2(c, -s);
+    case 2: return vec2(-s, -c);
+    default: return vec2(-c, s);
+  }
+}
+
+// Two normal values from two random words, with the Box-Muller transform.
+vec2 box_muller(uint x, uint y) {
+  // (0, 1], which has a logarithm.
+  precise float u = float((x >> 8) + 1) * uintBitsToFloat(0x33800000u);
+  precise float r2 = -2.0 * log_unit(u);
+  precise float radius = sqrt_positive(r2);
+  vec2 sc = sin_cos_turn(y >> 30, float((y >> 8) & 0x003FFFFFu) * uintBitsToFloat(0x34800000u));
+  precise vec2 result = vec2(radius * sc.y, radius * sc.x);
+  return result;
+}
+
+uint value(uvec4 block, uint lane) {
+  uint x = block[lane];
+  if (params.distribution == BITS) {
+    return x;
+  } else if (params.distribution == RANGE) {
+    uint hi, lo;
+    umulExtended(x, params.b, hi, lo);
+    return params.a + hi;
+  } else if (params.distribution == UNIFORM) {
+    precise float u = float(x >> 8) * uintBitsToFloat(0x33800000u);
+    precise float result = uintBitsToFloat(params.a) + u * uintBitsToFloat(params.b);
+    return floatBitsToUint(result);
+  } else {
+    uint pair = lane & 2u;
+    vec2 z = box_muller(block[pair], block[pair + 1]);
+    precise float result = uintBitsToFloat(params.a) + uintBitsToFloat(params.b) * z[lane & 1u];
+    return floatBitsToUint(result);
+  }
+}
+
+void main() {
+  uint index = gl_GlobalInvocationID.y * gl_NumWorkGroups.x * THREADS + gl_GlobalInvocationID.x;
+  uint first = index * 4;
+  if (first >= params.skip + params.len) {
+    return;
+  }
+  uint block_lo = params.block_lo + index;
+  uint block_hi = params.block_hi + (block_lo < index ? 1 : 0);
+  uvec4 block = philox(uvec4(block_lo, block_hi, 0, 0), uvec2(params.key_lo, params.key_hi));
+  for (uint lane = 0; lane < 4; lane++) {
+    uint n = first + lane;
+    if (n >= params.skip && n - params.skip < params.len) {
+      data[n - params.skip] = value(block, lane);
+    }
+  }
+}
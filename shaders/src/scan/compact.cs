@@ -0,0 +1,26 @@
+#version 450
+
+// Scatter the values whose flag is set at the position given by the
+// inclusive scan of the flags. Values are moved as raw 32 bits words.
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer Flags { uint flags[]; };
+layout(std430, set = 0, binding = 1) buffer Scanned { uint scanned[]; };
+layout(std430, set = 0, binding = 3) buffer Values { uint values[]; };
+layout(std430, set = 0, binding = 4) buffer Compacted { uint compacted[]; };
+
+layout(push_constant) uniform Params {
+  uint n;
+} params;
+
+void main() {
+  uint i = (gl_WorkGroupID.y * gl_NumWorkGroups.x + gl_WorkGroupID.x) * 256u + gl_LocalInvocationID.x;
+  if (i >= params.n) {
+    return;
+  }
+
+  if (flags[i] != 0u) {
+    compacted[scanned[i] - 1u] = values[i];
+  }
+}
@@ -0,0 +1,120 @@
+#version 450
+
+#define T int
+#define T_ZERO 0
+
+// Multi-level prefix sum, each work group handling a block of BLOCK elements.
+// mode 0: inclusive scan of each block of a level, in place, and write the
+//         total of each block in the sums buffer, at `out_offset`.
+//         Level 0 reads `data` and writes `scanned`, the other levels live in
+//         the sums buffer, at `in_offset`.
+// mode 1: add the scanned total of the previous blocks (found in the next
+//         level, at `out_offset`) to each element of a level.
+// mode 2: turn the inclusive scan in `scanned` into an exclusive scan in `data`.
+// Blocks are numbered over a 2D dispatch to go past the work group count limit.
+
+const uint THREADS = 256;
+const uint BLOCK = 512;
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer Data { T data[]; };
+layout(std430, set = 0, binding = 1) buffer Scanned { T scanned[]; };
+layout(std430, set = 0, binding = 2) buffer Sums { T sums[]; };
+
+layout(push_constant) uniform Params {
+  uint n;
+  uint mode;
+  uint level;
+  uint in_offset;
+  uint out_offset;
+} params;
+
+shared T s_pairs[THREADS];
+
+T load(uint i) {
+  if (i >= params.n) {
+    return T_ZERO;
+  }
+  if (params.level == 0u) {
+    return data[i];
+  }
+  return sums[params.in_offset + i];
+}
+
+void store(uint i, T value) {
+  if (i >= params.n) {
+    return;
+  }
+  if (params.level == 0u) {
+    scanned[i] = value;
+  } else {
+    sums[params.in_offset + i] = value;
+  }
+}
+
+T load_scanned(uint i) {
+  if (params.level == 0u) {
+    return scanned[i];
+  }
+  return sums[params.in_offset + i];
+}
+
+void main() {
+  uint t = gl_LocalInvocationID.x;
+  uint block = gl_WorkGroupID.y * gl_NumWorkGroups.x + gl_WorkGroupID.x;
+  uint base = block * BLOCK;
+  if (base >= params.n) {
+    return;
+  }
+
+  if (params.mode == 2u) {
+    for (uint e = t; e < BLOCK; e += THREADS) {
+      uint i = base + e;
+      if (i < params.n) {
+        data[i] = i == 0u ? T_ZERO : scanned[i - 1u];
+      }
+    }
+    return;
+  }
+
+  if (params.mode == 1u) {
+    if (block == 0u) {
+      return;
+    }
+    T offset = sums[params.out_offset + block - 1u];
+    for (uint e = t; e < BLOCK; e += THREADS) {
+      uint i = base + e;
+      if (i < params.n) {
+        store(i, load_scanned(i) + offset);
+      }
+    }
+    return;
+  }
+
+  T a = load(base + 2u * t);
+  T b = load(base + 2u * t + 1u);
+  s_pairs[t] = a + b;
+  barrier();
+
+  for (uint offset = 1u; offset < THREADS; offset = offset * 2u) {
+    T value = T_ZERO;
+    if (t >= offset) {
+      value = s_pairs[t - offset];
+    }
+    barrier();
+    s_pairs[t] = s_pairs[t] + value;
+    barrier();
+  }
+
+  T before = T_ZERO;
+  if (t > 0u) {
+    before = s_pairs[t - 1u];
+  }
+  store(base + 2u * t, before + a);
+  store(base + 2u * t + 1u, before + a + b);
+
+  if (t == THREADS - 1u) {
+    sums[params.out_offset + block] = s_pairs[t];
+  }
+}
@@ -0,0 +1,45 @@
+#version 450
+
+// One step (k, j) of the bitonic sort, for the steps whose distance `j` is
+// too big to be done inside a work group. Each invocation handles one pair.
+// When j == k / 2, the element is compared with its mirror in the block of
+// size k, otherwise with the element j further. The smallest key always goes
+// to the lowest index, so pairs reaching past `n` are left untouched.
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer Keys { uint keys[]; };
+layout(std430, set = 0, binding = 1) buffer Values { uint values[]; };
+
+layout(push_constant) uniform Params {
+  uint n;
+  uint k;
+  uint j;
+  uint has_values;
+} params;
+
+void main() {
+  uint t = gl_GlobalInvocationID.x;
+  uint j = params.j;
+  uint a = (t / j) * 2u * j + (t % j);
+  uint b = a + j;
+  if (j == params.k / 2u) {
+    b = (t / j) * params.k + (params.k - 1u - (t % j));
+  }
+
+  if (b >= params.n) {
+    return;
+  }
+
+  uint key_a = keys[a];
+  uint key_b = keys[b];
+  if (key_a > key_b) {
+    keys[a] = key_b;
+    keys[b] = key_a;
+    if (params.has_values != 0u) {
+      uint value_a = values[a];
+      values[a] = values[b];
+      values[b] = value_a;
+    }
+  }
+}
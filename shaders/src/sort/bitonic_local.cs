@@ -0,0 +1,88 @@
+#version 450
+
+// Bitonic sort of the keys inside blocks of BLOCK elements, in shared memory.
+// When `k` is 0, each block is fully sorted (every stage up to k = BLOCK).
+// Otherwise, only the steps j < BLOCK of the merge stage `k` are done, the
+// bigger steps having been done by bitonic_global.
+// Every comparison puts the smallest key at the lowest index, so the elements
+// past `n` behave like +inf and never have to be read nor written.
+
+const uint THREADS = 256;
+const uint BLOCK = 512;
+
+layout(local_size_x = 256, local_size_y = 1, local_size_z = 1) in;
+
+layout(std430, set = 0, binding = 0) buffer Keys { uint keys[]; };
+layout(std430, set = 0, binding = 1) buffer Values { uint values[]; };
+
+layout(push_constant) uniform Params {
+  uint n;
+  uint k;
+  uint j;
+  uint has_values;
+} params;
+
+shared uint s_keys[BLOCK];
+shared uint s_values[BLOCK];
+
+void compare_exchange(uint a, uint b) {
+  uint key_a = s_keys[a];
+  uint key_b = s_keys[b];
+  if (key_a > key_b) {
+    s_keys[a] = key_b;
+    s_keys[b] = key_a;
+    uint value_a = s_values[a];
+    s_values[a] = s_values[b];
+    s_values[b] = value_a;
+  }
+}
+
+void main() {
+  uint t = gl_LocalInvocationID.x;
+  uint base = gl_WorkGroupID.x * BLOCK;
+
+  for (uint e = t; e < BLOCK; e += THREADS) {
+    uint g = base + e;
+    if (g < params.n) {
+      s_keys[e] = keys[g];
+      s_values[e] = params.has_values != 0u ? values[g] : 0u;
+    } else {
+      s_keys[e] = 0xFFFFFFFFu;
+      s_values[e] = 0u;
+    }
+  }
+  barrier();
+
+  if (params.k == 0u) {
+    for (uint k = 2u; k <= BLOCK; k = k * 2u) {
+      // First step of a stage: compare with the mirrored element of the block.
+      uint half_k = k / 2u;
+      uint a = (t / half_k) * k + (t % half_k);
+      uint b = (t / half_k) * k + (k - 1u - (t % half_k));
+      compare_exchange(a, b);
+      barrier();
+
+      for (uint j = half_k / 2u; j > 0u; j = j / 2u) {
+        uint a = (t / j) * 2u * j + (t % j);
+        compare_exchange(a, a + j);
+        barrier();
+      }
+    }
+  } else {
+    for (uint j = BLOCK / 2u; j > 0u; j = j / 2u) {
+      uint a = (t / j) * 2u * j + (t % j);
+      compare_exchange(a, a + j);
+      barrier();
+    }
+  }
+
+  for (uint e = t; e < BLOCK; e += THREADS) {
+    uint g = base + e;
+    if (g < params.n) {
+      keys[g] = s_keys[e];
+      if (params.has_values != 0u) {
+        values[g] = s_values[e];
+      }
+    }
+  }
+}
@@ -0,0 +1,422 @@
+use crate::low::{vkcmd, vkfence, vkmem, vksemaphore, vkstate::VulkanState};
+use ash::vk;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// A buffer of `T` living in device memory, independently of any job.
+/// It can be bound to several jobs, so data produced by one job can be used
+/// by the next one without going through the host.
+pub struct Buffer<T> {
+    len: usize,
+    buffer: vkmem::VkBuffer,
+    memory: vkmem::VkMem,
+    /// The host can map the memory of the buffer, otherwise it goes through transfers.
+    host_visible: bool,
+    vulkan: Arc<VulkanState>,
+    element: PhantomData<T>,
+}
+
+impl<T: Copy> Buffer<T> {
+    /// Create a buffer of `len` elements without initializing its content.
+    pub fn uninit(vulkan: Arc<VulkanState>, len: usize) -> Buffer<T> {
+        Buffer::allocate(vulkan, len, false)
+    }
+
+    /// Create a buffer of `len` elements in device local memory, which is usually faster
+    /// for the shaders. Its content goes through `upload` and `download`, unless the host
+    /// can map that memory, as on integrated GPUs.
+    pub fn device_local(vulkan: Arc<VulkanState>, len: usize) -> Buffer<T> {
+        Buffer::allocate(vulkan, len, true)
+    }
+
+    fn allocate(vulkan: Arc<VulkanState>, len: usize, device_local: bool) -> Buffer<T> {
+        assert!(len > 0, "[ERR] A buffer can not be empty.");
+        let size = (len * std::mem::size_of::<T>()) as u64;
+        let mut buffer = vkmem::VkBuffer::new(vulkan.clone(), size);
+        let requirements = buffer.get_buffer_memory_requirements();
+        let host_flags =
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+        let flags = if device_local {
+            vk::MemoryPropertyFlags::DEVICE_LOCAL
+        } else {
+            host_flags
+        };
+        // The memory type may not have the flags we prefer, it is only host visible
+        // if the device says so, otherwise the buffer goes through transfers.
+        let memory = vkmem::VkMem::find_mem_for(vulkan.clone(), requirements, flags)
+            .expect("[ERR] Could not find a memory type fitting our need.");
+        assert!(
+            memory.size >= requirements.size,
+            "[ERR] The memory of the buffer is smaller than it requires."
+        );
+        let host_visible = memory.property_flags().contains(host_flags);
+        buffer.bind(memory.mem, 0);
+
+        Buffer {
+            len,
+            buffer,
+            memory,
+            host_visible,
+            vulkan,
+            element: PhantomData,
+        }
+    }
+
+    /// Create a buffer holding a copy of `data`.
+    pub fn from_slice(vulkan: Arc<VulkanState>, data: &[T]) -> Buffer<T> {
+        let mut buffer = Buffer::uninit(vulkan, data.len());
+        buffer.write(data);
+        buffer
+    }
+
+    /// Create a buffer of `len` elements all set to `value`.
+    pub fn filled(vulkan: Arc<VulkanState>, len: usize, value: T) -> Buffer<T> {
+        Buffer::from_slice(vulkan, &vec![value; len])
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Replace the content of the buffer with `data`.
+    /// No job using the buffer should be running.
+    pub fn write(&mut self, data: &[T]) {
+        assert_eq!(
+            data.len(),
+            self.len,
+            "[ERR] The data does not have the size of the buffer."
+        );
+        if self.host_visible {
+            self.memory.map_buffer(data, &self.buffer);
+        } else {
+            self.upload(data).wait();
+        }
+    }
+
+    /// Copy the content of the buffer to the host.
+    /// No job writing to the buffer should be running.
+    pub fn read(&self) -> Vec<T> {
+        if self.host_visible {
+            self.memory.get_buffer(&self.buffer)
+        } else {
+            self.download().wait()
+        }
+    }
+
+    /// Start copying `data` to the buffer through a staging buffer, on the dedicated
+    /// transfer queue when the vulkan state has one, so it can overlap with jobs running
+    /// on the compute queues. Jobs using the buffer on another compute queue than the
+    /// first one must be done.
+    pub fn upload(&mut self, data: &[T]) -> Upload<'_> {
+        assert_eq!(
+            data.len(),
+            self.len,
+            "[ERR] The data does not have the size of the buffer."
+        );
+        let transfer = Transfer::new(self.vulkan.clone(), &self.buffer, true, |staging, mem| {
+            mem.map_buffer(data, staging)
+        });
+        Upload {
+            transfer,
+            buffer: PhantomData,
+        }
+    }
+
+    /// Start copying the buffer to the host, the same way as `upload`.
+    pub fn download(&self) -> Download<'_, T> {
+        let transfer = Transfer::new(self.vulkan.clone(), &self.buffer, false, |_, _| {});
+        Download {
+            transfer,
+            buffer: PhantomData,
+        }
+    }
+}
+
+impl<T> Buffer<T> {
+    /// The underlying Vulkan buffer.
+    pub fn raw(&self) -> &vkmem::VkBuffer {
+        &self.buffer
+    }
+
+    pub fn vulkan(&self) -> Arc<VulkanState> {
+        self.vulkan.clone()
+    }
+}
+
+/// A copy between a buffer and a staging buffer the host can map.
+/// Out of a transfer, buffers belong to the compute queue family: with a dedicated transfer
+/// queue, the buffer is released by a compute queue, acquired by the transfer queue for the
+/// copy, then given back the same way.
+struct Transfer {
+    staging: vkmem::VkBuffer,
+    staging_memory: vkmem::VkMem,
+    _pools: Vec<vkcmd::VkCmdPool>,
+    _semaphores: Vec<vksemaphore::VkSemaphore>,
+    fence: vkfence::VkFence,
+}
+
+impl Transfer {
+    fn new(
+        vulkan: Arc<VulkanState>,
+        buffer: &vkmem::VkBuffer,
+        to_device: bool,
+        fill: impl FnOnce(&vkmem::VkBuffer, &vkmem::VkMem),
+    ) -> Transfer {
+        let mut staging = vkmem::VkBuffer::new(vulkan.clone(), buffer.size);
+        let host_flags =
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+        let staging_memory = vkmem::VkMem::find_mem_for(
+            vulkan.clone(),
+            staging.get_buffer_memory_requirements(),
+            host_flags,
+        )
+        .expect("[ERR] Could not find a memory type fitting our need.");
+        assert!(
+            staging_memory.property_flags().contains(host_flags),
+            "[ERR] Could not find a host visible memory type for the staging buffer."
+        );
+        staging.bind(staging_memory.mem, 0);
+        fill(&staging, &staging_memory);
+
+        let (src, dst) = if to_device {
+            (staging.buffer, buffer.buffer)
+        } else {
+            (buffer.buffer, staging.buffer)
+        };
+        let compute_family = vulkan.queue_family_index;
+        let fence = vkfence::VkFence::new(vulkan.clone(), false);
+        let barrier = |src_access, dst_access, src_family, dst_family| {
+            vk::BufferMemoryBarrier::builder()
+                .src_access_mask(src_access)
+                .dst_access_mask(dst_access)
+                .src_queue_family_index(src_family)
+                .dst_queue_family_index(dst_family)
+                .buffer(buffer.buffer)
+                .size(vk::WHOLE_SIZE)
+                .build()
+        };
+        // The host reads the staging buffer once the copy is done.
+        let staging_barrier = vk::BufferMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(staging.buffer)
+            .size(vk::WHOLE_SIZE)
+            .build();
+        let shader_access = vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE;
+        let transfer_access = vk::AccessFlags::TRANSFER_READ | vk::AccessFlags::TRANSFER_WRITE;
+
+        let transfer = match &vulkan.transfer_queue {
+            None => {
+                // Everything on the first compute queue, no ownership to transfer.
+                let mut pool = vkcmd::VkCmdPool::new(vulkan.clone());
+                let cmd = pool.create_cmd_buffer(vk::CommandBufferLevel::PRIMARY);
+                pool.begin_cmd(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, cmd);
+                pool.buffer_barriers(
+                    vk::PipelineStageFlags::COMPUTE_SHADER,
+                    vk::PipelineStageFlags::TRANSFER,
+                    &[barrier(
+                        vk::AccessFlags::SHADER_WRITE,
+                        transfer_access,
+                        vk::QUEUE_FAMILY_IGNORED,
+                        vk::QUEUE_FAMILY_IGNORED,
+                    )],
+                    cmd,
+                );
+                pool.copy_buffer(src, dst, buffer.size, cmd);
+                pool.buffer_barriers(
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::COMPUTE_SHADER | vk::PipelineStageFlags::HOST,
+                    &[
+                        barrier(
+                            vk::AccessFlags::TRANSFER_WRITE,
+                            shader_access,
+                            vk::QUEUE_FAMILY_IGNORED,
+                            vk::QUEUE_FAMILY_IGNORED,
+                        ),
+                        staging_barrier,
+                    ],
+                    cmd,
+                );
+                pool.end_cmd(cmd);
+                {
+                    let queue = vulkan.compute_queue(0).lock().unwrap();
+                    pool.submit(*queue, Some(fence.fence));
+                }
+                (vec![pool], Vec::new())
+            }
+            Some(transfer_queue) => {
+                let transfer_family = transfer_queue.family_index;
+                let released = vksemaphore::VkSemaphore::new(vulkan.clone());
+                let copied = vksemaphore::VkSemaphore::new(vulkan.clone());
+
+                // The compute family releases the buffer once the previous dispatches are done.
+                let mut release = vkcmd::VkCmdPool::new(vulkan.clone());
+                let cmd = release.create_cmd_buffer(vk::CommandBufferLevel::PRIMARY);
+                release.begin_cmd(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, cmd);
+                release.buffer_barriers(
+                    vk::PipelineStageFlags::COMPUTE_SHADER,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    &[barrier(
+                        vk::AccessFlags::SHADER_WRITE,
+                        vk::AccessFlags::empty(),
+                        compute_family,
+                        transfer_family,
+                    )],
+                    cmd,
+                );
+                release.end_cmd(cmd);
+
+                // The transfer family acquires it, copies, and gives it back.
+                let mut copy = vkcmd::VkCmdPool::for_family(vulkan.clone(), transfer_family);
+                let cmd = copy.create_cmd_buffer(vk::CommandBufferLevel::PRIMARY);
+                copy.begin_cmd(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, cmd);
+                copy.buffer_barriers(
+                    vk::PipelineStageFlags::TOP_OF_PIPE,
+                    vk::PipelineStageFlags::TRANSFER,
+                    &[barrier(
+                        vk::AccessFlags::empty(),
+                        transfer_access,
+                        compute_family,
+                        transfer_family,
+                    )],
+                    cmd,
+                );
+                copy.copy_buffer(src, dst, buffer.size, cmd);
+                copy.buffer_barriers(
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE | vk::PipelineStageFlags::HOST,
+                    &[
+                        barrier(
+                            vk::AccessFlags::TRANSFER_WRITE,
+                            vk::AccessFlags::empty(),
+                            transfer_family,
+                            compute_family,
+                        ),
+                        staging_barrier,
+                    ],
+                    cmd,
+                );
+                copy.end_cmd(cmd);
+
+                // The compute family acquires it back before the next dispatches.
+                let mut acquire = vkcmd::VkCmdPool::new(vulkan.clone());
+                let cmd = acquire.create_cmd_buffer(vk::CommandBufferLevel::PRIMARY);
+                acquire.begin_cmd(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, cmd);
+                acquire.buffer_barriers(
+                    vk::PipelineStageFlags::TOP_OF_PIPE,
+                    vk::PipelineStageFlags::COMPUTE_SHADER,
+                    &[barrier(
+                        vk::AccessFlags::empty(),
+                        shader_access,
+                        transfer_family,
+                        compute_family,
+                    )],
+                    cmd,
+                );
+                acquire.end_cmd(cmd);
+
+                {
+                    let queue = vulkan.compute_queue(0).lock().unwrap();
+                    release.submit_semaphores(*queue, &[], &[released.semaphore], None);
+                }
+                {
+                    let queue = transfer_queue.queue.lock().unwrap();
+                    copy.submit_semaphores(
+                        *queue,
+                        &[(released.semaphore, vk::PipelineStageFlags::TRANSFER)],
+                        &[copied.semaphore],
+                        None,
+                    );
+                }
+                {
+                    let queue = vulkan.compute_queue(0).lock().unwrap();
+                    acquire.submit_semaphores(
+                        *queue,
+                        &[(copied.semaphore, vk::PipelineStageFlags::COMPUTE_SHADER)],
+                        &[],
+                        Some(fence.fence),
+                    );
+                }
+                (vec![release, copy, acquire], vec![released, copied])
+            }
+        };
+
+        Transfer {
+            staging,
+            staging_memory,
+            _pools: transfer.0,
+            _semaphores: transfer.1,
+            fence,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.fence.status() == vkfence::FenceStates::SIGNALED
+    }
+
+    /// Block until the copy is done, or return the error of the wait, e.g. a lost device.
+    fn wait(&self) -> Result<(), vk::Result> {
+        loop {
+            match self.fence.wait(1000 * 1000 * 1000) {
+                vk::Result::SUCCESS => return Ok(()),
+                vk::Result::TIMEOUT => {}
+                err => return Err(err),
+            }
+        }
+    }
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        // The staging buffer and the command buffers must outlive the copy.
+        // If the wait failed, the device is lost and will not use them anymore.
+        let _ = self.wait();
+    }
+}
+
+/// An upload started with `Buffer::upload`.
+pub struct Upload<'b> {
+    transfer: Transfer,
+    buffer: PhantomData<&'b mut ()>,
+}
+
+impl<'b> Upload<'b> {
+    pub fn is_done(&self) -> bool {
+        self.transfer.is_done()
+    }
+
+    /// Block until the buffer holds the uploaded data.
+    pub fn wait(self) {
+        self.transfer
+            .wait()
+            .expect("[ERR] Could not wait for the upload.");
+    }
+}
+
+/// A download started with `Buffer::download`.
+pub struct Download<'b, T> {
+    transfer: Transfer,
+    buffer: PhantomData<&'b T>,
+}
+
+impl<'b, T> Download<'b, T> {
+    pub fn is_done(&self) -> bool {
+        self.transfer.is_done()
+    }
+
+    /// Block until the copy is done and return the content of the buffer.
+    pub fn wait(self) -> Vec<T> {
+        self.transfer
+            .wait()
+            .expect("[ERR] Could not wait for the download.");
+        self.transfer
+            .staging_memory
+            .get_buffer(&self.transfer.staging)
+    }
+}
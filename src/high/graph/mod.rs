@@ -0,0 +1,818 @@
+use crate::high::buffer::Buffer;
+use crate::high::job::ShaderSource;
+use crate::low::{vkcmd, vkdescriptor, vkfence, vkmem, vkpipeline, vkshader, vkstate};
+pub use ash::version::DeviceV1_0;
+use ash::vk;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fmt::Write;
+use std::sync::{Arc, RwLock};
+
+/// How a node uses a buffer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    pub fn reads(self) -> bool {
+        self != Access::Write
+    }
+
+    pub fn writes(self) -> bool {
+        self != Access::Read
+    }
+}
+
+/// Why a node has to run after another one.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Hazard {
+    /// The node reads what the other one wrote.
+    ReadAfterWrite,
+    /// The node overwrites what the other one reads.
+    WriteAfterRead,
+    /// Both nodes write the same buffer, the last one must win.
+    WriteAfterWrite,
+}
+
+impl Hazard {
+    fn short_name(self) -> &'static str {
+        match self {
+            Hazard::ReadAfterWrite => "RAW",
+            Hazard::WriteAfterRead => "WAR",
+            Hazard::WriteAfterWrite => "WAW",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind<'a> {
+    Dispatch {
+        shader: ShaderSource<'a>,
+        dispatch: (u32, u32, u32),
+        constants: Vec<u32>,
+    },
+    Copy,
+}
+
+/// One step of a graph: a dispatch of a shader or a copy between two buffers.
+/// The buffers a node uses are referred to by name.
+#[derive(Debug, Clone)]
+pub struct Node<'a> {
+    name: String,
+    kind: NodeKind<'a>,
+    /// (binding, buffer name, access)
+    bindings: Vec<(u32, String, Access)>,
+}
+
+impl<'a> Node<'a> {
+    /// Dispatch `shader`. The buffers it uses are declared with `read`, `write` and `read_write`.
+    pub fn dispatch(name: &str, shader: ShaderSource<'a>, dispatch: (u32, u32, u32)) -> Node<'a> {
+        Node {
+            name: name.to_string(),
+            kind: NodeKind::Dispatch {
+                shader,
+                dispatch,
+                constants: Vec::new(),
+            },
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Copy the whole content of `src` at the start of `dst`.
+    pub fn copy(name: &str, src: &str, dst: &str) -> Node<'a> {
+        Node {
+            name: name.to_string(),
+            kind: NodeKind::Copy,
+            bindings: vec![
+                (0, src.to_string(), Access::Read),
+                (1, dst.to_string(), Access::Write),
+            ],
+        }
+    }
+
+    pub fn read(self, binding: u32, buffer: &str) -> Node<'a> {
+        self.bind(binding, buffer, Access::Read)
+    }
+
+    pub fn write(self, binding: u32, buffer: &str) -> Node<'a> {
+        self.bind(binding, buffer, Access::Write)
+    }
+
+    pub fn read_write(self, binding: u32, buffer: &str) -> Node<'a> {
+        self.bind(binding, buffer, Access::ReadWrite)
+    }
+
+    /// Push constants given to the shader of a dispatch.
+    pub fn constants(mut self, values: &[u32]) -> Node<'a> {
+        match &mut self.kind {
+            NodeKind::Dispatch { constants, .. } => *constants = values.to_vec(),
+            NodeKind::Copy => panic!("[ERR] A copy does not take push constants."),
+        }
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn bind(mut self, binding: u32, buffer: &str, access: Access) -> Node<'a> {
+        assert!(
+            matches!(self.kind, NodeKind::Dispatch { .. }),
+            "[ERR] The buffers of a copy are given when it is created."
+        );
+        self.bindings.push((binding, buffer.to_string(), access));
+        self
+    }
+}
+
+#[derive(Clone, Copy)]
+enum BufferSource<'a> {
+    /// Uploaded before the graph runs, and downloaded after.
+    Input(&'a [u8]),
+    /// Downloaded after the graph runs.
+    Output,
+    /// Only used inside the graph, its memory can be shared with other transient buffers.
+    Transient,
+    /// Owned outside of the graph, neither uploaded nor downloaded.
+    Device(&'a vkmem::VkBuffer),
+}
+
+#[derive(Clone)]
+struct GraphBuffer<'a> {
+    name: String,
+    size: u64,
+    source: BufferSource<'a>,
+}
+
+/// `from` has to run before `to` because of what they do with `buffer`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub buffer: usize,
+    pub hazard: Hazard,
+}
+
+/// The order in which the nodes of a graph run, and how they are synchronized.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub edges: Vec<Edge>,
+    /// Nodes grouped by level. A node only depends on nodes of the previous levels,
+    /// so the nodes of a level can run at the same time.
+    pub levels: Vec<Vec<usize>>,
+    /// Buffers which need a barrier before each level.
+    pub barriers: Vec<Vec<usize>>,
+    /// Memory slot of each transient buffer. Transient buffers used during
+    /// levels that don't overlap share the same slot.
+    pub slots: Vec<Option<usize>>,
+}
+
+/// Content of the input and output buffers of a graph once it has run.
+pub struct GraphOutput {
+    buffers: HashMap<String, Vec<u8>>,
+}
+
+impl GraphOutput {
+    /// Content of the buffer called `name`, if it is an input or an output of the graph.
+    pub fn get<T: Copy>(&self, name: &str) -> Option<Vec<T>> {
+        let bytes = self.buffers.get(name)?;
+        let len = bytes.len() / std::mem::size_of::<T>();
+        let mut output: Vec<T> = Vec::with_capacity(len);
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                output.as_mut_ptr() as *mut u8,
+                len * std::mem::size_of::<T>(),
+            );
+            output.set_len(len);
+        }
+        Some(output)
+    }
+}
+
+/// A set of dispatches and copies over named buffers.
+/// The dependencies between nodes are found from what they read and write,
+/// in the order the nodes were added, so the graph is always acyclic.
+#[derive(Default)]
+pub struct Graph<'a> {
+    buffers: Vec<GraphBuffer<'a>>,
+    nodes: Vec<Node<'a>>,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new() -> Graph<'a> {
+        Graph {
+            buffers: Vec::new(),
+            nodes: Vec::new(),
+        }
+    }
+
+    /// A buffer initialized with `data`, which can be read back after the graph has run.
+    pub fn add_input<T: Copy>(self, name: &str, data: &'a [T]) -> Graph<'a> {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data))
+        };
+        self.add(name, bytes.len() as u64, BufferSource::Input(bytes))
+    }
+
+    /// A buffer of `len` elements of `T` which can be read back after the graph has run.
+    pub fn add_output<T>(self, name: &str, len: usize) -> Graph<'a> {
+        let size = (len * std::mem::size_of::<T>()) as u64;
+        self.add(name, size, BufferSource::Output)
+    }
+
+    /// A buffer of `len` elements of `T` only used between nodes of the graph.
+    pub fn add_transient<T>(self, name: &str, len: usize) -> Graph<'a> {
+        let size = (len * std::mem::size_of::<T>()) as u64;
+        self.add(name, size, BufferSource::Transient)
+    }
+
+    /// A buffer living in device memory outside of the graph.
+    pub fn add_device_buffer<U>(self, name: &str, buffer: &'a Buffer<U>) -> Graph<'a> {
+        let raw = buffer.raw();
+        self.add(name, raw.size, BufferSource::Device(raw))
+    }
+
+    pub fn add_node(mut self, node: Node<'a>) -> Graph<'a> {
+        assert!(
+            self.nodes.iter().all(|other| other.name != node.name),
+            "[ERR] There is already a node called `{}`.",
+            node.name
+        );
+        self.nodes.push(node);
+        self
+    }
+
+    fn add(mut self, name: &str, size: u64, source: BufferSource<'a>) -> Graph<'a> {
+        assert!(
+            self.buffers.iter().all(|buffer| buffer.name != name),
+            "[ERR] There is already a buffer called `{}`.",
+            name
+        );
+        assert!(size > 0, "[ERR] The buffer `{}` is empty.", name);
+        self.buffers.push(GraphBuffer {
+            name: name.to_string(),
+            size,
+            source,
+        });
+        self
+    }
+
+    fn buffer_index(&self, node: &Node<'a>, name: &str) -> usize {
+        self.buffers
+            .iter()
+            .position(|buffer| buffer.name == name)
+            .unwrap_or_else(|| {
+                panic!(
+                    "[ERR] The node `{}` uses the unknown buffer `{}`.",
+                    node.name, name
+                )
+            })
+    }
+
+    /// Buffers used by a node with how they are used, each buffer once.
+    fn accesses(&self, node: &Node<'a>) -> Vec<(usize, Access)> {
+        let mut accesses: Vec<(usize, Access)> = Vec::new();
+        for (_, name, access) in &node.bindings {
+            let buffer = self.buffer_index(node, name);
+            match accesses.iter_mut().find(|(b, _)| *b == buffer) {
+                Some((_, previous)) if *previous != *access => *previous = Access::ReadWrite,
+                Some(_) => {}
+                None => accesses.push((buffer, *access)),
+            }
+        }
+        accesses
+    }
+
+    /// Find the dependencies between nodes, group them in levels, and plan the
+    /// barriers and the memory of the transient buffers.
+    pub fn schedule(&self) -> Schedule {
+        let accesses: Vec<Vec<(usize, Access)>> =
+            self.nodes.iter().map(|node| self.accesses(node)).collect();
+
+        // Edges only go from a node to a node added after it.
+        let mut edges: Vec<Edge> = Vec::new();
+        let mut last_writer: Vec<Option<usize>> = vec![None; self.buffers.len()];
+        let mut readers: Vec<Vec<usize>> = vec![Vec::new(); self.buffers.len()];
+        for (node, node_accesses) in accesses.iter().enumerate() {
+            for &(buffer, access) in node_accesses {
+                if let Some(writer) = last_writer[buffer] {
+                    let hazard = if access.reads() {
+                        Hazard::ReadAfterWrite
+                    } else {
+                        Hazard::WriteAfterWrite
+                    };
+                    edges.push(Edge {
+                        from: writer,
+                        to: node,
+                        buffer,
+                        hazard,
+                    });
+                }
+                if access.writes() {
+                    for &reader in &readers[buffer] {
+                        edges.push(Edge {
+                            from: reader,
+                            to: node,
+                            buffer,
+                            hazard: Hazard::WriteAfterRead,
+                        });
+                    }
+                }
+            }
+            for &(buffer, access) in node_accesses {
+                if access.writes() {
+                    last_writer[buffer] = Some(node);
+                    readers[buffer].clear();
+                } else {
+                    readers[buffer].push(node);
+                }
+            }
+        }
+
+        // Level of a node: length of the longest chain of dependencies leading to it.
+        let mut node_level: Vec<usize> = vec![0; self.nodes.len()];
+        for edge in &edges {
+            node_level[edge.to] = node_level[edge.to].max(node_level[edge.from] + 1);
+        }
+        let level_count = node_level.iter().map(|l| l + 1).max().unwrap_or(0);
+        let mut levels: Vec<Vec<usize>> = vec![Vec::new(); level_count];
+        for (node, level) in node_level.iter().enumerate() {
+            levels[*level].push(node);
+        }
+
+        let slots = self.plan_slots(&accesses, &node_level);
+
+        // Memory a buffer lives in: its own, or the slot it shares with other transient buffers.
+        let memory_of = |buffer: usize| match slots[buffer] {
+            Some(slot) => self.buffers.len() + slot,
+            None => buffer,
+        };
+        let mut written = vec![false; self.buffers.len() * 2];
+        let mut read = vec![false; self.buffers.len() * 2];
+        let mut occupant: Vec<Option<usize>> = vec![None; self.buffers.len() * 2];
+        let mut barriers: Vec<Vec<usize>> = vec![Vec::new(); level_count];
+        for (level, nodes) in levels.iter().enumerate() {
+            for &node in nodes {
+                for &(buffer, access) in &accesses[node] {
+                    let memory = memory_of(buffer);
+                    if written[memory] || (access.writes() && read[memory]) {
+                        written[memory] = false;
+                        read[memory] = false;
+                        // When the memory changes hands, the previous buffer is synchronized too.
+                        if let Some(previous) = occupant[memory].filter(|p| *p != buffer) {
+                            barriers[level].push(previous);
+                        }
+                        barriers[level].push(buffer);
+                    }
+                }
+            }
+            for &node in nodes {
+                for &(buffer, access) in &accesses[node] {
+                    let memory = memory_of(buffer);
+                    written[memory] |= access.writes();
+                    read[memory] |= access.reads();
+                    occupant[memory] = Some(buffer);
+                }
+            }
+            barriers[level].sort_unstable();
+            barriers[level].dedup();
+        }
+
+        Schedule {
+            edges,
+            levels,
+            barriers,
+            slots,
+        }
+    }
+
+    /// Give each transient buffer a memory slot, reusing the slots of the
+    /// transient buffers no longer in use.
+    fn plan_slots(
+        &self,
+        accesses: &[Vec<(usize, Access)>],
+        node_level: &[usize],
+    ) -> Vec<Option<usize>> {
+        // First and last level where each buffer is used.
+        let mut lifetimes: Vec<Option<(usize, usize)>> = vec![None; self.buffers.len()];
+        for (node, node_accesses) in accesses.iter().enumerate() {
+            let level = node_level[node];
+            for &(buffer, _) in node_accesses {
+                lifetimes[buffer] = Some(match lifetimes[buffer] {
+                    Some((first, last)) => (first.min(level), last.max(level)),
+                    None => (level, level),
+                });
+            }
+        }
+
+        let mut transients: Vec<(usize, (usize, usize))> = self
+            .buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| matches!(buffer.source, BufferSource::Transient))
+            .filter_map(|(index, _)| lifetimes[index].map(|lifetime| (index, lifetime)))
+            .collect();
+        transients.sort_by_key(|(index, (first, _))| (*first, *index));
+
+        // Last level using each slot.
+        let mut slot_ends: Vec<usize> = Vec::new();
+        let mut slots: Vec<Option<usize>> = vec![None; self.buffers.len()];
+        for (buffer, (first, last)) in transients {
+            let free = slot_ends.iter().position(|end| *end < first);
+            let slot = match free {
+                Some(slot) => {
+                    slot_ends[slot] = last;
+                    slot
+                }
+                None => {
+                    slot_ends.push(last);
+                    slot_ends.len() - 1
+                }
+            };
+            slots[buffer] = Some(slot);
+        }
+
+        slots
+    }
+
+    /// The graph in the Graphviz DOT format, with nodes of the same level on the same rank.
+    pub fn to_dot(&self) -> String {
+        let schedule = self.schedule();
+        let mut dot = String::new();
+        writeln!(dot, "digraph wyzoid {{").unwrap();
+        writeln!(dot, "    node [shape=box];").unwrap();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let label = match &node.kind {
+                NodeKind::Dispatch { dispatch, .. } => format!(
+                    "{}\\ndispatch ({}, {}, {})",
+                    node.name, dispatch.0, dispatch.1, dispatch.2
+                ),
+                NodeKind::Copy => format!(
+                    "{}\\ncopy {} -> {}",
+                    node.name, node.bindings[0].1, node.bindings[1].1
+                ),
+            };
+            writeln!(dot, "    n{} [label=\"{}\"];", index, label).unwrap();
+        }
+        for (level, nodes) in schedule.levels.iter().enumerate() {
+            let names: Vec<String> = nodes.iter().map(|node| format!("n{};", node)).collect();
+            let barriers: Vec<&str> = schedule.barriers[level]
+                .iter()
+                .map(|buffer| self.buffers[*buffer].name.as_str())
+                .collect();
+            writeln!(
+                dot,
+                "    {{ rank=same; {} }} // level {}, barriers: [{}]",
+                names.join(" "),
+                level,
+                barriers.join(", ")
+            )
+            .unwrap();
+        }
+        for edge in &schedule.edges {
+            writeln!(
+                dot,
+                "    n{} -> n{} [label=\"{} ({})\"];",
+                edge.from,
+                edge.to,
+                self.buffers[edge.buffer].name,
+                edge.hazard.short_name()
+            )
+            .unwrap();
+        }
+        for (buffer, slot) in schedule.slots.iter().enumerate() {
+            if let Some(slot) = slot {
+                writeln!(
+                    dot,
+                    "    // transient buffer {} in memory slot {}",
+                    self.buffers[buffer].name, slot
+                )
+                .unwrap();
+            }
+        }
+        writeln!(dot, "}}").unwrap();
+
+        dot
+    }
+
+    /// Run every node in one submission and wait for them to be done.
+    pub fn execute(&self, vulkan: Arc<vkstate::VulkanState>) -> GraphOutput {
+        let schedule = self.schedule();
+        for node in &self.nodes {
+            if let NodeKind::Copy = node.kind {
+                let src = self.buffer_index(node, &node.bindings[0].1);
+                let dst = self.buffer_index(node, &node.bindings[1].1);
+                assert!(
+                    self.buffers[src].size <= self.buffers[dst].size,
+                    "[ERR] The copy `{}` does not fit in its destination.",
+                    node.name
+                );
+            }
+        }
+
+        // Buffers owned by the graph: inputs and outputs share one memory, transient ones another.
+        let mut owned: Vec<Option<vkmem::VkBuffer>> = self
+            .buffers
+            .iter()
+            .map(|buffer| match buffer.source {
+                BufferSource::Device(_) => None,
+                _ => Some(vkmem::VkBuffer::new(vulkan.clone(), buffer.size)),
+            })
+            .collect();
+
+        let persistent: Vec<usize> = (0..self.buffers.len())
+            .filter(|b| owned[*b].is_some() && schedule.slots[*b].is_none())
+            .collect();
+        let mut persistent_size: u64 = 0;
+        let mut persistent_offsets: Vec<u64> = Vec::with_capacity(persistent.len());
+        for &buffer in &persistent {
+            let requirements = owned[buffer]
+                .as_ref()
+                .unwrap()
+                .get_buffer_memory_requirements();
+            let offset = persistent_size.div_ceil(requirements.alignment) * requirements.alignment;
+            persistent_offsets.push(offset);
+            persistent_size = offset + requirements.size;
+        }
+        // The host writes the inputs and reads the outputs, so it maps the persistent buffers.
+        let persistent_memory = if persistent_size > 0 {
+            let requirements = vkmem::combined_requirements(
+                persistent.iter().map(|b| owned[*b].as_ref().unwrap()),
+                persistent_size,
+            );
+            let host_flags =
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+            let memory = vkmem::VkMem::find_mem_for(vulkan.clone(), requirements, host_flags)
+                .expect("[ERR] Could not find a memory type fitting our need.");
+            assert!(
+                memory.property_flags().contains(host_flags),
+                "[ERR] Could not find a host visible memory type for the graph buffers."
+            );
+            Some(memory)
+        } else {
+            None
+        };
+        for (&buffer, &offset) in persistent.iter().zip(&persistent_offsets) {
+            let memory = persistent_memory.as_ref().unwrap();
+            let vk_buffer = owned[buffer].as_mut().unwrap();
+            vk_buffer.bind(memory.mem, offset);
+            if let BufferSource::Input(data) = self.buffers[buffer].source {
+                memory.map_buffer(data, vk_buffer);
+            }
+        }
+
+        // Each slot is as large and as aligned as the largest of its buffers.
+        let slot_count = schedule.slots.iter().flatten().max().map_or(0, |s| s + 1);
+        let mut slot_sizes: Vec<(u64, u64)> = vec![(0, 1); slot_count];
+        for (buffer, slot) in schedule.slots.iter().enumerate() {
+            if let Some(slot) = slot {
+                let requirements = owned[buffer]
+                    .as_ref()
+                    .unwrap()
+                    .get_buffer_memory_requirements();
+                slot_sizes[*slot].0 = slot_sizes[*slot].0.max(requirements.size);
+                slot_sizes[*slot].1 = slot_sizes[*slot].1.max(requirements.alignment);
+            }
+        }
+        let mut transient_size: u64 = 0;
+        let mut slot_offsets: Vec<u64> = Vec::with_capacity(slot_count);
+        for (size, alignment) in &slot_sizes {
+            let offset = transient_size.div_ceil(*alignment) * alignment;
+            slot_offsets.push(offset);
+            transient_size = offset + size;
+        }
+        // Only the shaders use the transient buffers.
+        let transient_memory = if transient_size > 0 {
+            let requirements = vkmem::combined_requirements(
+                schedule
+                    .slots
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, slot)| slot.is_some())
+                    .map(|(b, _)| owned[b].as_ref().unwrap()),
+                transient_size,
+            );
+            Some(
+                vkmem::VkMem::find_mem_for(
+                    vulkan.clone(),
+                    requirements,
+                    vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                )
+                .expect("[ERR] Could not find a memory type fitting our need."),
+            )
+        } else {
+            None
+        };
+        for (buffer, slot) in schedule.slots.iter().enumerate() {
+            if let Some(slot) = slot {
+                owned[buffer]
+                    .as_mut()
+                    .unwrap()
+                    .bind(transient_memory.as_ref().unwrap().mem, slot_offsets[*slot]);
+            }
+        }
+
+        let handles: Vec<(vk::Buffer, u64)> = self
+            .buffers
+            .iter()
+            .enumerate()
+            .map(|(index, buffer)| match buffer.source {
+                BufferSource::Device(device) => (device.buffer, device.size),
+                _ => {
+                    let vk_buffer = owned[index].as_ref().unwrap();
+                    (vk_buffer.buffer, vk_buffer.size)
+                }
+            })
+            .collect();
+
+        // One pipeline per dispatch, its layout only has the bindings of the node.
+        let mut shaders: Vec<Option<Arc<RwLock<vkshader::VkShader>>>> = Vec::new();
+        let mut pipelines: Vec<Option<vkpipeline::VkComputePipeline>> = Vec::new();
+        let mut descriptors: Vec<Option<vkdescriptor::VkDescriptor>> = Vec::new();
+        for node in &self.nodes {
+            let (source, constants) = match &node.kind {
+                NodeKind::Dispatch {
+                    shader, constants, ..
+                } => (shader, constants),
+                NodeKind::Copy => {
+                    shaders.push(None);
+                    pipelines.push(None);
+                    descriptors.push(None);
+                    continue;
+                }
+            };
+            let mut shader = match source {
+                ShaderSource::File(path) => {
+                    vkshader::VkShader::new(vulkan.clone(), path, CString::new("main").unwrap())
+                }
+                ShaderSource::Spirv(spirv) => vkshader::VkShader::from_spirv(
+                    vulkan.clone(),
+                    spirv,
+                    CString::new("main").unwrap(),
+                ),
+            };
+            if !constants.is_empty() {
+                shader.add_push_constant_range(
+                    0,
+                    (constants.len() * std::mem::size_of::<u32>()) as u32,
+                    vk::ShaderStageFlags::COMPUTE,
+                );
+            }
+            for (binding, _, _) in &node.bindings {
+                shader.add_layout_binding(
+                    *binding,
+                    1,
+                    vk::DescriptorType::STORAGE_BUFFER,
+                    vk::ShaderStageFlags::COMPUTE,
+                );
+            }
+            shader.create_pipeline_layout();
+            let pipeline = vkpipeline::VkComputePipeline::new(vulkan.clone(), &shader);
+            let shader = Arc::new(RwLock::new(shader));
+
+            let mut descriptor = vkdescriptor::VkDescriptor::new(vulkan.clone(), shader.clone());
+            descriptor.add_pool_size(
+                node.bindings.len() as u32,
+                vk::DescriptorType::STORAGE_BUFFER,
+            );
+            descriptor.create_pool(1);
+            descriptor.create_set();
+            let set = *descriptor.get_first_set().unwrap();
+            let mut write_descriptor = vkdescriptor::VkWriteDescriptor::new(vulkan.clone());
+            for (_, name, _) in &node.bindings {
+                let (buffer, size) = handles[self.buffer_index(node, name)];
+                write_descriptor.add_buffer(buffer, 0, size);
+            }
+            let infos: Vec<Vec<vk::DescriptorBufferInfo>> = write_descriptor
+                .buffer_descriptors
+                .iter()
+                .map(|info| vec![*info])
+                .collect();
+            for (i, (binding, _, _)) in node.bindings.iter().enumerate() {
+                write_descriptor.add_write_descriptors(
+                    set,
+                    vk::DescriptorType::STORAGE_BUFFER,
+                    &infos[i],
+                    *binding,
+                    0,
+                );
+            }
+            write_descriptor.update_descriptors_sets();
+
+            shaders.push(Some(shader));
+            pipelines.push(Some(pipeline));
+            descriptors.push(Some(descriptor));
+        }
+
+        // Every level in one command buffer, with barriers only where the schedule needs them.
+        let mut cmd_pool = vkcmd::VkCmdPool::new(vulkan.clone());
+        let cmd = cmd_pool.create_cmd_buffer(vk::CommandBufferLevel::PRIMARY);
+        cmd_pool.begin_cmd(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, cmd);
+        let stages = vk::PipelineStageFlags::COMPUTE_SHADER | vk::PipelineStageFlags::TRANSFER;
+        for (level, nodes) in schedule.levels.iter().enumerate() {
+            if !schedule.barriers[level].is_empty() {
+                let buffer_barriers: Vec<vk::BufferMemoryBarrier> = schedule.barriers[level]
+                    .iter()
+                    .map(|buffer| {
+                        vk::BufferMemoryBarrier::builder()
+                            .src_access_mask(
+                                vk::AccessFlags::SHADER_WRITE | vk::AccessFlags::TRANSFER_WRITE,
+                            )
+                            .dst_access_mask(
+                                vk::AccessFlags::SHADER_READ
+                                    | vk::AccessFlags::SHADER_WRITE
+                                    | vk::AccessFlags::TRANSFER_READ
+                                    | vk::AccessFlags::TRANSFER_WRITE,
+                            )
+                            .buffer(handles[*buffer].0)
+                            .size(vk::WHOLE_SIZE)
+                            .build()
+                    })
+                    .collect();
+                unsafe {
+                    vulkan.device.cmd_pipeline_barrier(
+                        cmd_pool.cmd_buffers[cmd],
+                        stages,
+                        stages,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &buffer_barriers,
+                        &[],
+                    );
+                }
+            }
+
+            for &node in nodes {
+                let node_ref = &self.nodes[node];
+                match &node_ref.kind {
+                    NodeKind::Dispatch {
+                        dispatch,
+                        constants,
+                        ..
+                    } => {
+                        let layout = shaders[node]
+                            .as_ref()
+                            .unwrap()
+                            .read()
+                            .unwrap()
+                            .pipeline
+                            .unwrap();
+                        cmd_pool.bind_pipeline(
+                            pipelines[node].as_ref().unwrap().pipeline,
+                            vk::PipelineBindPoint::COMPUTE,
+                            cmd,
+                        );
+                        cmd_pool.bind_descriptor(
+                            layout,
+                            vk::PipelineBindPoint::COMPUTE,
+                            &descriptors[node].as_ref().unwrap().set,
+                            cmd,
+                        );
+                        if !constants.is_empty() {
+                            let bytes: Vec<u8> =
+                                constants.iter().flat_map(|c| c.to_ne_bytes()).collect();
+                            cmd_pool.push_constants(
+                                layout,
+                                vk::ShaderStageFlags::COMPUTE,
+                                &bytes,
+                                cmd,
+                            );
+                        }
+                        cmd_pool.dispatch(dispatch.0, dispatch.1, dispatch.2, cmd);
+                    }
+                    NodeKind::Copy => {
+                        let src = self.buffer_index(node_ref, &node_ref.bindings[0].1);
+                        let dst = self.buffer_index(node_ref, &node_ref.bindings[1].1);
+                        cmd_pool.copy_buffer(handles[src].0, handles[dst].0, handles[src].1, cmd);
+                    }
+                }
+            }
+        }
+        cmd_pool.end_cmd(cmd);
+
+        let fence = vkfence::VkFence::new(vulkan.clone(), false);
+        {
+            let queue = vulkan.compute_queue(0).lock().unwrap();
+            cmd_pool.submit(*queue, Some(fence.fence));
+        }
+        loop {
+            match fence.wait(1000 * 1000 * 1000) {
+                vk::Result::SUCCESS => break,
+                vk::Result::TIMEOUT => {}
+                err => panic!("[ERR] Could not wait for the graph: {}.", err),
+            }
+        }
+
+        let mut buffers = HashMap::new();
+        for (&buffer, vk_buffer) in persistent.iter().map(|b| (b, owned[*b].as_ref().unwrap())) {
+            let bytes: Vec<u8> = persistent_memory.as_ref().unwrap().get_buffer(vk_buffer);
+            buffers.insert(self.buffers[buffer].name.clone(), bytes);
+        }
+
+        GraphOutput { buffers }
+    }
+}
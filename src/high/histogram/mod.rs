@@ -0,0 +1,132 @@
+use crate::high::job::{JobBuilder, JobStatus};
+use crate::high::reduce::{self, ReduceOp};
+use crate::low::vkstate::VulkanState;
+use std::sync::Arc;
+
+const HISTOGRAM: &[u8] = include_bytes!("../../../shaders/bin/histogram/histogram.cs.spirv");
+
+/// Number of invocations in one work group of the histogram shader.
+const THREADS: usize = 256;
+/// Number of values we want each invocation to count.
+const ITEMS_PER_THREAD: usize = 16;
+/// Maximum number of work groups we dispatch on the x axis.
+const MAX_GROUPS: usize = 65535;
+
+/// Counts of values in `counts.len()` bins of the same width covering [low, high].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub counts: Vec<u32>,
+    pub low: f32,
+    pub high: f32,
+}
+
+impl Histogram {
+    pub fn bin_width(&self) -> f32 {
+        (self.high - self.low) / self.counts.len() as f32
+    }
+
+    /// Lower bound of the `bin`-th bin.
+    pub fn bin_start(&self, bin: usize) -> f32 {
+        self.low + bin as f32 * self.bin_width()
+    }
+}
+
+/// Count the values of `data` in `bins` bins of the same width covering [low, high].
+/// Values equal to `high` are counted in the last bin, values out of the range and NaN are ignored.
+pub fn histogram(
+    vulkan: Arc<VulkanState>,
+    data: &[f32],
+    bins: usize,
+    low: f32,
+    high: f32,
+) -> Histogram {
+    assert!(bins > 0, "[ERR] A histogram needs at least one bin.");
+    assert!(
+        low.is_finite() && high.is_finite(),
+        "[ERR] The histogram range must be finite."
+    );
+    assert!(low < high, "[ERR] The histogram range is empty.");
+
+    let counts = if data.is_empty() {
+        vec![0; bins]
+    } else {
+        run_histogram(vulkan, data, bins, low, high)
+    };
+
+    Histogram { counts, low, high }
+}
+
+/// Count the values of `data` in `bins` bins covering the range of the data,
+/// found with a min/max reduction on the GPU.
+/// Infinities and NaN are ignored, the range only covers the finite values.
+pub fn histogram_auto(vulkan: Arc<VulkanState>, data: &[f32], bins: usize) -> Histogram {
+    assert!(bins > 0, "[ERR] A histogram needs at least one bin.");
+    let empty = Histogram {
+        counts: vec![0; bins],
+        low: 0.0,
+        high: 0.0,
+    };
+    if data.is_empty() {
+        return empty;
+    }
+
+    let mut low = reduce::reduce(vulkan.clone(), data, ReduceOp::Min);
+    let mut high = reduce::reduce(vulkan.clone(), data, ReduceOp::Max);
+    if !low.is_finite() || !high.is_finite() {
+        // An infinite range would give infinitely wide bins, look for the finite one.
+        let finite = data.iter().filter(|v| v.is_finite());
+        low = finite.clone().fold(f32::INFINITY, |a, &b| a.min(b));
+        high = finite.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+        if low > high {
+            return empty;
+        }
+    }
+    if low >= high {
+        // Every finite value is the same, they all go in the first bin.
+        let mut counts = vec![0; bins];
+        counts[0] = data.iter().filter(|v| **v == low).count() as u32;
+        return Histogram { counts, low, high };
+    }
+
+    histogram(vulkan, data, bins, low, high)
+}
+
+fn run_histogram(
+    vulkan: Arc<VulkanState>,
+    data: &[f32],
+    bins: usize,
+    low: f32,
+    high: f32,
+) -> Vec<u32> {
+    // The job only has one element type, the shader reads the bits back as floats.
+    let input: Vec<u32> = data.iter().map(|v| v.to_bits()).collect();
+    let counts: Vec<u32> = vec![0; bins];
+    let groups = input
+        .len()
+        .div_ceil(THREADS * ITEMS_PER_THREAD)
+        .clamp(1, MAX_GROUPS);
+
+    let mut job = JobBuilder::new()
+        .add_buffer(&input, 0, 0)
+        .add_buffer(&counts, 0, 1)
+        .add_spirv(HISTOGRAM)
+        .add_pass(
+            0,
+            (groups as u32, 1, 1),
+            &[
+                input.len() as u32,
+                bins as u32,
+                low.to_bits(),
+                high.to_bits(),
+            ],
+        )
+        .build(vulkan);
+    job.execute();
+    while job.status() == JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+
+    job.get_output()
+        .expect("[ERR] Could not get the histogram.")
+        .swap_remove(1)
+}
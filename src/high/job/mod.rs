@@ -1,12 +1,20 @@
-use crate::low::{vkcmd, vkdescriptor, vkfence, vkmem, vkpipeline, vkshader, vkstate};
-use crate::utils::get_fract_s;
+use crate::high::buffer::Buffer;
+use crate::low::vkfeatures::Feature;
+use crate::low::{
+    vkcmd, vkdescriptor, vkfence, vkimage, vkmem, vkpipeline, vksemaphore, vkshader, vkstate,
+};
+use crate::utils::{get_fract_s, load_file};
 pub use ash::version::{DeviceV1_0, EntryV1_0, InstanceV1_0};
 use ash::vk;
-use std::cell::RefCell;
 use std::ffi::CString;
 use std::fmt;
+use std::future::Future;
+use std::io::Cursor;
 use std::path::PathBuf;
-use std::rc::Rc;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::{Context, Poll};
 use std::time::Duration;
 use std::time::Instant;
 
@@ -32,6 +40,12 @@ pub struct JobTimingsBuilder {
     download: Option<Duration>,
 }
 
+impl Default for JobTimingsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl JobTimingsBuilder {
     pub fn new() -> JobTimingsBuilder {
         JobTimingsBuilder {
@@ -109,16 +123,26 @@ impl JobTimingsBuilder {
     }
 }
 
+impl JobTimings {
+    /// Achieved GFLOP/s if the shaders did `flop` floating point operations during the execution.
+    /// This is approximate: the execution time is measured on the host, from the submission to
+    /// the first `status` call seeing the job done, so it includes the submission latency and
+    /// is only as precise as the polling of the job.
+    pub fn gflops(&self, flop: f64) -> f64 {
+        flop / self.execution.as_secs_f64() / 1e9
+    }
+}
+
 impl fmt::Display for JobTimings {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "upload: {}ms\n", get_fract_s(self.upload))?;
-        write!(f, "shader: {}ms\n", get_fract_s(self.shader))?;
-        write!(f, "command: {}ms\n", get_fract_s(self.cmd))?;
-        write!(f, "execution: {}ms\n", get_fract_s(self.execution))?;
-        write!(f, "download: {}ms\n", get_fract_s(self.download))?;
-        write!(
+        writeln!(f, "upload: {}ms", get_fract_s(self.upload))?;
+        writeln!(f, "shader: {}ms", get_fract_s(self.shader))?;
+        writeln!(f, "command: {}ms", get_fract_s(self.cmd))?;
+        writeln!(f, "execution: {}ms", get_fract_s(self.execution))?;
+        writeln!(f, "download: {}ms", get_fract_s(self.download))?;
+        writeln!(
             f,
-            "total: {}ms\n",
+            "total: {}ms",
             get_fract_s(self.upload + self.shader + self.cmd + self.execution + self.download)
         )
     }
@@ -128,11 +152,113 @@ impl fmt::Display for JobTimings {
 pub struct BindPoint {
     pub set: u32,
     pub bind: u32,
+    pub kind: BufferKind,
 }
 
 impl BindPoint {
+    /// Bind a storage buffer.
     pub fn new(set: u32, bind: u32) -> BindPoint {
-        BindPoint { set, bind }
+        BindPoint::with_kind(set, bind, BufferKind::Storage)
+    }
+
+    pub fn with_kind(set: u32, bind: u32, kind: BufferKind) -> BindPoint {
+        BindPoint { set, bind, kind }
+    }
+}
+
+/// How a shader sees an image of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageKind {
+    /// An `image2D`, read and written with `imageLoad`/`imageStore`, downloaded after the job.
+    Storage,
+    /// A `sampler2D`, read only, with `texture` or `texelFetch`.
+    Sampled(vkimage::SamplerConfig),
+}
+
+impl ImageKind {
+    pub fn descriptor_type(self) -> vk::DescriptorType {
+        match self {
+            ImageKind::Storage => vk::DescriptorType::STORAGE_IMAGE,
+            ImageKind::Sampled(_) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+        }
+    }
+
+    fn usage(self) -> vk::ImageUsageFlags {
+        match self {
+            ImageKind::Storage => {
+                vk::ImageUsageFlags::STORAGE
+                    | vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::TRANSFER_DST
+            }
+            ImageKind::Sampled(_) => {
+                vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST
+            }
+        }
+    }
+
+    /// The layout of the image while the shaders use it.
+    fn layout(self) -> vk::ImageLayout {
+        match self {
+            ImageKind::Storage => vk::ImageLayout::GENERAL,
+            ImageKind::Sampled(_) => vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        }
+    }
+}
+
+/// An image bound to a job, with the pixels to upload to it if any.
+pub struct JobImage<'a, T> {
+    pub set: u32,
+    pub bind: u32,
+    pub kind: ImageKind,
+    pub pixels: Option<&'a Vec<T>>,
+    pub width: u32,
+    pub height: u32,
+    pub format: vk::Format,
+}
+
+/// How a shader sees a buffer of a job, which also decides the usage of the buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferKind {
+    /// A `buffer` block.
+    Storage,
+    /// A std140 `uniform` block, read only.
+    Uniform,
+    /// A `textureBuffer` of texels of some format, read with `texelFetch`.
+    UniformTexel(vk::Format),
+    /// An `imageBuffer` of texels of some format, read and written with `imageLoad`/`imageStore`.
+    StorageTexel(vk::Format),
+}
+
+impl BufferKind {
+    pub fn descriptor_type(self) -> vk::DescriptorType {
+        match self {
+            BufferKind::Storage => vk::DescriptorType::STORAGE_BUFFER,
+            BufferKind::Uniform => vk::DescriptorType::UNIFORM_BUFFER,
+            BufferKind::UniformTexel(_) => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+            BufferKind::StorageTexel(_) => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+        }
+    }
+
+    pub fn usage(self) -> vk::BufferUsageFlags {
+        match self {
+            BufferKind::Storage => vk::BufferUsageFlags::STORAGE_BUFFER,
+            BufferKind::Uniform => vk::BufferUsageFlags::UNIFORM_BUFFER,
+            BufferKind::UniformTexel(_) => vk::BufferUsageFlags::UNIFORM_TEXEL_BUFFER,
+            BufferKind::StorageTexel(_) => vk::BufferUsageFlags::STORAGE_TEXEL_BUFFER,
+        }
+    }
+
+    /// The format of the texels and the format feature it needs, for texel buffers.
+    fn texel_format(self) -> Option<(vk::Format, vk::FormatFeatureFlags)> {
+        match self {
+            BufferKind::UniformTexel(format) => {
+                Some((format, vk::FormatFeatureFlags::UNIFORM_TEXEL_BUFFER))
+            }
+            BufferKind::StorageTexel(format) => {
+                Some((format, vk::FormatFeatureFlags::STORAGE_TEXEL_BUFFER))
+            }
+            _ => None,
+        }
     }
 }
 
@@ -144,27 +270,133 @@ pub enum JobStatus {
     FAILURE,
 }
 
+/// Where the SPIR-V of a shader comes from.
+#[derive(Debug, Clone, Copy)]
+pub enum ShaderSource<'a> {
+    /// A compiled shader on disk.
+    File(&'a PathBuf),
+    /// SPIR-V bytes already in memory, typically embedded with `include_bytes!`.
+    Spirv(&'a [u8]),
+}
+
+impl<'a> ShaderSource<'a> {
+    /// The SPIR-V words of the shader.
+    pub fn bytecode(&self) -> Vec<u32> {
+        let bytes = match self {
+            ShaderSource::File(path) => load_file(path).expect("[ERR] Could not load shader file."),
+            ShaderSource::Spirv(spirv) => spirv.to_vec(),
+        };
+        ash::util::read_spv(&mut Cursor::new(bytes)).expect("[ERR] Invalid SPIR-V bytecode.")
+    }
+}
+
+/// One dispatch of a job: which shader to run, how many work groups to launch
+/// and the push constants to give it.
+#[derive(Debug, Clone)]
+pub struct JobPass {
+    pub shader: usize,
+    pub dispatch: (u32, u32, u32),
+    pub constants: Vec<u32>,
+    /// Number of invocations asked with `dispatch_for`, turned into work groups by `build`.
+    pub invocations: Option<(u32, u32, u32)>,
+}
+
+/// Value of the timeline semaphore of a job once the job is done.
+pub const JOB_DONE: u64 = 1;
+
 pub struct Job<'a, T> {
     inputs: Vec<(BindPoint, &'a Vec<T>)>,
     buffers: Vec<(BindPoint, usize)>,
-    shaders: Vec<&'a PathBuf>,
-    dispatch: Vec<(u32, u32, u32)>,
+    device_buffers: Vec<(BindPoint, &'a vkmem::VkBuffer)>,
+    uniforms: Vec<(BindPoint, Vec<u8>)>,
+    images: Vec<JobImage<'a, T>>,
+    shaders: Vec<ShaderSource<'a>>,
+    passes: Vec<JobPass>,
+    queue: usize,
+    /// Semaphores of the jobs this one runs after.
+    dependencies: Vec<Arc<vksemaphore::VkTimelineSemaphore>>,
     state: JobState,
 }
 
+/// The output of a job, available once its fence is signaled. See `Job::execute_async`.
+pub struct JobFuture<'j, 'a, T> {
+    job: &'j Job<'a, T>,
+    watch: Option<vkfence::FenceWatch<'j>>,
+}
+
+impl<'j, 'a, T> Future for JobFuture<'j, 'a, T> {
+    type Output = Option<Vec<Vec<T>>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.get_mut();
+        let job = future.job;
+        if job.status() != JobStatus::EXECUTING {
+            return Poll::Ready(job.get_output());
+        }
+
+        let state = &job.state;
+        let watch = future.watch.get_or_insert_with(|| {
+            state
+                .vulkan
+                .fence_waiter()
+                .watch(state.fence.as_ref().unwrap())
+        });
+        match watch.poll(cx.waker()) {
+            Some(_) => Poll::Ready(job.get_output()),
+            None => Poll::Pending,
+        }
+    }
+}
+
 pub struct JobState {
-    timing: JobTimingsBuilder,
+    timing: Mutex<JobTimingsBuilder>,
+    done: AtomicBool,
     fence: Option<vkfence::VkFence>,
+    /// Set to `JOB_DONE` by the GPU when the job is done, if the device has timeline semaphores.
+    semaphore: Option<Arc<vksemaphore::VkTimelineSemaphore>>,
     memory: Option<vkmem::VkMem>,
     buffers: Vec<vkmem::VkBuffer>,
-    vulkan: Rc<vkstate::VulkanState>,
+    uniform_buffers: Vec<vkmem::VkBuffer>,
+    views: Vec<vkmem::VkBufferView>,
+    images: Vec<vkimage::VkImage>,
+    image_views: Vec<vkimage::VkImageView>,
+    samplers: Vec<vkimage::VkSampler>,
+    /// Where the pixels of each image are uploaded from and downloaded to.
+    image_staging: Vec<vkmem::VkBuffer>,
+    // Everything the command buffers use must live until the job is done.
+    // The command pool belongs to the job, so each thread records in its own pool.
+    cmd_pool: Option<vkcmd::VkCmdPool>,
+    descriptors: Vec<vkdescriptor::VkDescriptor>,
+    pipelines: Vec<vkpipeline::VkComputePipeline>,
+    shaders: Vec<Arc<RwLock<vkshader::VkShader>>>,
+    vulkan: Arc<vkstate::VulkanState>,
+}
+
+impl Drop for JobState {
+    fn drop(&mut self) {
+        // The GPU may still use the resources of the job.
+        if let Some(fence) = &self.fence {
+            fence.wait(u64::MAX);
+        }
+    }
 }
 
 pub struct JobBuilder<'a, T> {
     inputs: Vec<(BindPoint, &'a Vec<T>)>,
     buffers: Vec<(BindPoint, usize)>,
-    shaders: Vec<&'a PathBuf>,
-    dispatch: Vec<(u32, u32, u32)>,
+    device_buffers: Vec<(BindPoint, &'a vkmem::VkBuffer)>,
+    uniforms: Vec<(BindPoint, Vec<u8>)>,
+    images: Vec<JobImage<'a, T>>,
+    shaders: Vec<ShaderSource<'a>>,
+    passes: Vec<JobPass>,
+    queue: usize,
+    features: Vec<Feature>,
+}
+
+impl<'a, T> Default for JobBuilder<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a, T> JobBuilder<'a, T> {
@@ -172,13 +404,143 @@ impl<'a, T> JobBuilder<'a, T> {
         JobBuilder {
             inputs: Vec::new(),
             buffers: Vec::new(),
+            device_buffers: Vec::new(),
+            uniforms: Vec::new(),
+            images: Vec::new(),
             shaders: Vec::new(),
-            dispatch: Vec::new(),
+            passes: Vec::new(),
+            queue: 0,
+            features: Vec::new(),
+        }
+    }
+
+    pub fn add_buffer(self, data: &'a Vec<T>, set: u32, bind: u32) -> JobBuilder<'a, T> {
+        self.add_buffer_as(data, BufferKind::Storage, set, bind)
+    }
+
+    /// Bind `data` as `kind`. It is part of the output of the job like any other buffer,
+    /// even if the shader can not write it.
+    pub fn add_buffer_as(
+        mut self,
+        data: &'a Vec<T>,
+        kind: BufferKind,
+        set: u32,
+        bind: u32,
+    ) -> JobBuilder<'a, T> {
+        self.inputs
+            .push((BindPoint::with_kind(set, bind, kind), data));
+        self
+    }
+
+    /// Bind `data` as an `imageBuffer` of texels of `format`.
+    pub fn add_texel_buffer(
+        self,
+        data: &'a Vec<T>,
+        format: vk::Format,
+        set: u32,
+        bind: u32,
+    ) -> JobBuilder<'a, T> {
+        self.add_buffer_as(data, BufferKind::StorageTexel(format), set, bind)
+    }
+
+    /// Bind `data` as a read only `textureBuffer` of texels of `format`.
+    pub fn add_uniform_texel_buffer(
+        self,
+        data: &'a Vec<T>,
+        format: vk::Format,
+        set: u32,
+        bind: u32,
+    ) -> JobBuilder<'a, T> {
+        self.add_buffer_as(data, BufferKind::UniformTexel(format), set, bind)
+    }
+
+    /// Bind an `image2D` of `format`, of `extent` (width, height) pixels, filled with `pixels`.
+    /// The pixels are tightly packed rows, with as many `T` per pixel as the format needs,
+    /// e.g. 4 `u8` for `R8G8B8A8_UNORM` or 4 `f32` for `R32G32B32A32_SFLOAT`.
+    /// The image is downloaded after the job, see `Job::get_image_output`.
+    pub fn add_storage_image(
+        self,
+        pixels: &'a Vec<T>,
+        extent: (u32, u32),
+        format: vk::Format,
+        set: u32,
+        bind: u32,
+    ) -> JobBuilder<'a, T> {
+        self.add_image(Some(pixels), ImageKind::Storage, extent, format, set, bind)
+    }
+
+    /// Bind an `image2D` the shaders only write, downloaded after the job.
+    pub fn add_output_image(
+        self,
+        extent: (u32, u32),
+        format: vk::Format,
+        set: u32,
+        bind: u32,
+    ) -> JobBuilder<'a, T> {
+        self.add_image(None, ImageKind::Storage, extent, format, set, bind)
+    }
+
+    /// Bind a `sampler2D` of `format` filled with `pixels`, read through `sampler`.
+    pub fn add_sampled_image(
+        self,
+        pixels: &'a Vec<T>,
+        extent: (u32, u32),
+        format: vk::Format,
+        sampler: vkimage::SamplerConfig,
+        set: u32,
+        bind: u32,
+    ) -> JobBuilder<'a, T> {
+        self.add_image(
+            Some(pixels),
+            ImageKind::Sampled(sampler),
+            extent,
+            format,
+            set,
+            bind,
+        )
+    }
+
+    fn add_image(
+        mut self,
+        pixels: Option<&'a Vec<T>>,
+        kind: ImageKind,
+        extent: (u32, u32),
+        format: vk::Format,
+        set: u32,
+        bind: u32,
+    ) -> JobBuilder<'a, T> {
+        if let Some(pixels) = pixels {
+            let size = extent.0 as u64 * extent.1 as u64 * vkimage::format_size(format);
+            let given = std::mem::size_of_val(pixels.as_slice()) as u64;
+            assert_eq!(
+                given, size,
+                "[ERR] A {}x{} image of format {:?} needs {} bytes of pixels, not {}.",
+                extent.0, extent.1, format, size, given
+            );
         }
+        self.images.push(JobImage {
+            set,
+            bind,
+            kind,
+            pixels,
+            width: extent.0,
+            height: extent.1,
+            format,
+        });
+        self
     }
 
-    pub fn add_buffer(mut self, data: &'a Vec<T>, set: u32, bind: u32) -> JobBuilder<'a, T> {
-        self.inputs.push((BindPoint::new(set, bind), data));
+    /// Bind a copy of `block` as a std140 uniform block, typically the parameters of the shaders.
+    /// It is not part of the output of the job. `U` must follow the std140 layout,
+    /// e.g. a `#[repr(C)]` struct padding its `vec3`s to 16 bytes.
+    pub fn add_uniform<U: Copy>(mut self, block: &U, set: u32, bind: u32) -> JobBuilder<'a, T> {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(block as *const U as *const u8, std::mem::size_of::<U>())
+        };
+        self.uniforms.push((
+            BindPoint::with_kind(set, bind, BufferKind::Uniform),
+            bytes.to_vec(),
+        ));
         self
     }
 
@@ -187,30 +549,199 @@ impl<'a, T> JobBuilder<'a, T> {
         self
     }
 
+    /// Bind a buffer that already lives in device memory, whatever its element type.
+    /// The job neither uploads nor downloads it, so it is not part of the output,
+    /// but its content stays available to the next jobs using it.
+    pub fn add_device_buffer<U>(
+        mut self,
+        buffer: &'a Buffer<U>,
+        set: u32,
+        bind: u32,
+    ) -> JobBuilder<'a, T> {
+        self.device_buffers
+            .push((BindPoint::new(set, bind), buffer.raw()));
+        self
+    }
+
     pub fn add_shader(mut self, shader: &'a PathBuf) -> JobBuilder<'a, T> {
-        self.shaders.push(shader);
+        self.shaders.push(ShaderSource::File(shader));
+        self
+    }
+
+    /// Add a shader from SPIR-V bytes, e.g. one embedded with `include_bytes!`.
+    pub fn add_spirv(mut self, spirv: &'a [u8]) -> JobBuilder<'a, T> {
+        self.shaders.push(ShaderSource::Spirv(spirv));
+        self
+    }
+
+    /// Dispatch the shader added at the same position as this dispatch.
+    pub fn add_dispatch(self, dispatch: (u32, u32, u32)) -> JobBuilder<'a, T> {
+        let shader = self.passes.len();
+        self.add_pass(shader, dispatch, &[])
+    }
+
+    /// Dispatch the `shader`-th shader with some push constants.
+    /// The same shader can be used by several passes, its pipeline is only created once.
+    /// `build` panics if there is no `shader`-th shader.
+    pub fn add_pass(
+        mut self,
+        shader: usize,
+        dispatch: (u32, u32, u32),
+        constants: &[u32],
+    ) -> JobBuilder<'a, T> {
+        self.passes.push(JobPass {
+            shader,
+            dispatch,
+            constants: constants.to_vec(),
+            invocations: None,
+        });
+        self
+    }
+
+    /// Dispatch the shader added at the same position as this dispatch over at least
+    /// `n_x * n_y * n_z` invocations. The number of work groups is computed from the
+    /// local size of the shader when the job is built, so the shader should not assume
+    /// every invocation has an element to work on.
+    pub fn dispatch_for(self, n_x: u32, n_y: u32, n_z: u32) -> JobBuilder<'a, T> {
+        let shader = self.passes.len();
+        self.add_pass_for(shader, (n_x, n_y, n_z), &[])
+    }
+
+    /// Like `add_pass`, with a number of invocations instead of a number of work groups.
+    pub fn add_pass_for(
+        mut self,
+        shader: usize,
+        invocations: (u32, u32, u32),
+        constants: &[u32],
+    ) -> JobBuilder<'a, T> {
+        self.passes.push(JobPass {
+            shader,
+            dispatch: (0, 0, 0),
+            constants: constants.to_vec(),
+            invocations: Some(invocations),
+        });
+        self
+    }
+
+    /// Submit the job to the `index`-th compute queue of the vulkan state instead of the first one.
+    /// Jobs on different queues are not ordered, use `Job::after` when one needs the other.
+    pub fn on_queue(mut self, index: usize) -> JobBuilder<'a, T> {
+        self.queue = index;
         self
     }
 
-    pub fn add_dispatch(mut self, dispatch: (u32, u32, u32)) -> JobBuilder<'a, T> {
-        self.dispatch.push(dispatch);
+    /// The shaders of the job need `feature`, which is checked when the job is built.
+    pub fn require_feature(mut self, feature: Feature) -> JobBuilder<'a, T> {
+        self.features.push(feature);
         self
     }
 
-    pub fn build(self, vulkan: Rc<vkstate::VulkanState>) -> Job<'a, T> {
+    pub fn build(mut self, vulkan: Arc<vkstate::VulkanState>) -> Job<'a, T> {
+        vulkan.compute_queue(self.queue);
+        for feature in &self.features {
+            if !vulkan.has_feature(*feature) {
+                panic!(
+                    "[ERR] The job needs the device feature {}, which is not enabled. \
+                     Ask for it with `DeviceBuilder::require_feature` or `optional_feature`.",
+                    feature
+                );
+            }
+        }
+        for (index, pass) in self.passes.iter().enumerate() {
+            assert!(
+                pass.shader < self.shaders.len(),
+                "[ERR] Pass {} uses shader {}, but the job only has {} shaders.",
+                index,
+                pass.shader,
+                self.shaders.len()
+            );
+        }
+        self.resolve_dispatches(&vulkan);
+        let semaphore = if vulkan.timeline_semaphore {
+            Some(Arc::new(vksemaphore::VkTimelineSemaphore::new(
+                vulkan.clone(),
+                0,
+            )))
+        } else {
+            None
+        };
         let state = JobState {
             fence: None,
+            semaphore,
             buffers: Vec::new(),
+            uniform_buffers: Vec::new(),
+            views: Vec::new(),
+            images: Vec::new(),
+            image_views: Vec::new(),
+            samplers: Vec::new(),
+            image_staging: Vec::new(),
             memory: None,
-            timing: JobTimingsBuilder::new(),
-            vulkan: vulkan,
+            cmd_pool: None,
+            descriptors: Vec::new(),
+            pipelines: Vec::new(),
+            shaders: Vec::new(),
+            timing: Mutex::new(JobTimingsBuilder::new()),
+            done: AtomicBool::new(false),
+            vulkan,
         };
         Job {
             inputs: self.inputs,
             buffers: self.buffers,
+            device_buffers: self.device_buffers,
+            uniforms: self.uniforms,
+            images: self.images,
             shaders: self.shaders,
-            dispatch: self.dispatch,
-            state: state,
+            passes: self.passes,
+            queue: self.queue,
+            dependencies: Vec::new(),
+            state,
+        }
+    }
+}
+
+impl<'a, T> JobBuilder<'a, T> {
+    /// Turn the invocations of the passes added with `dispatch_for` into work groups.
+    fn resolve_dispatches(&mut self, vulkan: &vkstate::VulkanState) {
+        if self.passes.iter().all(|pass| pass.invocations.is_none()) {
+            return;
+        }
+        let max_groups = vulkan.info.limits.max_compute_work_group_count;
+
+        let shaders = &self.shaders;
+        let mut local_sizes: Vec<Option<[u32; 3]>> = vec![None; shaders.len()];
+        for (pass_index, pass) in self.passes.iter_mut().enumerate() {
+            let invocations = match pass.invocations {
+                Some(invocations) => invocations,
+                None => continue,
+            };
+            let source = &shaders[pass.shader];
+            let local_size = *local_sizes[pass.shader].get_or_insert_with(|| {
+                vkshader::local_size(&source.bytecode(), "main").unwrap_or_else(|| {
+                    panic!(
+                        "[ERR] Could not find the local size of shader {} in its SPIR-V.",
+                        pass.shader
+                    )
+                })
+            });
+
+            let invocations = [invocations.0, invocations.1, invocations.2];
+            let mut groups = [0; 3];
+            for axis in 0..3 {
+                groups[axis] = invocations[axis].div_ceil(local_size[axis]);
+                if groups[axis] > max_groups[axis] {
+                    panic!(
+                        "[ERR] Pass {} needs {} work groups on {} ({} invocations with a local size of {}), \
+                         but the device allows at most {}.",
+                        pass_index,
+                        groups[axis],
+                        ["x", "y", "z"][axis],
+                        invocations[axis],
+                        local_size[axis],
+                        max_groups[axis]
+                    );
+                }
+            }
+            pass.dispatch = (groups[0], groups[1], groups[2]);
         }
     }
 }
@@ -218,14 +749,40 @@ impl<'a, T> JobBuilder<'a, T> {
 // TODO: Correctly manage set binding.
 // For the moment, all binding will use the set 0 neverminding the actual value in BindPoint
 impl<'a, T> Job<'a, T> {
+    /// Make the job wait on the GPU for `other` to be done before starting,
+    /// without blocking the host. Both jobs can be executed in any order,
+    /// but `other` has to be executed at some point for this one to finish.
+    pub fn after<U>(&mut self, other: &Job<'_, U>) -> &mut Job<'a, T> {
+        assert_eq!(
+            self.status(),
+            JobStatus::INIT,
+            "[ERR] A dependency must be added before the job is executed."
+        );
+        let semaphore = other
+            .state
+            .semaphore
+            .as_ref()
+            .expect("[ERR] The device does not support timeline semaphores.");
+        self.dependencies.push(semaphore.clone());
+        self
+    }
+
+    /// The timeline semaphore set to `JOB_DONE` when the job is done,
+    /// if the device supports timeline semaphores.
+    pub fn semaphore(&self) -> Option<&vksemaphore::VkTimelineSemaphore> {
+        self.state.semaphore.as_deref()
+    }
+
     pub fn execute(&mut self) {
         let inputs = &self.inputs;
         let ro_buffers = &self.buffers;
+        let uniforms = &self.uniforms;
+        let images = &self.images;
         let shaders = &self.shaders;
-        let dispatch = &self.dispatch;
+        let passes = &self.passes;
 
         // Memory init.
-        self.state.timing = self.state.timing.start_upload();
+        self.update_timing(JobTimingsBuilder::start_upload);
         let mut buffer_sizes: Vec<u64> = inputs
             .iter()
             .map(|v| (v.1.len() * std::mem::size_of::<T>()) as u64)
@@ -233,62 +790,207 @@ impl<'a, T> Job<'a, T> {
         for s in ro_buffers {
             buffer_sizes.push((s.1 * std::mem::size_of::<T>()) as u64);
         }
+        for u in uniforms {
+            buffer_sizes.push(u.1.len() as u64);
+        }
+        for image in images {
+            buffer_sizes.push(
+                image.width as u64 * image.height as u64 * vkimage::format_size(image.format),
+            );
+        }
+        // In the order of the buffers the shaders see: the ones owned by the job,
+        // then the device buffers, then the uniform blocks.
+        let points: Vec<BindPoint> = inputs
+            .iter()
+            .map(|v| v.0)
+            .chain(ro_buffers.iter().map(|s| s.0))
+            .chain(self.device_buffers.iter().map(|b| b.0))
+            .chain(uniforms.iter().map(|u| u.0))
+            .collect();
+        // The staging buffers of the images are only copied from and to.
+        let usages = points[..inputs.len() + ro_buffers.len()]
+            .iter()
+            .chain(&points[points.len() - uniforms.len()..])
+            .map(|point| point.kind.usage())
+            .chain(images.iter().map(|_| vk::BufferUsageFlags::empty()));
 
-        self.state.buffers = buffer_sizes
+        let mut buffers: Vec<vkmem::VkBuffer> = buffer_sizes
             .iter()
-            .map(|size| vkmem::VkBuffer::new(self.state.vulkan.clone(), *size))
+            .zip(usages)
+            .map(|(size, usage)| {
+                vkmem::VkBuffer::with_usage(self.state.vulkan.clone(), *size, usage)
+            })
             .collect();
-        let (mem_size, offsets) =
-            vkmem::compute_non_overlapping_buffer_alignment(&self.state.buffers);
-        self.state.memory = Some(
-            vkmem::VkMem::find_mem(self.state.vulkan.clone(), mem_size)
-                .expect("[ERR] Could not find a memory type fitting our need."),
-        );
+        let (mem_size, offsets) = vkmem::compute_non_overlapping_buffer_alignment(&buffers);
+        if mem_size > 0 {
+            self.state.memory = Some(
+                vkmem::VkMem::find_mem(self.state.vulkan.clone(), mem_size)
+                    .expect("[ERR] Could not find a memory type fitting our need."),
+            );
+        }
 
-        for i in 0..self.state.buffers.len() {
-            let mbuf = self.state.buffers.get_mut(i).unwrap();
-            mbuf.bind(self.state.memory.as_ref().unwrap().mem, offsets[i]);
+        let outputs = inputs.len() + ro_buffers.len();
+        let staging = outputs + uniforms.len();
+        for (i, mbuf) in buffers.iter_mut().enumerate() {
+            let memory = self.state.memory.as_ref().unwrap();
+            mbuf.bind(memory.mem, offsets[i]);
             if i < inputs.len() {
+                memory.map_buffer(inputs[i].1, mbuf);
+            } else if i >= staging {
+                if let Some(pixels) = images[i - staging].pixels {
+                    memory.map_buffer(pixels, mbuf);
+                }
+            } else if i >= outputs {
+                memory.map_buffer(&uniforms[i - outputs].1, mbuf);
+            }
+        }
+        self.state.image_staging = buffers.split_off(staging);
+        self.state.uniform_buffers = buffers.split_off(outputs);
+        self.state.buffers = buffers;
+
+        for image in images {
+            let vk_image = vkimage::VkImage::new(
+                self.state.vulkan.clone(),
+                image.width,
+                image.height,
+                image.format,
+                image.kind.usage(),
+            );
+            self.state.image_views.push(vkimage::VkImageView::new(
+                self.state.vulkan.clone(),
+                &vk_image,
+            ));
+            self.state.images.push(vk_image);
+            if let ImageKind::Sampled(config) = image.kind {
+                if config.filter == vk::Filter::LINEAR
+                    && !vkimage::supports_linear_filter(&self.state.vulkan, image.format)
+                {
+                    panic!(
+                        "[ERR] The device can not filter images of format {:?} linearly.",
+                        image.format
+                    );
+                }
                 self.state
-                    .memory
-                    .as_ref()
-                    .unwrap()
-                    .map_buffer(inputs[i].1, mbuf);
+                    .samplers
+                    .push(vkimage::VkSampler::new(self.state.vulkan.clone(), &config));
             }
         }
+        let mut samplers = self.state.samplers.iter();
+        let image_infos: Vec<Vec<vk::DescriptorImageInfo>> = images
+            .iter()
+            .zip(&self.state.image_views)
+            .map(|(image, view)| {
+                let sampler = match image.kind {
+                    ImageKind::Storage => vk::Sampler::null(),
+                    ImageKind::Sampled(_) => samplers.next().unwrap().sampler,
+                };
+                vec![vk::DescriptorImageInfo::builder()
+                    .sampler(sampler)
+                    .image_view(view.view)
+                    .image_layout(image.kind.layout())
+                    .build()]
+            })
+            .collect();
 
-        self.state.timing = self.state.timing.stop_upload();
+        self.update_timing(JobTimingsBuilder::stop_upload);
+
+        let bound: Vec<&vkmem::VkBuffer> = self
+            .state
+            .buffers
+            .iter()
+            .chain(self.device_buffers.iter().map(|b| b.1))
+            .chain(self.state.uniform_buffers.iter())
+            .collect();
+        let bound_buffers: Vec<(vk::Buffer, u64)> =
+            bound.iter().map(|b| (b.buffer, b.size)).collect();
+        // Texel buffers are seen through a view of their format.
+        let mut views: Vec<Vec<vk::BufferView>> = Vec::with_capacity(bound.len());
+        for (buffer, point) in bound.iter().zip(&points) {
+            match point.kind.texel_format() {
+                Some((format, feature)) => {
+                    let view = vkmem::VkBufferView::new(
+                        self.state.vulkan.clone(),
+                        buffer,
+                        format,
+                        feature,
+                    );
+                    views.push(vec![view.view]);
+                    self.state.views.push(view);
+                }
+                None => views.push(Vec::new()),
+            }
+        }
+        let buffer_infos: Vec<Vec<vk::DescriptorBufferInfo>> = bound_buffers
+            .iter()
+            .map(|(buffer, size)| {
+                vec![vk::DescriptorBufferInfo::builder()
+                    .buffer(*buffer)
+                    .offset(0)
+                    .range(*size)
+                    .build()]
+            })
+            .collect();
 
         // Shaders
-        self.state.timing = self.state.timing.start_shader();
-        let mut shad_vec: Vec<Rc<RefCell<vkshader::VkShader>>> = Vec::with_capacity(shaders.len());
+        self.update_timing(JobTimingsBuilder::start_shader);
+        let mut shad_vec: Vec<Arc<RwLock<vkshader::VkShader>>> = Vec::with_capacity(shaders.len());
         let mut shad_pip_vec: Vec<vkpipeline::VkComputePipeline> =
             Vec::with_capacity(shaders.len());
         let mut shad_pipeline_layout: Vec<vk::PipelineLayout> = Vec::with_capacity(shaders.len());
         let mut shad_desc_vec: Vec<vkdescriptor::VkDescriptor> = Vec::with_capacity(shaders.len());
         let mut shad_desc_set: Vec<vkdescriptor::VkWriteDescriptor> =
             Vec::with_capacity(shaders.len());
-        for path in shaders {
-            shad_vec.push(Rc::new(RefCell::new(vkshader::VkShader::new(
-                self.state.vulkan.clone(),
-                path,
-                CString::new("main").unwrap(),
-            ))));
-        }
-        for shader in shad_vec.iter_mut() {
-            for i in 0..self.state.buffers.len() {
-                shader.borrow_mut().add_layout_binding(
-                    i as u32,
+        for source in shaders {
+            let shader = match source {
+                ShaderSource::File(path) => vkshader::VkShader::new(
+                    self.state.vulkan.clone(),
+                    path,
+                    CString::new("main").unwrap(),
+                ),
+                ShaderSource::Spirv(spirv) => vkshader::VkShader::from_spirv(
+                    self.state.vulkan.clone(),
+                    spirv,
+                    CString::new("main").unwrap(),
+                ),
+            };
+            shad_vec.push(Arc::new(RwLock::new(shader)));
+        }
+        let bindings: Vec<(u32, vk::DescriptorType)> = points
+            .iter()
+            .map(|point| (point.bind, point.kind.descriptor_type()))
+            .chain(
+                images
+                    .iter()
+                    .map(|image| (image.bind, image.kind.descriptor_type())),
+            )
+            .collect();
+        for (shader_index, shader) in shad_vec.iter_mut().enumerate() {
+            let constants_size = passes
+                .iter()
+                .filter(|pass| pass.shader == shader_index)
+                .map(|pass| (pass.constants.len() * std::mem::size_of::<u32>()) as u32)
+                .max()
+                .unwrap_or(0);
+            if constants_size > 0 {
+                shader.write().unwrap().add_push_constant_range(
+                    0,
+                    constants_size,
+                    vk::ShaderStageFlags::COMPUTE,
+                );
+            }
+            for (bind, ty) in &bindings {
+                shader.write().unwrap().add_layout_binding(
+                    *bind,
                     1,
-                    vk::DescriptorType::STORAGE_BUFFER,
+                    *ty,
                     vk::ShaderStageFlags::COMPUTE,
                 );
             }
-            shader.borrow_mut().create_pipeline_layout();
-            shad_pipeline_layout.push(shader.borrow().pipeline.unwrap());
+            shader.write().unwrap().create_pipeline_layout();
+            shad_pipeline_layout.push(shader.read().unwrap().pipeline.unwrap());
             shad_pip_vec.push(vkpipeline::VkComputePipeline::new(
                 self.state.vulkan.clone(),
-                &shader.borrow(),
+                &shader.read().unwrap(),
             ));
             shad_desc_vec.push(vkdescriptor::VkDescriptor::new(
                 self.state.vulkan.clone(),
@@ -299,70 +1001,100 @@ impl<'a, T> Job<'a, T> {
             ));
         }
 
+        let mut descriptor_types: Vec<vk::DescriptorType> =
+            bindings.iter().map(|(_, ty)| *ty).collect();
+        descriptor_types.sort_by_key(|ty| ty.as_raw());
+        descriptor_types.dedup();
         for descriptor in shad_desc_vec.iter_mut() {
-            descriptor.add_pool_size(
-                self.state.buffers.len() as u32,
-                vk::DescriptorType::STORAGE_BUFFER,
-            );
+            for ty in &descriptor_types {
+                let count = bindings.iter().filter(|(_, t)| t == ty).count();
+                descriptor.add_pool_size(count as u32, *ty);
+            }
             descriptor.create_pool(1);
             descriptor.create_set();
         }
 
-        let mut n = 0;
-        for write_descriptor_set in shad_desc_set.iter_mut() {
+        for (n, write_descriptor_set) in shad_desc_set.iter_mut().enumerate() {
             let desc_set: vk::DescriptorSet = *shad_desc_vec[n].get_first_set().unwrap();
-            let mut buffers_nfos: Vec<Vec<vk::DescriptorBufferInfo>> = Vec::new();
-            for i in 0..self.state.buffers.len() {
-                write_descriptor_set.add_buffer(
-                    self.state.buffers[i].buffer,
-                    0,
-                    self.state.buffers[i].size,
-                );
-                buffers_nfos.push(vec![write_descriptor_set.buffer_descriptors[i]]);
-                write_descriptor_set.add_write_descriptors(
+            for (i, point) in points.iter().enumerate() {
+                let ty = point.kind.descriptor_type();
+                if views[i].is_empty() {
+                    write_descriptor_set.add_write_descriptors(
+                        desc_set,
+                        ty,
+                        &buffer_infos[i],
+                        point.bind,
+                        0,
+                    );
+                } else {
+                    write_descriptor_set
+                        .add_texel_write_descriptors(desc_set, ty, &views[i], point.bind, 0);
+                }
+            }
+            for (image, info) in images.iter().zip(&image_infos) {
+                write_descriptor_set.add_image_write_descriptors(
                     desc_set,
-                    vk::DescriptorType::STORAGE_BUFFER,
-                    &buffers_nfos[i],
-                    i as u32,
+                    image.kind.descriptor_type(),
+                    info,
+                    image.bind,
                     0,
                 );
             }
             write_descriptor_set.update_descriptors_sets();
-            n += 1;
         }
 
-        self.state.timing = self.state.timing.stop_shader();
+        self.update_timing(JobTimingsBuilder::stop_shader);
 
         // Command buffers
-        self.state.timing = self.state.timing.start_cmd();
-        let mut cmd_buffers: Vec<usize> = Vec::with_capacity(shaders.len());
+        self.update_timing(JobTimingsBuilder::start_cmd);
+        let mut cmd_buffers: Vec<usize> = Vec::with_capacity(passes.len());
         let mut cmd_pool = vkcmd::VkCmdPool::new(self.state.vulkan.clone());
 
-        for _ in 0..shaders.len() {
+        for _ in 0..passes.len() {
             cmd_buffers.push(cmd_pool.create_cmd_buffer(vk::CommandBufferLevel::PRIMARY));
         }
 
         for i in cmd_buffers {
+            let pass = &passes[i];
+            let s = pass.shader;
             cmd_pool.begin_cmd(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, i);
-            cmd_pool.bind_pipeline(shad_pip_vec[i].pipeline, vk::PipelineBindPoint::COMPUTE, i);
+            if i == 0 {
+                self.record_image_uploads(&mut cmd_pool, i);
+            }
+            cmd_pool.bind_pipeline(shad_pip_vec[s].pipeline, vk::PipelineBindPoint::COMPUTE, i);
             cmd_pool.bind_descriptor(
-                shad_pipeline_layout[i],
+                shad_pipeline_layout[s],
                 vk::PipelineBindPoint::COMPUTE,
-                &shad_desc_vec[i].set,
+                &shad_desc_vec[s].set,
                 i,
             );
+            if !pass.constants.is_empty() {
+                let constants: Vec<u8> = pass
+                    .constants
+                    .iter()
+                    .flat_map(|c| c.to_ne_bytes().to_vec())
+                    .collect();
+                cmd_pool.push_constants(
+                    shad_pipeline_layout[s],
+                    vk::ShaderStageFlags::COMPUTE,
+                    &constants,
+                    i,
+                );
+            }
 
-            let d = dispatch[i];
+            let d = pass.dispatch;
             cmd_pool.dispatch(d.0, d.1, d.2, i);
 
-            // Memory barrier
+            // Memory barrier, the next pass may read and write the same buffers again.
             let mut buffer_barrier: Vec<vk::BufferMemoryBarrier> = Vec::new();
-            for buffer in &self.state.buffers {
+            for (buffer, _) in &bound_buffers {
                 buffer_barrier.push(
                     vk::BufferMemoryBarrier::builder()
                         .src_access_mask(vk::AccessFlags::SHADER_WRITE)
-                        .dst_access_mask(vk::AccessFlags::SHADER_READ)
-                        .buffer(buffer.buffer)
+                        .dst_access_mask(
+                            vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+                        )
+                        .buffer(*buffer)
                         .size(vk::WHOLE_SIZE)
                         .build(),
                 );
@@ -378,21 +1110,120 @@ impl<'a, T> Job<'a, T> {
                     &[],
                 );
             }
+            for (image, vk_image) in images.iter().zip(&self.state.images) {
+                if image.kind == ImageKind::Storage {
+                    vk_image.transition(
+                        &mut cmd_pool,
+                        vk::ImageLayout::GENERAL,
+                        vk::ImageLayout::GENERAL,
+                        i,
+                    );
+                }
+            }
+            if i == passes.len() - 1 {
+                self.record_image_downloads(&mut cmd_pool, i);
+            }
 
             cmd_pool.end_cmd(i);
         }
-        self.state.timing = self.state.timing.stop_cmd();
+        self.update_timing(JobTimingsBuilder::stop_cmd);
 
         // Execution
         self.state.fence = Some(vkfence::VkFence::new(self.state.vulkan.clone(), false));
-        self.state.timing = self.state.timing.start_execution();
-        let queue = unsafe {
-            self.state
-                .vulkan
-                .device
-                .get_device_queue(self.state.vulkan.queue_family_index, 0)
-        };
-        cmd_pool.submit(queue, Some(self.state.fence.as_ref().unwrap().fence));
+        self.update_timing(JobTimingsBuilder::start_execution);
+        {
+            let waits: Vec<(vk::Semaphore, u64)> = self
+                .dependencies
+                .iter()
+                .map(|dependency| (dependency.semaphore, JOB_DONE))
+                .collect();
+            let signals: Vec<(vk::Semaphore, u64)> = self
+                .state
+                .semaphore
+                .iter()
+                .map(|semaphore| (semaphore.semaphore, JOB_DONE))
+                .collect();
+            let queue = self.state.vulkan.compute_queue(self.queue).lock().unwrap();
+            cmd_pool.submit_timeline(
+                *queue,
+                &waits,
+                &signals,
+                Some(self.state.fence.as_ref().unwrap().fence),
+            );
+        }
+
+        self.state.cmd_pool = Some(cmd_pool);
+        self.state.descriptors = shad_desc_vec;
+        self.state.pipelines = shad_pip_vec;
+        self.state.shaders = shad_vec;
+    }
+
+    /// Copy the pixels of the images from their staging buffer, and move every image
+    /// to the layout the shaders use it in.
+    fn record_image_uploads(&self, cmd_pool: &mut vkcmd::VkCmdPool, index: usize) {
+        let staged = self.state.images.iter().zip(&self.state.image_staging);
+        for (image, (vk_image, staging)) in self.images.iter().zip(staged) {
+            let mut layout = vk::ImageLayout::UNDEFINED;
+            if image.pixels.is_some() {
+                vk_image.transition(
+                    cmd_pool,
+                    layout,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    index,
+                );
+                vk_image.copy_from_buffer(cmd_pool, staging, index);
+                layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
+            }
+            vk_image.transition(cmd_pool, layout, image.kind.layout(), index);
+        }
+    }
+
+    /// Copy the storage images to their staging buffer, for the host to read them.
+    fn record_image_downloads(&self, cmd_pool: &mut vkcmd::VkCmdPool, index: usize) {
+        let staged = self.state.images.iter().zip(&self.state.image_staging);
+        let mut barriers: Vec<vk::BufferMemoryBarrier> = Vec::new();
+        for (image, (vk_image, staging)) in self.images.iter().zip(staged) {
+            if image.kind != ImageKind::Storage {
+                continue;
+            }
+            vk_image.transition(
+                cmd_pool,
+                vk::ImageLayout::GENERAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                index,
+            );
+            vk_image.copy_to_buffer(cmd_pool, staging, index);
+            barriers.push(
+                vk::BufferMemoryBarrier::builder()
+                    .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                    .dst_access_mask(vk::AccessFlags::HOST_READ)
+                    .buffer(staging.buffer)
+                    .size(vk::WHOLE_SIZE)
+                    .build(),
+            );
+        }
+        if !barriers.is_empty() {
+            cmd_pool.buffer_barriers(
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::HOST,
+                &barriers,
+                index,
+            );
+        }
+    }
+
+    /// Execute the job if it was not yet, and return a future resolving to its output
+    /// once it is done. The fence is waited on by the fence waiter thread of the
+    /// vulkan state, so awaiting a job never blocks the executor.
+    pub fn execute_async(&mut self) -> JobFuture<'_, 'a, T> {
+        if self.status() == JobStatus::INIT {
+            self.execute();
+        }
+
+        JobFuture {
+            job: self,
+            watch: None,
+        }
     }
 
     pub fn status(&self) -> JobStatus {
@@ -402,29 +1233,65 @@ impl<'a, T> Job<'a, T> {
 
         let r = self.state.fence.as_ref().map(|fence| fence.status());
         match r.unwrap() {
-            vkfence::FenceStates::SIGNALED => JobStatus::SUCESS,
+            vkfence::FenceStates::SIGNALED => {
+                // The first time we see the job done, we stop the execution timer.
+                if !self.state.done.swap(true, Ordering::AcqRel) {
+                    self.update_timing(JobTimingsBuilder::stop_execution);
+                }
+                JobStatus::SUCESS
+            }
             vkfence::FenceStates::UNSIGNALED => JobStatus::EXECUTING,
             _ => JobStatus::FAILURE,
         }
     }
 
+    /// Content of every buffer, the buffers added with `add_buffer` first,
+    /// then the ones added with `add_ro_buffer`, each in the order they were added.
     pub fn get_output(&self) -> Option<Vec<Vec<T>>> {
         if self.status() != JobStatus::SUCESS {
             return None;
         }
 
+        self.update_timing(JobTimingsBuilder::start_download);
         let output: Vec<Vec<T>> = self
             .state
             .buffers
             .iter()
             .map(|buf| self.state.memory.as_ref().unwrap().get_buffer(buf))
             .collect();
+        self.update_timing(JobTimingsBuilder::stop_download);
+
+        Some(output)
+    }
+
+    /// Pixels of every storage image, in the order they were added.
+    pub fn get_image_output(&self) -> Option<Vec<Vec<T>>> {
+        if self.status() != JobStatus::SUCESS {
+            return None;
+        }
+
+        self.update_timing(JobTimingsBuilder::start_download);
+        let memory = self.state.memory.as_ref().unwrap();
+        let output: Vec<Vec<T>> = self
+            .images
+            .iter()
+            .zip(&self.state.image_staging)
+            .filter(|(image, _)| image.kind == ImageKind::Storage)
+            .map(|(_, staging)| memory.get_buffer(staging))
+            .collect();
+        self.update_timing(JobTimingsBuilder::stop_download);
 
         Some(output)
     }
 
     pub fn get_timing(&self) -> JobTimings {
-        self.state.timing.build()
+        self.state.timing.lock().unwrap().build()
+    }
+
+    /// Apply one of the start/stop methods of `JobTimingsBuilder` to the job timings.
+    fn update_timing(&self, timer: fn(JobTimingsBuilder) -> JobTimingsBuilder) {
+        let mut timing = self.state.timing.lock().unwrap();
+        *timing = timer(*timing);
     }
 
     pub fn wait_until_idle(&self, timeout: u64) -> JobStatus {
@@ -0,0 +1,198 @@
+use crate::high::buffer::Buffer;
+use crate::high::job::{JobBuilder, JobStatus, JobTimings};
+use crate::low::vkstate::VulkanState;
+use std::sync::Arc;
+
+const SGEMM: &[u8] = include_bytes!("../../../shaders/bin/linalg/sgemm.cs.spirv");
+const TRANSPOSE: &[u8] = include_bytes!("../../../shaders/bin/linalg/transpose.cs.spirv");
+const GEMV: &[u8] = include_bytes!("../../../shaders/bin/linalg/gemv.cs.spirv");
+
+/// Size of the block of C computed by one work group of the SGEMM shader.
+const SGEMM_TILE: usize = 64;
+/// Size of the block transposed by one work group of the transpose shader.
+const TRANSPOSE_TILE: usize = 32;
+/// Maximum number of work groups we dispatch on one axis.
+const MAX_GROUPS: usize = 65535;
+
+/// A row-major `f32` matrix living in device memory.
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    buffer: Buffer<f32>,
+}
+
+impl Matrix {
+    /// Create a matrix without initializing its content.
+    pub fn uninit(vulkan: Arc<VulkanState>, rows: usize, cols: usize) -> Matrix {
+        assert!(rows * cols > 0, "[ERR] A matrix can not be empty.");
+        Matrix {
+            rows,
+            cols,
+            buffer: Buffer::uninit(vulkan, rows * cols),
+        }
+    }
+
+    /// Create a matrix from its row-major content.
+    pub fn new(vulkan: Arc<VulkanState>, rows: usize, cols: usize, data: &[f32]) -> Matrix {
+        let mut matrix = Matrix::uninit(vulkan, rows, cols);
+        matrix.upload(data);
+        matrix
+    }
+
+    pub fn zeros(vulkan: Arc<VulkanState>, rows: usize, cols: usize) -> Matrix {
+        Matrix::new(vulkan, rows, cols, &vec![0.0; rows * cols])
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The device buffer holding the matrix, to use it in other jobs.
+    pub fn buffer(&self) -> &Buffer<f32> {
+        &self.buffer
+    }
+
+    /// Replace the content of the matrix with `data`, in row-major order.
+    pub fn upload(&mut self, data: &[f32]) {
+        self.buffer.write(data);
+    }
+
+    /// Content of the matrix in row-major order.
+    pub fn to_vec(&self) -> Vec<f32> {
+        self.buffer.read()
+    }
+}
+
+/// Number of floating point operations done by `sgemm` for a (m x k) * (k x n) product.
+pub fn sgemm_flop(m: usize, n: usize, k: usize) -> f64 {
+    2.0 * m as f64 * n as f64 * k as f64
+}
+
+/// C = alpha * A * B + beta * C.
+/// When beta is 0, the previous content of C is ignored.
+pub fn sgemm(alpha: f32, a: &Matrix, b: &Matrix, beta: f32, c: &mut Matrix) -> JobTimings {
+    assert_eq!(a.cols, b.rows, "[ERR] A and B can not be multiplied.");
+    assert!(
+        c.rows == a.rows && c.cols == b.cols,
+        "[ERR] C does not have the size of A * B."
+    );
+
+    let groups = dispatch(
+        &a.buffer.vulkan(),
+        "SGEMM",
+        [b.cols.div_ceil(SGEMM_TILE), a.rows.div_ceil(SGEMM_TILE), 1],
+    );
+    let job = JobBuilder::<f32>::new()
+        .add_device_buffer(&a.buffer, 0, 0)
+        .add_device_buffer(&b.buffer, 0, 1)
+        .add_device_buffer(&c.buffer, 0, 2)
+        .add_spirv(SGEMM)
+        .add_pass(
+            0,
+            groups,
+            &[
+                a.rows as u32,
+                b.cols as u32,
+                a.cols as u32,
+                alpha.to_bits(),
+                beta.to_bits(),
+            ],
+        );
+    run(job, a.buffer.vulkan())
+}
+
+/// Write the transpose of `a` in `out`.
+pub fn transpose(a: &Matrix, out: &mut Matrix) -> JobTimings {
+    assert!(
+        out.rows == a.cols && out.cols == a.rows,
+        "[ERR] The output does not have the size of the transposed matrix."
+    );
+
+    let groups = dispatch(
+        &a.buffer.vulkan(),
+        "transpose",
+        [
+            a.cols.div_ceil(TRANSPOSE_TILE),
+            a.rows.div_ceil(TRANSPOSE_TILE),
+            1,
+        ],
+    );
+    let job = JobBuilder::<f32>::new()
+        .add_device_buffer(&a.buffer, 0, 0)
+        .add_device_buffer(&out.buffer, 0, 1)
+        .add_spirv(TRANSPOSE)
+        .add_pass(0, groups, &[a.rows as u32, a.cols as u32]);
+    run(job, a.buffer.vulkan())
+}
+
+/// y = alpha * A * x + beta * y, where x and y are column vectors.
+/// When beta is 0, the previous content of y is ignored.
+pub fn gemv(alpha: f32, a: &Matrix, x: &Matrix, beta: f32, y: &mut Matrix) -> JobTimings {
+    assert!(
+        x.cols == 1 && x.rows == a.cols,
+        "[ERR] x must be a column vector of the size of a row of A."
+    );
+    assert!(
+        y.cols == 1 && y.rows == a.rows,
+        "[ERR] y must be a column vector of the size of a column of A."
+    );
+
+    let x_groups = a.rows.clamp(1, MAX_GROUPS);
+    let groups = dispatch(
+        &a.buffer.vulkan(),
+        "GEMV",
+        [x_groups, a.rows.div_ceil(x_groups), 1],
+    );
+    let job = JobBuilder::<f32>::new()
+        .add_device_buffer(&a.buffer, 0, 0)
+        .add_device_buffer(&x.buffer, 0, 1)
+        .add_device_buffer(&y.buffer, 0, 2)
+        .add_spirv(GEMV)
+        .add_pass(
+            0,
+            groups,
+            &[
+                a.rows as u32,
+                a.cols as u32,
+                alpha.to_bits(),
+                beta.to_bits(),
+            ],
+        );
+    run(job, a.buffer.vulkan())
+}
+
+/// Turn the work groups of each axis into a dispatch, checking them against the
+/// `maxComputeWorkGroupCount` limit of the device.
+fn dispatch(vulkan: &VulkanState, name: &str, groups: [usize; 3]) -> (u32, u32, u32) {
+    let max_groups = vulkan.info.limits.max_compute_work_group_count;
+    for axis in 0..3 {
+        assert!(
+            groups[axis] <= max_groups[axis] as usize,
+            "[ERR] The {} needs {} work groups on axis {}, but the device can only dispatch {}.",
+            name,
+            groups[axis],
+            axis,
+            max_groups[axis]
+        );
+    }
+    (groups[0] as u32, groups[1] as u32, groups[2] as u32)
+}
+
+fn run(builder: JobBuilder<f32>, vulkan: Arc<VulkanState>) -> JobTimings {
+    let mut job = builder.build(vulkan);
+    job.execute();
+    while job.status() == JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+    assert_eq!(
+        job.status(),
+        JobStatus::SUCESS,
+        "[ERR] The linear algebra job failed."
+    );
+
+    job.get_timing()
+}
@@ -1 +1,13 @@
-pub mod job;
\ No newline at end of file
+mod buffer;
+pub mod graph;
+pub mod histogram;
+pub mod job;
+pub mod linalg;
+pub mod reduce;
+pub mod rng;
+pub mod scan;
+pub mod sort;
+pub mod stream;
+pub mod verify;
+
+pub use self::buffer::{Buffer, Download, Upload};
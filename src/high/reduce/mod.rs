@@ -0,0 +1,295 @@
+use crate::high::buffer::Buffer;
+use crate::high::job::{JobBuilder, JobStatus};
+use crate::low::vkstate::VulkanState;
+use std::sync::Arc;
+
+const REDUCE_U32: &[u8] = include_bytes!("../../../shaders/bin/reduce/reduce_u32.cs.spirv");
+const REDUCE_I32: &[u8] = include_bytes!("../../../shaders/bin/reduce/reduce_i32.cs.spirv");
+const REDUCE_F32: &[u8] = include_bytes!("../../../shaders/bin/reduce/reduce_f32.cs.spirv");
+
+/// Number of invocations in one work group of the reduce shaders.
+const THREADS: usize = 256;
+/// Number of elements we want each invocation to accumulate before the
+/// shared memory reduction.
+const ITEMS_PER_THREAD: usize = 8;
+/// Maximum number of work groups we dispatch on the x axis.
+const MAX_GROUPS: usize = 65535;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReduceOp {
+    Sum,
+    Product,
+    Min,
+    Max,
+}
+
+impl ReduceOp {
+    fn code(self) -> u32 {
+        match self {
+            ReduceOp::Sum => 0,
+            ReduceOp::Product => 1,
+            ReduceOp::Min => 2,
+            ReduceOp::Max => 3,
+        }
+    }
+}
+
+const ARGMIN: u32 = 4;
+const ARGMAX: u32 = 5;
+
+/// A type that can be reduced on the GPU.
+pub trait Reducible: Copy {
+    /// The reduce shader working on this type.
+    fn shader() -> &'static [u8];
+    /// The value returned when reducing an empty buffer.
+    fn identity(op: ReduceOp) -> Self;
+    /// The bits of a value, used to read back the indices which share the buffer type.
+    fn to_bits(self) -> u32;
+}
+
+impl Reducible for u32 {
+    fn shader() -> &'static [u8] {
+        REDUCE_U32
+    }
+
+    fn identity(op: ReduceOp) -> Self {
+        match op {
+            ReduceOp::Sum => 0,
+            ReduceOp::Product => 1,
+            ReduceOp::Min => u32::MAX,
+            ReduceOp::Max => u32::MIN,
+        }
+    }
+
+    fn to_bits(self) -> u32 {
+        self
+    }
+}
+
+impl Reducible for i32 {
+    fn shader() -> &'static [u8] {
+        REDUCE_I32
+    }
+
+    fn identity(op: ReduceOp) -> Self {
+        match op {
+            ReduceOp::Sum => 0,
+            ReduceOp::Product => 1,
+            ReduceOp::Min => i32::MAX,
+            ReduceOp::Max => i32::MIN,
+        }
+    }
+
+    fn to_bits(self) -> u32 {
+        self as u32
+    }
+}
+
+impl Reducible for f32 {
+    fn shader() -> &'static [u8] {
+        REDUCE_F32
+    }
+
+    fn identity(op: ReduceOp) -> Self {
+        match op {
+            ReduceOp::Sum => 0.0,
+            ReduceOp::Product => 1.0,
+            ReduceOp::Min => f32::INFINITY,
+            ReduceOp::Max => f32::NEG_INFINITY,
+        }
+    }
+
+    fn to_bits(self) -> u32 {
+        self.to_bits()
+    }
+}
+
+/// Reduce `data` to a single value with `op`.
+/// For `f32`, NaN comes after every number for `Min` and `Max`, so it is only
+/// returned when all the elements are NaN, while `Sum` and `Product` propagate it.
+pub fn reduce<T: Reducible>(vulkan: Arc<VulkanState>, data: &[T], op: ReduceOp) -> T {
+    if data.is_empty() {
+        return T::identity(op);
+    }
+
+    run_reduce(vulkan, data, op.code()).0
+}
+
+/// Return the index and the value of the smallest element of `data`.
+/// If the minimum appears several times, the lowest index is returned.
+/// NaN is ordered like in `reduce`.
+pub fn argmin<T: Reducible>(vulkan: Arc<VulkanState>, data: &[T]) -> Option<(usize, T)> {
+    if data.is_empty() {
+        return None;
+    }
+
+    let (value, index) = run_reduce(vulkan, data, ARGMIN);
+    Some((index as usize, value))
+}
+
+/// Return the index and the value of the biggest element of `data`.
+/// If the maximum appears several times, the lowest index is returned.
+/// NaN is ordered like in `reduce`.
+pub fn argmax<T: Reducible>(vulkan: Arc<VulkanState>, data: &[T]) -> Option<(usize, T)> {
+    if data.is_empty() {
+        return None;
+    }
+
+    let (value, index) = run_reduce(vulkan, data, ARGMAX);
+    Some((index as usize, value))
+}
+
+/// The result of a reduction left in device memory, so the next jobs can bind it
+/// without going through the host.
+pub struct DeviceReduction<T> {
+    /// The reduced value is the first element.
+    pub value: Buffer<T>,
+    /// For argmin and argmax, the index of the value is the first element.
+    pub index: Buffer<u32>,
+}
+
+impl<T: Copy> DeviceReduction<T> {
+    /// Read back the reduced value and its index.
+    pub fn read(&self) -> (T, u32) {
+        (self.value.read()[0], self.index.read()[0])
+    }
+}
+
+/// Reduce `data` with `op`, like `reduce`, leaving the result in device memory.
+pub fn reduce_buffer<T: Reducible>(data: &Buffer<T>, op: ReduceOp) -> DeviceReduction<T> {
+    run_reduce_buffer(data, op.code())
+}
+
+/// Find the smallest element of `data`, like `argmin`, leaving the result in device memory.
+pub fn argmin_buffer<T: Reducible>(data: &Buffer<T>) -> DeviceReduction<T> {
+    run_reduce_buffer(data, ARGMIN)
+}
+
+/// Find the biggest element of `data`, like `argmax`, leaving the result in device memory.
+pub fn argmax_buffer<T: Reducible>(data: &Buffer<T>) -> DeviceReduction<T> {
+    run_reduce_buffer(data, ARGMAX)
+}
+
+fn group_count(n: usize) -> usize {
+    let per_group = THREADS * ITEMS_PER_THREAD;
+    n.div_ceil(per_group).clamp(1, MAX_GROUPS)
+}
+
+/// The passes reducing `n` elements with `op`. Each pass reduces its input to one
+/// value per work group, ping-ponging between two partial buffers until a single
+/// value is left.
+struct Plan {
+    /// Size of partial A, which the first pass writes.
+    first_groups: usize,
+    /// Size of partial B, which the second pass writes.
+    second_groups: usize,
+    /// Work groups and push constants of each pass.
+    passes: Vec<(u32, [u32; 3])>,
+    /// The partial buffer holding the result, 1 for A and 2 for B.
+    last: u32,
+}
+
+fn plan(n: usize, op: u32) -> Plan {
+    assert!(
+        n <= u32::MAX as usize,
+        "[ERR] Can not reduce more than {} elements.",
+        u32::MAX
+    );
+    let first_groups = group_count(n);
+    let second_groups = group_count(first_groups);
+    let mut passes = vec![(first_groups as u32, [n as u32, 0, op])];
+
+    // src 1 is partial A, src 2 is partial B.
+    let mut count = first_groups;
+    let mut last = 1;
+    while count > 1 {
+        let groups = group_count(count);
+        passes.push((groups as u32, [count as u32, last, op]));
+        count = groups;
+        last = if last == 1 { 2 } else { 1 };
+    }
+
+    Plan {
+        first_groups,
+        second_groups,
+        passes,
+        last,
+    }
+}
+
+fn run_reduce<T: Reducible>(vulkan: Arc<VulkanState>, data: &[T], op: u32) -> (T, u32) {
+    let plan = plan(data.len(), op);
+    let input = data.to_vec();
+
+    let mut builder = JobBuilder::new()
+        .add_buffer(&input, 0, 0)
+        .add_ro_buffer(plan.first_groups, 0, 1)
+        .add_ro_buffer(plan.second_groups, 0, 2)
+        .add_ro_buffer(plan.first_groups, 0, 3)
+        .add_ro_buffer(plan.second_groups, 0, 4)
+        .add_spirv(T::shader());
+    for (groups, constants) in &plan.passes {
+        builder = builder.add_pass(0, (*groups, 1, 1), constants);
+    }
+
+    let mut job = builder.build(vulkan);
+    job.execute();
+    while job.status() == JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+
+    let output = job
+        .get_output()
+        .expect("[ERR] Could not get the reduction buffers.");
+    let (values, indices) = if plan.last == 1 {
+        (&output[1], &output[3])
+    } else {
+        (&output[2], &output[4])
+    };
+    (values[0], indices[0].to_bits())
+}
+
+fn run_reduce_buffer<T: Reducible>(data: &Buffer<T>, op: u32) -> DeviceReduction<T> {
+    let vulkan = data.vulkan();
+    let plan = plan(data.len(), op);
+    let partial_a = Buffer::<T>::uninit(vulkan.clone(), plan.first_groups);
+    let partial_b = Buffer::<T>::uninit(vulkan.clone(), plan.second_groups);
+    let index_a = Buffer::<u32>::uninit(vulkan.clone(), plan.first_groups);
+    let index_b = Buffer::<u32>::uninit(vulkan.clone(), plan.second_groups);
+
+    {
+        let mut builder = JobBuilder::<T>::new()
+            .add_device_buffer(data, 0, 0)
+            .add_device_buffer(&partial_a, 0, 1)
+            .add_device_buffer(&partial_b, 0, 2)
+            .add_device_buffer(&index_a, 0, 3)
+            .add_device_buffer(&index_b, 0, 4)
+            .add_spirv(T::shader());
+        for (groups, constants) in &plan.passes {
+            builder = builder.add_pass(0, (*groups, 1, 1), constants);
+        }
+
+        let mut job = builder.build(vulkan);
+        job.execute();
+        while job.status() == JobStatus::EXECUTING {
+            job.wait_until_idle(1000 * 1000 * 1000);
+        }
+        assert_eq!(
+            job.status(),
+            JobStatus::SUCESS,
+            "[ERR] The reduction job failed."
+        );
+    }
+
+    if plan.last == 1 {
+        DeviceReduction {
+            value: partial_a,
+            index: index_a,
+        }
+    } else {
+        DeviceReduction {
+            value: partial_b,
+            index: index_b,
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::high::buffer::Buffer;
+use crate::high::job::{JobBuilder, JobStatus, JobTimings};
+
+const PHILOX: &[u8] = include_bytes!("../../../shaders/bin/rng/philox.cs.spirv");
+
+/// Number of invocations in one work group of the Philox shader.
+const THREADS: usize = 256;
+/// Maximum number of work groups we dispatch on one axis.
+const MAX_GROUPS: usize = 65535;
+/// Number of values in a block of the generator.
+const LANES: usize = 4;
+
+const PHILOX_M: [u32; 2] = [0xD251_1F53, 0xCD9E_8D57];
+const PHILOX_W: [u32; 2] = [0x9E37_79B9, 0xBB67_AE85];
+
+/// 2^-24 and 2^-22, turning 24 and 22 random bits into [0, 1).
+const UNIT_24: u32 = 0x3380_0000;
+const UNIT_22: u32 = 0x3480_0000;
+/// sqrt(0.5), then the coefficients of the Cephes logf, and ln(2) in two parts.
+const SQRT_HALF: u32 = 0x3F35_04F3;
+const LOG: [u32; 9] = [
+    0x3D90_21BB,
+    0xBDEB_D1B8,
+    0x3DEF_251A,
+    0xBDFE_5D4F,
+    0x3E11_E9BF,
+    0xBE2A_AE50,
+    0x3E4C_CEAC,
+    0xBE7F_FFFC,
+    0x3EAA_AAAA,
+];
+const LN2_LOW: u32 = 0xB95E_8083;
+const LN2_HIGH: u32 = 0x3F31_8000;
+/// pi / 2, then the Taylor coefficients of sin and cos from the highest degree.
+const HALF_PI: u32 = 0x3FC9_0FDB;
+const SIN: [u32; 6] = [
+    0x2F30_9231,
+    0xB2D7_322B,
+    0x3638_EF1D,
+    0xB950_0D01,
+    0x3C08_8889,
+    0xBE2A_AAAB,
+];
+const COS: [u32; 7] = [
+    0xAD49_CBA5,
+    0x310F_76C7,
+    0xB493_F27E,
+    0x37D0_0D01,
+    0xBAB6_0B61,
+    0x3D2A_AAAB,
+    0xBF00_0000,
+];
+
+/// The 10 rounds of Philox4x32 on a 128 bits counter with a 64 bits key.
+pub fn philox4x32(counter: [u32; 4], key: [u32; 2]) -> [u32; 4] {
+    let mut ctr = counter;
+    let mut key = key;
+    for round in 0..10 {
+        if round > 0 {
+            key[0] = key[0].wrapping_add(PHILOX_W[0]);
+            key[1] = key[1].wrapping_add(PHILOX_W[1]);
+        }
+        let p0 = PHILOX_M[0] as u64 * ctr[0] as u64;
+        let p1 = PHILOX_M[1] as u64 * ctr[2] as u64;
+        ctr = [
+            (p1 >> 32) as u32 ^ ctr[1] ^ key[0],
+            p1 as u32,
+            (p0 >> 32) as u32 ^ ctr[3] ^ key[1],
+            p0 as u32,
+        ];
+    }
+    ctr
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Distribution {
+    Bits,
+    /// low and high - low.
+    Range(u32, u32),
+    /// low and high - low.
+    Uniform(f32, f32),
+    /// mean and standard deviation.
+    Normal(f32, f32),
+}
+
+impl Distribution {
+    /// The distribution and its parameters, as pushed to the shader.
+    fn constants(self) -> [u32; 3] {
+        match self {
+            Distribution::Bits => [0, 0, 0],
+            Distribution::Range(low, range) => [1, low, range],
+            Distribution::Uniform(low, scale) => [2, low.to_bits(), scale.to_bits()],
+            Distribution::Normal(mean, std_dev) => [3, mean.to_bits(), std_dev.to_bits()],
+        }
+    }
+
+    /// The bits of the value of `lane` in `block`, computed like the shader does.
+    fn value(self, block: &[u32; 4], lane: usize) -> u32 {
+        let x = block[lane];
+        match self {
+            Distribution::Bits => x,
+            Distribution::Range(low, range) => {
+                low.wrapping_add(((x as u64 * range as u64) >> 32) as u32)
+            }
+            Distribution::Uniform(low, scale) => {
+                let u = (x >> 8) as f32 * f32::from_bits(UNIT_24);
+                (low + u * scale).to_bits()
+            }
+            Distribution::Normal(mean, std_dev) => {
+                let pair = lane & 2;
+                let z = box_muller(block[pair], block[pair + 1]);
+                (mean + std_dev * z[lane & 1]).to_bits()
+            }
+        }
+    }
+}
+
+/// Natural logarithm of x in (0, 1], from the Cephes logf.
+fn log_unit(x: f32) -> f32 {
+    let bits = x.to_bits();
+    let mut e = (bits >> 23) as i32 - 126;
+    let mut m = f32::from_bits((bits & 0x007F_FFFF) | 0x3F00_0000);
+    if m < f32::from_bits(SQRT_HALF) {
+        e -= 1;
+        m = m + m - 1.0;
+    } else {
+        m -= 1.0;
+    }
+    let z = m * m;
+    let mut y = f32::from_bits(LOG[0]);
+    for c in &LOG[1..] {
+        y = y * m + f32::from_bits(*c);
+    }
+    y = y * m * z;
+    let fe = e as f32;
+    y += f32::from_bits(LN2_LOW) * fe;
+    y -= 0.5 * z;
+    let result = m + y;
+    result + f32::from_bits(LN2_HIGH) * fe
+}
+
+/// Square root of x >= 0, from Newton iterations on its inverse.
+fn sqrt_positive(x: f32) -> f32 {
+    if x == 0.0 {
+        return 0.0;
+    }
+    let mut y = f32::from_bits(0x5F37_59DF - (x.to_bits() >> 1));
+    for _ in 0..3 {
+        y = y * (1.5 - ((0.5 * x) * y) * y);
+    }
+    x * y
+}
+
+/// Sine and cosine of 2 pi (quadrant + r) / 4, with r in [0, 1), from their Taylor
+/// series on [0, pi / 2).
+fn sin_cos_turn(quadrant: u32, r: f32) -> (f32, f32) {
+    let t = r * f32::from_bits(HALF_PI);
+    let t2 = t * t;
+    let mut s = f32::from_bits(SIN[0]);
+    for c in &SIN[1..] {
+        s = s * t2 + f32::from_bits(*c);
+    }
+    let s = (s * t2 + 1.0) * t;
+    let mut c = f32::from_bits(COS[0]);
+    for k in &COS[1..] {
+        c = c * t2 + f32::from_bits(*k);
+    }
+    let c = c * t2 + 1.0;
+    match quadrant {
+        0 => (s, c),
+        1 => (c, -s),
+        2 => (-s, -c),
+        _ => (-c, s),
+    }
+}
+
+/// Two normal values from two random words, with the Box-Muller transform.
+fn box_muller(x: u32, y: u32) -> [f32; 2] {
+    // (0, 1], which has a logarithm.
+    let u = ((x >> 8) + 1) as f32 * f32::from_bits(UNIT_24);
+    let radius = sqrt_positive(-2.0 * log_unit(u));
+    let r = ((y >> 8) & 0x003F_FFFF) as f32 * f32::from_bits(UNIT_22);
+    let (sin, cos) = sin_cos_turn(y >> 30, r);
+    [radius * cos, radius * sin]
+}
+
+/// A Philox4x32-10 generator, filling device buffers on the GPU or vectors on the CPU
+/// with the same values.
+///
+/// The generator is counter-based: value `n` of the stream of a seed only depends on
+/// `n`, and each fill or generation continues the stream where the last one stopped.
+/// The floats match bit for bit, unless the device flushes denormals, which only
+/// happens when the values themselves are denormal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Philox {
+    seed: u64,
+    offset: u64,
+}
+
+impl Philox {
+    pub fn new(seed: u64) -> Philox {
+        Philox { seed, offset: 0 }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Index in the stream of the next value.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Skip `n` values of the stream.
+    pub fn skip(&mut self, n: u64) {
+        self.offset = self.offset.wrapping_add(n);
+    }
+
+    /// Fill `buffer` with uniform random bits.
+    pub fn fill_u32(&mut self, buffer: &mut Buffer<u32>) -> JobTimings {
+        self.fill(buffer, Distribution::Bits)
+    }
+
+    /// Fill `buffer` with integers in [low, high), from the high bits of the product of
+    /// the random words by `high - low`.
+    pub fn fill_range_u32(&mut self, buffer: &mut Buffer<u32>, low: u32, high: u32) -> JobTimings {
+        self.fill(buffer, range(low, high))
+    }
+
+    /// Fill `buffer` with uniform floats in [low, high), `high` being only reached through
+    /// rounding. They have 24 random bits.
+    pub fn fill_uniform(&mut self, buffer: &mut Buffer<f32>, low: f32, high: f32) -> JobTimings {
+        self.fill(buffer, uniform(low, high))
+    }
+
+    /// Fill `buffer` with normally distributed floats.
+    pub fn fill_normal(&mut self, buffer: &mut Buffer<f32>, mean: f32, std_dev: f32) -> JobTimings {
+        self.fill(buffer, Distribution::Normal(mean, std_dev))
+    }
+
+    /// The next `len` values of `fill_u32`, computed on the CPU.
+    pub fn gen_u32(&mut self, len: usize) -> Vec<u32> {
+        self.generate(len, Distribution::Bits)
+    }
+
+    /// The next `len` values of `fill_range_u32`, computed on the CPU.
+    pub fn gen_range_u32(&mut self, len: usize, low: u32, high: u32) -> Vec<u32> {
+        self.generate(len, range(low, high))
+    }
+
+    /// The next `len` values of `fill_uniform`, computed on the CPU.
+    pub fn gen_uniform(&mut self, len: usize, low: f32, high: f32) -> Vec<f32> {
+        self.generate(len, uniform(low, high))
+            .into_iter()
+            .map(f32::from_bits)
+            .collect()
+    }
+
+    /// The next `len` values of `fill_normal`, computed on the CPU.
+    pub fn gen_normal(&mut self, len: usize, mean: f32, std_dev: f32) -> Vec<f32> {
+        self.generate(len, Distribution::Normal(mean, std_dev))
+            .into_iter()
+            .map(f32::from_bits)
+            .collect()
+    }
+
+    fn key(&self) -> [u32; 2] {
+        [self.seed as u32, (self.seed >> 32) as u32]
+    }
+
+    /// The block of the first value, and how many values of it come before.
+    fn start(&self) -> (u64, usize) {
+        (
+            self.offset / LANES as u64,
+            (self.offset % LANES as u64) as usize,
+        )
+    }
+
+    fn generate(&mut self, len: usize, distribution: Distribution) -> Vec<u32> {
+        let (first_block, skip) = self.start();
+        let key = self.key();
+        let mut output = Vec::with_capacity(len);
+        for index in 0..(skip + len).div_ceil(LANES) {
+            let block_index = first_block.wrapping_add(index as u64);
+            let block = philox4x32([block_index as u32, (block_index >> 32) as u32, 0, 0], key);
+            for lane in 0..LANES {
+                let n = index * LANES + lane;
+                if n >= skip && n - skip < len {
+                    output.push(distribution.value(&block, lane));
+                }
+            }
+        }
+        self.skip(len as u64);
+        output
+    }
+
+    fn fill<U: Copy>(&mut self, buffer: &mut Buffer<U>, distribution: Distribution) -> JobTimings {
+        let len = buffer.len();
+        assert!(
+            len <= u32::MAX as usize - LANES,
+            "[ERR] The buffer is too large to be filled at once."
+        );
+        let (first_block, skip) = self.start();
+        let key = self.key();
+        let [code, a, b] = distribution.constants();
+
+        let blocks = (skip + len).div_ceil(LANES);
+        let groups = blocks.div_ceil(THREADS);
+        let x_groups = groups.clamp(1, MAX_GROUPS);
+        let mut job = JobBuilder::<u32>::new()
+            .add_device_buffer(buffer, 0, 0)
+            .add_spirv(PHILOX)
+            .add_pass(
+                0,
+                (x_groups as u32, groups.div_ceil(x_groups) as u32, 1),
+                &[
+                    key[0],
+                    key[1],
+                    first_block as u32,
+                    (first_block >> 32) as u32,
+                    skip as u32,
+                    len as u32,
+                    code,
+                    a,
+                    b,
+                ],
+            )
+            .build(buffer.vulkan());
+        job.execute();
+        while job.status() == JobStatus::EXECUTING {
+            job.wait_until_idle(1000 * 1000 * 1000);
+        }
+        assert_eq!(
+            job.status(),
+            JobStatus::SUCESS,
+            "[ERR] The random number job failed."
+        );
+
+        self.skip(len as u64);
+        job.get_timing()
+    }
+}
+
+fn range(low: u32, high: u32) -> Distribution {
+    assert!(low < high, "[ERR] The range of the integers is empty.");
+    Distribution::Range(low, high - low)
+}
+
+fn uniform(low: f32, high: f32) -> Distribution {
+    assert!(low < high, "[ERR] The range of the floats is empty.");
+    let scale = high - low;
+    assert!(
+        scale.is_finite(),
+        "[ERR] The range of the floats is too large."
+    );
+    Distribution::Uniform(low, scale)
+}
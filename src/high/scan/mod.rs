@@ -0,0 +1,233 @@
+use crate::high::job::{JobBuilder, JobStatus};
+use crate::low::vkstate::VulkanState;
+use std::sync::Arc;
+
+const SCAN_U32: &[u8] = include_bytes!("../../../shaders/bin/scan/scan_u32.cs.spirv");
+const SCAN_I32: &[u8] = include_bytes!("../../../shaders/bin/scan/scan_i32.cs.spirv");
+const SCAN_F32: &[u8] = include_bytes!("../../../shaders/bin/scan/scan_f32.cs.spirv");
+const COMPACT: &[u8] = include_bytes!("../../../shaders/bin/scan/compact.cs.spirv");
+
+/// Number of elements scanned by one work group.
+const BLOCK: usize = 512;
+/// Number of invocations in one work group of the compaction shader.
+const COMPACT_THREADS: usize = 256;
+/// Maximum number of work groups we dispatch on one axis.
+const MAX_GROUPS: usize = 65535;
+
+const MODE_SCAN: u32 = 0;
+const MODE_PROPAGATE: u32 = 1;
+const MODE_EXCLUSIVE: u32 = 2;
+
+/// A type that can be scanned on the GPU.
+pub trait Scannable: Copy {
+    /// The scan shader working on this type.
+    fn shader() -> &'static [u8];
+    fn to_bits(self) -> u32;
+    fn from_bits(bits: u32) -> Self;
+}
+
+impl Scannable for u32 {
+    fn shader() -> &'static [u8] {
+        SCAN_U32
+    }
+
+    fn to_bits(self) -> u32 {
+        self
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        bits
+    }
+}
+
+impl Scannable for i32 {
+    fn shader() -> &'static [u8] {
+        SCAN_I32
+    }
+
+    fn to_bits(self) -> u32 {
+        self as u32
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        bits as i32
+    }
+}
+
+impl Scannable for f32 {
+    fn shader() -> &'static [u8] {
+        SCAN_F32
+    }
+
+    fn to_bits(self) -> u32 {
+        self.to_bits()
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        f32::from_bits(bits)
+    }
+}
+
+/// Spread `groups` work groups over x and y so that no axis goes past the limit.
+/// The shaders rebuild the group index from both axes.
+fn dispatch_2d(groups: usize) -> (u32, u32, u32) {
+    let x = groups.clamp(1, MAX_GROUPS);
+    (x as u32, groups.div_ceil(x) as u32, 1)
+}
+
+/// Offsets of each level of block sums in the sums buffer, and the size of this buffer.
+/// Level 0 is the input itself, level `l + 1` holds one sum per block of level `l`.
+fn levels(n: usize) -> (Vec<(usize, usize)>, usize) {
+    let mut levels = vec![(n, 0)];
+    let mut size = n;
+    let mut offset = 0;
+    loop {
+        let blocks = size.div_ceil(BLOCK);
+        levels.push((blocks, offset));
+        offset += blocks;
+        if blocks == 1 {
+            break;
+        }
+        size = blocks;
+    }
+
+    (levels, offset)
+}
+
+/// Add the passes scanning the first `n` elements of buffer 0 into buffer 1,
+/// using buffer 2 for the block sums, to `builder`.
+fn add_scan_passes<'a, T>(
+    mut builder: JobBuilder<'a, T>,
+    shader: usize,
+    levels: &[(usize, usize)],
+) -> JobBuilder<'a, T> {
+    // Inclusive scan of each block, going up the levels until one block is left.
+    for level in 0..levels.len() - 1 {
+        let (size, in_offset) = levels[level];
+        let out_offset = levels[level + 1].1;
+        builder = builder.add_pass(
+            shader,
+            dispatch_2d(size.div_ceil(BLOCK)),
+            &[
+                size as u32,
+                MODE_SCAN,
+                level as u32,
+                in_offset as u32,
+                out_offset as u32,
+            ],
+        );
+    }
+
+    // Going down, each level gets the scanned sums of the previous blocks.
+    for level in (0..levels.len() - 2).rev() {
+        let (size, in_offset) = levels[level];
+        let out_offset = levels[level + 1].1;
+        builder = builder.add_pass(
+            shader,
+            dispatch_2d(size.div_ceil(BLOCK)),
+            &[
+                size as u32,
+                MODE_PROPAGATE,
+                level as u32,
+                in_offset as u32,
+                out_offset as u32,
+            ],
+        );
+    }
+
+    builder
+}
+
+fn run_scan<T: Scannable>(vulkan: Arc<VulkanState>, data: &[T], exclusive: bool) -> Vec<T> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    let input = data.to_vec();
+    let n = input.len();
+    let (levels, sums_size) = levels(n);
+
+    let mut builder = JobBuilder::new()
+        .add_buffer(&input, 0, 0)
+        .add_ro_buffer(n, 0, 1)
+        .add_ro_buffer(sums_size, 0, 2)
+        .add_spirv(T::shader());
+    builder = add_scan_passes(builder, 0, &levels);
+    if exclusive {
+        builder = builder.add_pass(
+            0,
+            dispatch_2d(n.div_ceil(BLOCK)),
+            &[n as u32, MODE_EXCLUSIVE, 0, 0, 0],
+        );
+    }
+
+    let mut job = builder.build(vulkan);
+    job.execute();
+    while job.status() == JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+
+    let mut output = job
+        .get_output()
+        .expect("[ERR] Could not get the scanned buffer.");
+    if exclusive {
+        output.swap_remove(0)
+    } else {
+        output.swap_remove(1)
+    }
+}
+
+/// Inclusive prefix sum: `output[i] = data[0] + ... + data[i]`.
+pub fn inclusive_scan<T: Scannable>(vulkan: Arc<VulkanState>, data: &[T]) -> Vec<T> {
+    run_scan(vulkan, data, false)
+}
+
+/// Exclusive prefix sum: `output[0] = 0` and `output[i] = data[0] + ... + data[i - 1]`.
+pub fn exclusive_scan<T: Scannable>(vulkan: Arc<VulkanState>, data: &[T]) -> Vec<T> {
+    run_scan(vulkan, data, true)
+}
+
+/// Keep the elements of `data` whose flag in `keep` is true, in order.
+/// The output position of each kept element is found with a scan of the flags.
+pub fn compact<T: Scannable>(vulkan: Arc<VulkanState>, data: &[T], keep: &[bool]) -> Vec<T> {
+    assert_eq!(
+        data.len(),
+        keep.len(),
+        "[ERR] There must be as many flags as elements."
+    );
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    let n = data.len();
+    let flags: Vec<u32> = keep.iter().map(|&k| k as u32).collect();
+    let values: Vec<u32> = data.iter().map(|v| v.to_bits()).collect();
+    let (levels, sums_size) = levels(n);
+
+    let mut builder = JobBuilder::new()
+        .add_buffer(&flags, 0, 0)
+        .add_ro_buffer(n, 0, 1)
+        .add_ro_buffer(sums_size, 0, 2)
+        .add_buffer(&values, 0, 3)
+        .add_ro_buffer(n, 0, 4)
+        .add_spirv(SCAN_U32)
+        .add_spirv(COMPACT);
+    builder = add_scan_passes(builder, 0, &levels);
+    builder = builder.add_pass(1, dispatch_2d(n.div_ceil(COMPACT_THREADS)), &[n as u32]);
+
+    let mut job = builder.build(vulkan);
+    job.execute();
+    while job.status() == JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+
+    // Outputs are: flags, values, scanned flags, sums, compacted values.
+    let output = job
+        .get_output()
+        .expect("[ERR] Could not get the compacted buffer.");
+    let count = output[2][n - 1] as usize;
+    output[4][..count]
+        .iter()
+        .map(|&bits| T::from_bits(bits))
+        .collect()
+}
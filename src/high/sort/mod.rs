@@ -0,0 +1,150 @@
+use crate::high::job::{JobBuilder, JobStatus};
+use crate::low::vkstate::VulkanState;
+use std::sync::Arc;
+
+const BITONIC_LOCAL: &[u8] = include_bytes!("../../../shaders/bin/sort/bitonic_local.cs.spirv");
+const BITONIC_GLOBAL: &[u8] = include_bytes!("../../../shaders/bin/sort/bitonic_global.cs.spirv");
+
+/// Number of elements sorted in shared memory by one work group of bitonic_local.
+const BLOCK: usize = 512;
+/// Maximum number of work groups we dispatch on the x axis.
+const MAX_GROUPS: usize = 65535;
+
+/// A key that can be sorted on the GPU.
+/// Keys are sorted as `u32`, so they have to be mapped to a `u32` that keeps their order.
+pub trait SortKey: Copy {
+    fn to_ordered(self) -> u32;
+    fn from_ordered(ordered: u32) -> Self;
+}
+
+impl SortKey for u32 {
+    fn to_ordered(self) -> u32 {
+        self
+    }
+
+    fn from_ordered(ordered: u32) -> Self {
+        ordered
+    }
+}
+
+impl SortKey for i32 {
+    fn to_ordered(self) -> u32 {
+        (self as u32) ^ 0x8000_0000
+    }
+
+    fn from_ordered(ordered: u32) -> Self {
+        (ordered ^ 0x8000_0000) as i32
+    }
+}
+
+/// Negative floats have all their bits flipped, positive ones only the sign bit,
+/// which gives -NaN < -inf < ... < -0.0 < 0.0 < ... < inf < NaN.
+impl SortKey for f32 {
+    fn to_ordered(self) -> u32 {
+        let bits = self.to_bits();
+        if bits & 0x8000_0000 != 0 {
+            !bits
+        } else {
+            bits | 0x8000_0000
+        }
+    }
+
+    fn from_ordered(ordered: u32) -> Self {
+        if ordered & 0x8000_0000 != 0 {
+            f32::from_bits(ordered & 0x7FFF_FFFF)
+        } else {
+            f32::from_bits(!ordered)
+        }
+    }
+}
+
+/// Sort `keys` in ascending order on the GPU.
+pub fn sort<T: SortKey>(vulkan: Arc<VulkanState>, keys: &[T]) -> Vec<T> {
+    let ordered: Vec<u32> = keys.iter().map(|key| key.to_ordered()).collect();
+    let (sorted, _) = bitonic_sort(vulkan, ordered, None);
+    sorted.into_iter().map(T::from_ordered).collect()
+}
+
+/// Sort `keys` in ascending order on the GPU, moving `values` along with their key.
+/// The order of values sharing the same key is unspecified.
+pub fn sort_by_key<T: SortKey>(
+    vulkan: Arc<VulkanState>,
+    keys: &[T],
+    values: &[u32],
+) -> (Vec<T>, Vec<u32>) {
+    assert_eq!(
+        keys.len(),
+        values.len(),
+        "[ERR] There must be as many values as keys."
+    );
+    let ordered: Vec<u32> = keys.iter().map(|key| key.to_ordered()).collect();
+    let (sorted, values) = bitonic_sort(vulkan, ordered, Some(values.to_vec()));
+    (
+        sorted.into_iter().map(T::from_ordered).collect(),
+        values.unwrap(),
+    )
+}
+
+/// Bitonic sort where every comparison puts the smallest key first.
+/// The data is virtually padded to a power of two with +inf,
+/// which never moves, so the padding is never actually allocated.
+fn bitonic_sort(
+    vulkan: Arc<VulkanState>,
+    keys: Vec<u32>,
+    values: Option<Vec<u32>>,
+) -> (Vec<u32>, Option<Vec<u32>>) {
+    let n = keys.len();
+    if n < 2 {
+        return (keys, values);
+    }
+
+    let padded = n.next_power_of_two().max(BLOCK);
+    let groups = padded / BLOCK;
+    assert!(
+        groups <= MAX_GROUPS,
+        "[ERR] Too many elements to sort ({}).",
+        n
+    );
+
+    let has_values = values.is_some();
+    // The shaders always bind a value buffer, even if it is not used.
+    let values = values.unwrap_or_else(|| vec![0]);
+    let flag = has_values as u32;
+    let n = n as u32;
+    let dispatch = (groups as u32, 1, 1);
+
+    let mut builder = JobBuilder::new()
+        .add_buffer(&keys, 0, 0)
+        .add_buffer(&values, 0, 1)
+        .add_spirv(BITONIC_LOCAL)
+        .add_spirv(BITONIC_GLOBAL)
+        .add_pass(0, dispatch, &[n, 0, 0, flag]);
+
+    let mut k = 2 * BLOCK;
+    while k <= padded {
+        let mut j = k / 2;
+        while j >= BLOCK {
+            builder = builder.add_pass(1, dispatch, &[n, k as u32, j as u32, flag]);
+            j /= 2;
+        }
+        builder = builder.add_pass(0, dispatch, &[n, k as u32, 0, flag]);
+        k *= 2;
+    }
+
+    let mut job = builder.build(vulkan);
+    job.execute();
+    while job.status() == JobStatus::EXECUTING {
+        job.wait_until_idle(1000 * 1000 * 1000);
+    }
+
+    let mut output = job
+        .get_output()
+        .expect("[ERR] Could not get the sorted buffers.");
+    let sorted_values = output.pop().unwrap();
+    let sorted_keys = output.pop().unwrap();
+    if has_values {
+        (sorted_keys, Some(sorted_values))
+    } else {
+        (sorted_keys, None)
+    }
+}
@@ -0,0 +1,380 @@
+use crate::high::job::ShaderSource;
+use crate::low::{vkcmd, vkdescriptor, vkfence, vkmem, vkpipeline, vkshader, vkstate};
+use ash::vk;
+use std::ffi::CString;
+use std::sync::{Arc, RwLock};
+
+/// Maximum number of work groups we dispatch on one axis.
+const MAX_GROUPS: usize = 65535;
+
+/// Runs one shader over a dataset too large to live on the device, one chunk at a time.
+///
+/// The shader reads the input chunk at binding 0 and writes the output chunk at binding 1,
+/// one output element per input element. Its push constants are, as `uint`s:
+/// the number of elements to compute, the number of halo elements before the first one,
+/// the number of elements in the input chunk, and the index of the first element in the
+/// dataset as a low and a high word. Work groups are spread over x and y, so the index of
+/// an element in its chunk is `(gl_WorkGroupID.y * gl_NumWorkGroups.x + gl_WorkGroupID.x)
+/// * gl_WorkGroupSize.x + gl_LocalInvocationID.x`.
+///
+/// Chunks are copied through host-visible staging buffers to device-local buffers bound to
+/// the shader, in several slots, so the host fills the next chunks and reads back the previous
+/// ones while the GPU works on another.
+#[derive(Debug, Clone)]
+pub struct Stream<'a> {
+    shader: ShaderSource<'a>,
+    chunk_len: usize,
+    halo: usize,
+    slots: usize,
+    local_size: usize,
+}
+
+/// The buffers, descriptor set and command buffer of one chunk in flight.
+struct Slot {
+    /// The buffers bound to the shader, in device-local memory when the device has some.
+    input: vkmem::VkBuffer,
+    output: vkmem::VkBuffer,
+    _memory: vkmem::VkMem,
+    /// The buffers the host writes the input chunk to and reads the output chunk from.
+    staging_input: vkmem::VkBuffer,
+    staging_output: vkmem::VkBuffer,
+    staging_memory: vkmem::VkMem,
+    descriptor: vkdescriptor::VkDescriptor,
+    cmd_pool: vkcmd::VkCmdPool,
+    fence: vkfence::VkFence,
+    /// The chunk being computed in this slot.
+    pending: Option<usize>,
+}
+
+impl<'a> Stream<'a> {
+    /// Stream `shader` over chunks of `chunk_len` elements, double-buffered,
+    /// with a local size of 64 and no halo.
+    pub fn new(shader: ShaderSource<'a>, chunk_len: usize) -> Stream<'a> {
+        assert!(chunk_len > 0, "[ERR] Chunks can not be empty.");
+        Stream {
+            shader,
+            chunk_len,
+            halo: 0,
+            slots: 2,
+            local_size: 64,
+        }
+    }
+
+    /// Give each chunk `halo` more input elements on each side, for stencil shaders.
+    /// At the start and the end of the dataset there are fewer of them.
+    pub fn halo(mut self, halo: usize) -> Stream<'a> {
+        self.halo = halo;
+        self
+    }
+
+    /// Number of chunks in flight: 2 for double buffering, 3 for triple buffering.
+    pub fn buffering(mut self, slots: usize) -> Stream<'a> {
+        assert!(slots > 0, "[ERR] At least one chunk has to be in flight.");
+        self.slots = slots;
+        self
+    }
+
+    /// Size of a work group of the shader, on x.
+    pub fn local_size(mut self, local_size: usize) -> Stream<'a> {
+        assert!(local_size > 0, "[ERR] The local size can not be 0.");
+        self.local_size = local_size;
+        self
+    }
+
+    /// Run the shader over `input` and return the reassembled output.
+    pub fn run<T: Copy, U: Copy + Default>(
+        &self,
+        vulkan: Arc<vkstate::VulkanState>,
+        input: &[T],
+    ) -> Vec<U> {
+        let mut output: Vec<U> = vec![U::default(); input.len()];
+        self.run_into(vulkan, input, &mut output);
+        output
+    }
+
+    /// Run the shader over `input`, writing the result in `output`.
+    pub fn run_into<T: Copy, U: Copy>(
+        &self,
+        vulkan: Arc<vkstate::VulkanState>,
+        input: &[T],
+        output: &mut [U],
+    ) {
+        assert_eq!(
+            input.len(),
+            output.len(),
+            "[ERR] The output must have the size of the input."
+        );
+        if input.is_empty() {
+            return;
+        }
+
+        let chunk_count = input.len().div_ceil(self.chunk_len);
+        let mut shader = match self.shader {
+            ShaderSource::File(path) => {
+                vkshader::VkShader::new(vulkan.clone(), path, CString::new("main").unwrap())
+            }
+            ShaderSource::Spirv(spirv) => {
+                vkshader::VkShader::from_spirv(vulkan.clone(), spirv, CString::new("main").unwrap())
+            }
+        };
+        shader.add_push_constant_range(
+            0,
+            (5 * std::mem::size_of::<u32>()) as u32,
+            vk::ShaderStageFlags::COMPUTE,
+        );
+        for binding in 0..2 {
+            shader.add_layout_binding(
+                binding,
+                1,
+                vk::DescriptorType::STORAGE_BUFFER,
+                vk::ShaderStageFlags::COMPUTE,
+            );
+        }
+        shader.create_pipeline_layout();
+        let layout = shader.pipeline.unwrap();
+        let pipeline = vkpipeline::VkComputePipeline::new(vulkan.clone(), &shader);
+        let shader = Arc::new(RwLock::new(shader));
+
+        let slot_count = self.slots.min(chunk_count);
+        let mut slots: Vec<Slot> = (0..slot_count)
+            .map(|_| self.create_slot::<T, U>(vulkan.clone(), shader.clone()))
+            .collect();
+
+        // A slot is reused once the chunk it was computing is copied back.
+        for chunk in 0..chunk_count {
+            let slot = &mut slots[chunk % slot_count];
+            self.finish(slot, output);
+            self.submit::<T, U>(&vulkan, slot, chunk, input, layout, pipeline.pipeline);
+        }
+        for slot in slots.iter_mut() {
+            self.finish(slot, output);
+        }
+    }
+
+    fn create_slot<T, U>(
+        &self,
+        vulkan: Arc<vkstate::VulkanState>,
+        shader: Arc<RwLock<vkshader::VkShader>>,
+    ) -> Slot {
+        let input_size = ((self.chunk_len + 2 * self.halo) * std::mem::size_of::<T>()) as u64;
+        let output_size = (self.chunk_len * std::mem::size_of::<U>()) as u64;
+        let host_flags =
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+        let (input, output, memory) = allocate_pair(
+            vulkan.clone(),
+            input_size,
+            output_size,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        );
+        let (staging_input, staging_output, staging_memory) =
+            allocate_pair(vulkan.clone(), input_size, output_size, host_flags);
+        assert!(
+            staging_memory.property_flags().contains(host_flags),
+            "[ERR] Could not find a host visible memory type for the staging buffers."
+        );
+
+        let mut descriptor = vkdescriptor::VkDescriptor::new(vulkan.clone(), shader);
+        descriptor.add_pool_size(2, vk::DescriptorType::STORAGE_BUFFER);
+        descriptor.create_pool(1);
+        descriptor.create_set();
+        let set = *descriptor.get_first_set().unwrap();
+        let mut write_descriptor = vkdescriptor::VkWriteDescriptor::new(vulkan.clone());
+        write_descriptor.add_buffer(input.buffer, 0, input.size);
+        write_descriptor.add_buffer(output.buffer, 0, output.size);
+        let infos: Vec<Vec<vk::DescriptorBufferInfo>> = write_descriptor
+            .buffer_descriptors
+            .iter()
+            .map(|info| vec![*info])
+            .collect();
+        for (binding, info) in infos.iter().enumerate() {
+            write_descriptor.add_write_descriptors(
+                set,
+                vk::DescriptorType::STORAGE_BUFFER,
+                info,
+                binding as u32,
+                0,
+            );
+        }
+        write_descriptor.update_descriptors_sets();
+
+        let mut cmd_pool = vkcmd::VkCmdPool::new(vulkan.clone());
+        cmd_pool.create_cmd_buffer(vk::CommandBufferLevel::PRIMARY);
+
+        Slot {
+            input,
+            output,
+            _memory: memory,
+            staging_input,
+            staging_output,
+            staging_memory,
+            descriptor,
+            cmd_pool,
+            fence: vkfence::VkFence::new(vulkan, false),
+            pending: None,
+        }
+    }
+
+    /// Range of the outputs of `chunk`, and range of the inputs it reads with its halo.
+    fn ranges(&self, chunk: usize, len: usize) -> ((usize, usize), (usize, usize)) {
+        let start = chunk * self.chunk_len;
+        let end = (start + self.chunk_len).min(len);
+        let input_start = start.saturating_sub(self.halo);
+        let input_end = (end + self.halo).min(len);
+        ((start, end), (input_start, input_end))
+    }
+
+    fn submit<T: Copy, U>(
+        &self,
+        vulkan: &vkstate::VulkanState,
+        slot: &mut Slot,
+        chunk: usize,
+        input: &[T],
+        layout: vk::PipelineLayout,
+        pipeline: vk::Pipeline,
+    ) {
+        let ((start, end), (input_start, input_end)) = self.ranges(chunk, input.len());
+        let chunk_input = &input[input_start..input_end];
+        slot.staging_memory
+            .map_memory(chunk_input, slot.staging_input.offset);
+
+        let count = end - start;
+        let constants: Vec<u8> = [
+            count as u32,
+            (start - input_start) as u32,
+            (input_end - input_start) as u32,
+            start as u32,
+            (start as u64 >> 32) as u32,
+        ]
+        .iter()
+        .flat_map(|c| c.to_ne_bytes())
+        .collect();
+        let groups = count.div_ceil(self.local_size);
+        let x_groups = groups.clamp(1, MAX_GROUPS);
+
+        let pool = &mut slot.cmd_pool;
+        pool.reset();
+        pool.begin_cmd(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, 0);
+        pool.copy_buffer(
+            slot.staging_input.buffer,
+            slot.input.buffer,
+            std::mem::size_of_val(chunk_input) as u64,
+            0,
+        );
+        pool.buffer_barriers(
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            &[vk::BufferMemoryBarrier::builder()
+                .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ)
+                .buffer(slot.input.buffer)
+                .size(vk::WHOLE_SIZE)
+                .build()],
+            0,
+        );
+        pool.bind_pipeline(pipeline, vk::PipelineBindPoint::COMPUTE, 0);
+        pool.bind_descriptor(
+            layout,
+            vk::PipelineBindPoint::COMPUTE,
+            &slot.descriptor.set,
+            0,
+        );
+        pool.push_constants(layout, vk::ShaderStageFlags::COMPUTE, &constants, 0);
+        pool.dispatch(x_groups as u32, groups.div_ceil(x_groups) as u32, 1, 0);
+        pool.buffer_barriers(
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::PipelineStageFlags::TRANSFER,
+            &[vk::BufferMemoryBarrier::builder()
+                .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+                .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+                .buffer(slot.output.buffer)
+                .size(vk::WHOLE_SIZE)
+                .build()],
+            0,
+        );
+        pool.copy_buffer(
+            slot.output.buffer,
+            slot.staging_output.buffer,
+            (count * std::mem::size_of::<U>()) as u64,
+            0,
+        );
+        // The host reads the output once the fence is signaled.
+        pool.buffer_barriers(
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::HOST,
+            &[vk::BufferMemoryBarrier::builder()
+                .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                .dst_access_mask(vk::AccessFlags::HOST_READ)
+                .buffer(slot.staging_output.buffer)
+                .size(vk::WHOLE_SIZE)
+                .build()],
+            0,
+        );
+        pool.end_cmd(0);
+
+        slot.fence.reset();
+        {
+            let queue = vulkan.compute_queue(0).lock().unwrap();
+            pool.submit(*queue, Some(slot.fence.fence));
+        }
+        slot.pending = Some(chunk);
+    }
+
+    /// Wait for the chunk computed in `slot`, if any, and copy its output in place.
+    fn finish<U: Copy>(&self, slot: &mut Slot, output: &mut [U]) {
+        let chunk = match slot.pending.take() {
+            Some(chunk) => chunk,
+            None => return,
+        };
+        loop {
+            match slot.fence.wait(1000 * 1000 * 1000) {
+                vk::Result::SUCCESS => break,
+                vk::Result::TIMEOUT => {}
+                err => panic!("[ERR] Could not wait for chunk {}: {}.", chunk, err),
+            }
+        }
+
+        let ((start, end), _) = self.ranges(chunk, output.len());
+        let result: Vec<U> = slot
+            .staging_memory
+            .get_memory(end - start, slot.staging_output.offset);
+        output[start..end].copy_from_slice(&result);
+    }
+}
+
+/// Create an input and an output buffer sharing one allocation, in a memory type both
+/// accept, with the `flags` if there is one.
+fn allocate_pair(
+    vulkan: Arc<vkstate::VulkanState>,
+    input_size: u64,
+    output_size: u64,
+    flags: vk::MemoryPropertyFlags,
+) -> (vkmem::VkBuffer, vkmem::VkBuffer, vkmem::VkMem) {
+    let mut buffers = vec![
+        vkmem::VkBuffer::new(vulkan.clone(), input_size),
+        vkmem::VkBuffer::new(vulkan.clone(), output_size),
+    ];
+    let (mem_size, offsets) = vkmem::compute_non_overlapping_buffer_alignment(&buffers);
+    let requirements = buffers
+        .iter()
+        .map(|buffer| buffer.get_buffer_memory_requirements())
+        .fold(
+            vk::MemoryRequirements {
+                size: mem_size,
+                alignment: 1,
+                memory_type_bits: !0,
+            },
+            |all, buffer| vk::MemoryRequirements {
+                alignment: all.alignment.max(buffer.alignment),
+                memory_type_bits: all.memory_type_bits & buffer.memory_type_bits,
+                ..all
+            },
+        );
+    let memory = vkmem::VkMem::find_mem_for(vulkan, requirements, flags)
+        .expect("[ERR] Could not find a memory type fitting our need.");
+    for (buffer, offset) in buffers.iter_mut().zip(offsets) {
+        buffer.bind(memory.mem, offset);
+    }
+    let output = buffers.pop().unwrap();
+    let input = buffers.pop().unwrap();
+    (input, output, memory)
+}
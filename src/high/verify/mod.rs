@@ -0,0 +1,170 @@
+use crate::high::job::{Job, JobStatus, JobTimings};
+use crate::utils::compare::{compare_buffers, CompareReport, Element, Tolerance};
+use crate::utils::get_fract_s;
+use crate::utils::random::SEED_VAR;
+use log::error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Checks the output of a job against a CPU implementation of the same computation.
+///
+/// The reference returns the expected content of every buffer of the job, in the order of
+/// `Job::get_output`. Each buffer is compared with the default tolerance, or its own one.
+pub struct Verification<F> {
+    reference: F,
+    tolerance: Tolerance,
+    /// Tolerance of some buffers, `None` for the ones not checked.
+    buffer_tolerances: Vec<(usize, Option<Tolerance>)>,
+    seed: Option<u64>,
+}
+
+impl<F> Verification<F> {
+    /// Compare every buffer to the output of `reference`, exactly.
+    pub fn new(reference: F) -> Verification<F> {
+        Verification {
+            reference,
+            tolerance: Tolerance::exact(),
+            buffer_tolerances: Vec::new(),
+            seed: None,
+        }
+    }
+
+    /// The tolerance of the buffers without their own.
+    pub fn tolerance(mut self, tolerance: Tolerance) -> Verification<F> {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// The tolerance of the buffer at `index` in the output.
+    pub fn buffer_tolerance(mut self, index: usize, tolerance: Tolerance) -> Verification<F> {
+        self.buffer_tolerances.push((index, Some(tolerance)));
+        self
+    }
+
+    /// Don't check the buffer at `index`, e.g. a scratch buffer. The reference may
+    /// return an empty `Vec` for it.
+    pub fn skip_buffer(mut self, index: usize) -> Verification<F> {
+        self.buffer_tolerances.push((index, None));
+        self
+    }
+
+    /// The seed the inputs were generated with, e.g. `random::Generator::seed`, which is
+    /// logged and reported when the verification fails.
+    pub fn seed(mut self, seed: u64) -> Verification<F> {
+        self.seed = Some(seed);
+        self
+    }
+
+    fn tolerance_of(&self, index: usize) -> Option<Tolerance> {
+        self.buffer_tolerances
+            .iter()
+            .rev()
+            .find(|(i, _)| *i == index)
+            .map_or(Some(self.tolerance), |(_, tolerance)| *tolerance)
+    }
+
+    /// Execute the job if it was not yet, wait for it, then run the reference.
+    pub fn run<T: Element>(self, job: &mut Job<'_, T>) -> VerifyReport<T>
+    where
+        F: FnOnce() -> Vec<Vec<T>>,
+    {
+        let start = Instant::now();
+        if job.status() == JobStatus::INIT {
+            job.execute();
+        }
+        while job.status() == JobStatus::EXECUTING {
+            job.wait_until_idle(1000 * 1000 * 1000);
+        }
+        let output = job
+            .get_output()
+            .expect("[ERR] The job failed, there is nothing to verify.");
+        let gpu_time = start.elapsed();
+
+        let tolerances: Vec<Option<Tolerance>> =
+            (0..output.len()).map(|i| self.tolerance_of(i)).collect();
+        let start = Instant::now();
+        let expected = (self.reference)();
+        let cpu_time = start.elapsed();
+        assert_eq!(
+            expected.len(),
+            output.len(),
+            "[ERR] The reference gives {} buffers, the job has {}.",
+            expected.len(),
+            output.len()
+        );
+
+        let buffers = expected
+            .iter()
+            .zip(&output)
+            .zip(tolerances)
+            .map(|((expected, actual), tolerance)| {
+                tolerance.map(|tolerance| compare_buffers(expected, actual, tolerance))
+            })
+            .collect();
+        let report = VerifyReport {
+            buffers,
+            gpu_time,
+            cpu_time,
+            job_timings: job.get_timing(),
+            seed: self.seed,
+        };
+        if let (false, Some(seed)) = (report.passed(), report.seed) {
+            error!(
+                "The verification failed, set {}={} to generate the same inputs.",
+                SEED_VAR, seed
+            );
+        }
+        report
+    }
+}
+
+/// The comparison of each buffer of a job with its CPU reference, and how long both took.
+pub struct VerifyReport<T> {
+    /// The report of each buffer, `None` for the skipped ones.
+    pub buffers: Vec<Option<CompareReport<T>>>,
+    /// From the execution of the job to the download of its output.
+    pub gpu_time: Duration,
+    pub cpu_time: Duration,
+    pub job_timings: JobTimings,
+    /// The seed of the inputs, if it was given.
+    pub seed: Option<u64>,
+}
+
+impl<T: Element> VerifyReport<T> {
+    pub fn passed(&self) -> bool {
+        self.buffers.iter().flatten().all(CompareReport::passed)
+    }
+
+    /// Panic with the report when a buffer differs from its reference.
+    pub fn assert(&self) {
+        if !self.passed() {
+            panic!("[ERR] The job differs from its reference.\n{}", self);
+        }
+    }
+}
+
+impl<T: Element> fmt::Display for VerifyReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: GPU {}ms (execution {}ms), CPU {}ms",
+            if self.passed() { "PASSED" } else { "FAILED" },
+            get_fract_s(self.gpu_time),
+            get_fract_s(self.job_timings.execution),
+            get_fract_s(self.cpu_time)
+        )?;
+        if let Some(seed) = self.seed {
+            writeln!(f, "seed: {}", seed)?;
+        }
+        for (i, buffer) in self.buffers.iter().enumerate() {
+            match buffer {
+                Some(report) => {
+                    let status = if report.passed() { "ok" } else { "FAILED" };
+                    write!(f, "buffer {} {}: {}", i, status, report)?;
+                }
+                None => writeln!(f, "buffer {} skipped", i)?,
+            }
+        }
+        Ok(())
+    }
+}
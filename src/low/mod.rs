@@ -1,7 +1,11 @@
 pub mod vkcmd;
 pub mod vkdescriptor;
+pub mod vkfeatures;
 pub mod vkfence;
+pub mod vkimage;
+pub mod vkinfo;
 pub mod vkmem;
 pub mod vkpipeline;
+pub mod vksemaphore;
 pub mod vkshader;
 pub mod vkstate;
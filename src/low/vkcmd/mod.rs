@@ -2,18 +2,27 @@ use crate::low::vkstate::VulkanState;
 
 use crate::ash::version::DeviceV1_0;
 use ash::vk;
-use std::rc::Rc;
+use std::sync::Arc;
 
+/// A command pool and its command buffers.
+/// Recording needs a mutable borrow because a pool must not be used by several threads at once,
+/// so each thread recording commands uses its own pool.
 pub struct VkCmdPool {
     pub cmd_pool: vk::CommandPool,
     pub cmd_buffers: Vec<vk::CommandBuffer>,
-    state: Rc<VulkanState>,
+    state: Arc<VulkanState>,
 }
 
 impl VkCmdPool {
-    pub fn new(state: Rc<VulkanState>) -> VkCmdPool {
+    pub fn new(state: Arc<VulkanState>) -> VkCmdPool {
+        let family = state.queue_family_index;
+        VkCmdPool::for_family(state, family)
+    }
+
+    /// A pool for the queues of another family than the compute one, e.g. the transfer family.
+    pub fn for_family(state: Arc<VulkanState>, family_index: u32) -> VkCmdPool {
         let command_pool_create_info = vk::CommandPoolCreateInfo::builder()
-            .queue_family_index(state.queue_family_index)
+            .queue_family_index(family_index)
             .build();
         let command_pool = unsafe {
             state
@@ -43,7 +52,18 @@ impl VkCmdPool {
         self.cmd_buffers.len() - 1
     }
 
-    pub fn begin_cmd(&self, usage: vk::CommandBufferUsageFlags, cmd_buffer_index: usize) {
+    /// Put every command buffer of the pool back in the initial state, to record them again.
+    /// None of them can be executing.
+    pub fn reset(&mut self) {
+        unsafe {
+            self.state
+                .device
+                .reset_command_pool(self.cmd_pool, vk::CommandPoolResetFlags::empty())
+                .expect("[ERR] Could not reset command pool.");
+        }
+    }
+
+    pub fn begin_cmd(&mut self, usage: vk::CommandBufferUsageFlags, cmd_buffer_index: usize) {
         let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder().flags(usage);
 
         unsafe {
@@ -57,7 +77,7 @@ impl VkCmdPool {
         };
     }
 
-    pub fn end_cmd(&self, cmd_buffer_index: usize) {
+    pub fn end_cmd(&mut self, cmd_buffer_index: usize) {
         unsafe {
             self.state
                 .device
@@ -67,7 +87,7 @@ impl VkCmdPool {
     }
 
     pub fn bind_pipeline(
-        &self,
+        &mut self,
         pipeline: vk::Pipeline,
         pipeline_type: vk::PipelineBindPoint,
         cmd_buffer_index: usize,
@@ -82,7 +102,7 @@ impl VkCmdPool {
     }
 
     pub fn bind_descriptor(
-        &self,
+        &mut self,
         layout: vk::PipelineLayout,
         pipeline_type: vk::PipelineBindPoint,
         descriptor_sets: &[vk::DescriptorSet],
@@ -100,7 +120,7 @@ impl VkCmdPool {
         };
     }
 
-    pub fn dispatch(&self, x: u32, y: u32, z: u32, cmd_buffer_index: usize) {
+    pub fn dispatch(&mut self, x: u32, y: u32, z: u32, cmd_buffer_index: usize) {
         unsafe {
             self.state
                 .device
@@ -108,8 +128,180 @@ impl VkCmdPool {
         };
     }
 
+    pub fn copy_buffer(
+        &mut self,
+        src: vk::Buffer,
+        dst: vk::Buffer,
+        size: u64,
+        cmd_buffer_index: usize,
+    ) {
+        let region = vk::BufferCopy::builder().size(size).build();
+        unsafe {
+            self.state.device.cmd_copy_buffer(
+                self.cmd_buffers[cmd_buffer_index],
+                src,
+                dst,
+                &[region],
+            );
+        }
+    }
+
+    pub fn buffer_barriers(
+        &mut self,
+        src_stage: vk::PipelineStageFlags,
+        dst_stage: vk::PipelineStageFlags,
+        barriers: &[vk::BufferMemoryBarrier],
+        cmd_buffer_index: usize,
+    ) {
+        unsafe {
+            self.state.device.cmd_pipeline_barrier(
+                self.cmd_buffers[cmd_buffer_index],
+                src_stage,
+                dst_stage,
+                vk::DependencyFlags::empty(),
+                &[],
+                barriers,
+                &[],
+            );
+        }
+    }
+
+    pub fn image_barriers(
+        &mut self,
+        src_stage: vk::PipelineStageFlags,
+        dst_stage: vk::PipelineStageFlags,
+        barriers: &[vk::ImageMemoryBarrier],
+        cmd_buffer_index: usize,
+    ) {
+        unsafe {
+            self.state.device.cmd_pipeline_barrier(
+                self.cmd_buffers[cmd_buffer_index],
+                src_stage,
+                dst_stage,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                barriers,
+            );
+        }
+    }
+
+    pub fn copy_buffer_to_image(
+        &mut self,
+        src: vk::Buffer,
+        dst: vk::Image,
+        dst_layout: vk::ImageLayout,
+        regions: &[vk::BufferImageCopy],
+        cmd_buffer_index: usize,
+    ) {
+        unsafe {
+            self.state.device.cmd_copy_buffer_to_image(
+                self.cmd_buffers[cmd_buffer_index],
+                src,
+                dst,
+                dst_layout,
+                regions,
+            );
+        }
+    }
+
+    pub fn copy_image_to_buffer(
+        &mut self,
+        src: vk::Image,
+        src_layout: vk::ImageLayout,
+        dst: vk::Buffer,
+        regions: &[vk::BufferImageCopy],
+        cmd_buffer_index: usize,
+    ) {
+        unsafe {
+            self.state.device.cmd_copy_image_to_buffer(
+                self.cmd_buffers[cmd_buffer_index],
+                src,
+                src_layout,
+                dst,
+                regions,
+            );
+        }
+    }
+
+    pub fn push_constants(
+        &mut self,
+        layout: vk::PipelineLayout,
+        stage: vk::ShaderStageFlags,
+        constants: &[u8],
+        cmd_buffer_index: usize,
+    ) {
+        unsafe {
+            self.state.device.cmd_push_constants(
+                self.cmd_buffers[cmd_buffer_index],
+                layout,
+                stage,
+                0,
+                constants,
+            )
+        };
+    }
+
     pub fn submit(&self, queue: vk::Queue, fence: Option<vk::Fence>) {
-        let submit_info = vk::SubmitInfo::builder().command_buffers(&self.cmd_buffers);
+        self.submit_timeline(queue, &[], &[], fence);
+    }
+
+    /// Submit every command buffer once the binary semaphores of `waits` are signaled,
+    /// each one blocking the given stages, and signal the ones of `signals` when the commands are done.
+    pub fn submit_semaphores(
+        &self,
+        queue: vk::Queue,
+        waits: &[(vk::Semaphore, vk::PipelineStageFlags)],
+        signals: &[vk::Semaphore],
+        fence: Option<vk::Fence>,
+    ) {
+        let wait_semaphores: Vec<vk::Semaphore> = waits.iter().map(|w| w.0).collect();
+        let wait_stages: Vec<vk::PipelineStageFlags> = waits.iter().map(|w| w.1).collect();
+        let submit_info = vk::SubmitInfo::builder()
+            .command_buffers(&self.cmd_buffers)
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages)
+            .signal_semaphores(signals);
+
+        unsafe {
+            self.state
+                .device
+                .queue_submit(
+                    queue,
+                    &[submit_info.build()],
+                    fence.unwrap_or(vk::Fence::null()),
+                )
+                .expect("[ERR] Could not submit queue.")
+        };
+    }
+
+    /// Submit every command buffer once the timeline semaphores of `waits` reach their value,
+    /// and set the ones of `signals` to their value when the commands are done.
+    pub fn submit_timeline(
+        &self,
+        queue: vk::Queue,
+        waits: &[(vk::Semaphore, u64)],
+        signals: &[(vk::Semaphore, u64)],
+        fence: Option<vk::Fence>,
+    ) {
+        let wait_semaphores: Vec<vk::Semaphore> = waits.iter().map(|w| w.0).collect();
+        let wait_values: Vec<u64> = waits.iter().map(|w| w.1).collect();
+        let wait_stages = vec![vk::PipelineStageFlags::COMPUTE_SHADER; waits.len()];
+        let signal_semaphores: Vec<vk::Semaphore> = signals.iter().map(|s| s.0).collect();
+        let signal_values: Vec<u64> = signals.iter().map(|s| s.1).collect();
+
+        let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::builder()
+            .wait_semaphore_values(&wait_values)
+            .signal_semaphore_values(&signal_values);
+        let mut submit_info = vk::SubmitInfo::builder()
+            .command_buffers(&self.cmd_buffers)
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages)
+            .signal_semaphores(&signal_semaphores);
+        if !waits.is_empty() || !signals.is_empty() {
+            submit_info = submit_info.push_next(&mut timeline_info);
+        }
+
         unsafe {
             self.state
                 .device
@@ -3,19 +3,19 @@ use crate::low::vkstate::VulkanState;
 
 use crate::ash::version::DeviceV1_0;
 use ash::vk;
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::RwLock;
 
 pub struct VkDescriptor {
     pub pool_sizes: Vec<vk::DescriptorPoolSize>,
     pub pool: Option<vk::DescriptorPool>,
     pub set: Vec<vk::DescriptorSet>,
-    state: Rc<VulkanState>,
-    shader: Rc<RefCell<VkShader>>,
+    state: Arc<VulkanState>,
+    shader: Arc<RwLock<VkShader>>,
 }
 
 impl VkDescriptor {
-    pub fn new(state: Rc<VulkanState>, shader: Rc<RefCell<VkShader>>) -> Self {
+    pub fn new(state: Arc<VulkanState>, shader: Arc<RwLock<VkShader>>) -> Self {
         VkDescriptor {
             pool_sizes: Vec::new(),
             pool: None,
@@ -46,7 +46,7 @@ impl VkDescriptor {
     }
 
     pub fn create_set(&mut self) {
-        let borrowed_layout = &self.shader.borrow().layout;
+        let borrowed_layout = &self.shader.read().unwrap().layout;
         let descriptor_allocate = vk::DescriptorSetAllocateInfo::builder()
             .descriptor_pool(self.pool.unwrap())
             .set_layouts(borrowed_layout);
@@ -79,11 +79,11 @@ impl Drop for VkDescriptor {
 pub struct VkWriteDescriptor {
     pub buffer_descriptors: Vec<vk::DescriptorBufferInfo>,
     pub write_descriptors: Vec<vk::WriteDescriptorSet>,
-    state: Rc<VulkanState>,
+    state: Arc<VulkanState>,
 }
 
 impl VkWriteDescriptor {
-    pub fn new(state: Rc<VulkanState>) -> Self {
+    pub fn new(state: Arc<VulkanState>) -> Self {
         VkWriteDescriptor {
             buffer_descriptors: Vec::new(),
             write_descriptors: Vec::new(),
@@ -118,6 +118,44 @@ impl VkWriteDescriptor {
         self.write_descriptors.push(write_descriptor_set);
     }
 
+    /// Write texel buffer descriptors. Like `buffer_info`, `views` must live until the update.
+    pub fn add_texel_write_descriptors(
+        &mut self,
+        descriptor_set: vk::DescriptorSet,
+        descriptor_type: vk::DescriptorType,
+        views: &[vk::BufferView],
+        dst_binding: u32,
+        dst_array: u32,
+    ) {
+        let write_descriptor_set = vk::WriteDescriptorSet::builder()
+            .dst_set(descriptor_set)
+            .dst_binding(dst_binding)
+            .dst_array_element(dst_array)
+            .descriptor_type(descriptor_type)
+            .texel_buffer_view(views)
+            .build();
+        self.write_descriptors.push(write_descriptor_set);
+    }
+
+    /// Write image descriptors. Like `buffer_info`, `images` must live until the update.
+    pub fn add_image_write_descriptors(
+        &mut self,
+        descriptor_set: vk::DescriptorSet,
+        descriptor_type: vk::DescriptorType,
+        images: &[vk::DescriptorImageInfo],
+        dst_binding: u32,
+        dst_array: u32,
+    ) {
+        let write_descriptor_set = vk::WriteDescriptorSet::builder()
+            .dst_set(descriptor_set)
+            .dst_binding(dst_binding)
+            .dst_array_element(dst_array)
+            .descriptor_type(descriptor_type)
+            .image_info(images)
+            .build();
+        self.write_descriptors.push(write_descriptor_set);
+    }
+
     pub fn update_descriptors_sets(&self) {
         unsafe {
             self.state
@@ -0,0 +1,237 @@
+use ash::version::{InstanceV1_0, InstanceV1_1};
+use ash::vk;
+use ash::Instance;
+use std::fmt;
+use std::os::raw::c_void;
+
+/// Name of the extension adding atomic operations on floats.
+pub const SHADER_ATOMIC_FLOAT: &str = "VK_EXT_shader_atomic_float";
+
+/// `VkPhysicalDeviceShaderAtomicFloatFeaturesEXT`, which this version of ash doesn't have.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct AtomicFloatFeatures {
+    s_type: vk::StructureType,
+    p_next: *mut c_void,
+    shader_buffer_float32_atomics: vk::Bool32,
+    shader_buffer_float32_atomic_add: vk::Bool32,
+    shader_buffer_float64_atomics: vk::Bool32,
+    shader_buffer_float64_atomic_add: vk::Bool32,
+    shader_shared_float32_atomics: vk::Bool32,
+    shader_shared_float32_atomic_add: vk::Bool32,
+    shader_shared_float64_atomics: vk::Bool32,
+    shader_shared_float64_atomic_add: vk::Bool32,
+    shader_image_float32_atomics: vk::Bool32,
+    shader_image_float32_atomic_add: vk::Bool32,
+    sparse_image_float32_atomics: vk::Bool32,
+    sparse_image_float32_atomic_add: vk::Bool32,
+}
+
+impl Default for AtomicFloatFeatures {
+    fn default() -> Self {
+        AtomicFloatFeatures {
+            s_type: vk::StructureType::from_raw(1_000_260_000),
+            p_next: std::ptr::null_mut(),
+            shader_buffer_float32_atomics: vk::FALSE,
+            shader_buffer_float32_atomic_add: vk::FALSE,
+            shader_buffer_float64_atomics: vk::FALSE,
+            shader_buffer_float64_atomic_add: vk::FALSE,
+            shader_shared_float32_atomics: vk::FALSE,
+            shader_shared_float32_atomic_add: vk::FALSE,
+            shader_shared_float64_atomics: vk::FALSE,
+            shader_shared_float64_atomic_add: vk::FALSE,
+            shader_image_float32_atomics: vk::FALSE,
+            shader_image_float32_atomic_add: vk::FALSE,
+            sparse_image_float32_atomics: vk::FALSE,
+            sparse_image_float32_atomic_add: vk::FALSE,
+        }
+    }
+}
+
+/// Declare the features we know, with their Vulkan name and the struct of the chain holding them.
+macro_rules! features {
+    ($($feature:ident => $name:literal, $group:ident . $($field:ident).+;)+) => {
+        /// A device feature shaders may need, see `DeviceBuilder::require_feature`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Feature {
+            $($feature,)+
+        }
+
+        impl Feature {
+            /// Every feature we know.
+            pub const ALL: &'static [Feature] = &[$(Feature::$feature,)+];
+
+            /// The name of the feature in the Vulkan specification, e.g. "shaderFloat64".
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Feature::$feature => $name,)+
+                }
+            }
+
+            /// The struct of the chain holding the feature.
+            fn group(self) -> &'static str {
+                match self {
+                    $(Feature::$feature => stringify!($group),)+
+                }
+            }
+        }
+
+        impl FeatureChain {
+            fn field(&mut self, feature: Feature) -> &mut vk::Bool32 {
+                match feature {
+                    $(Feature::$feature => &mut self.$group.$($field).+,)+
+                }
+            }
+        }
+    };
+}
+
+features! {
+    ShaderFloat64 => "shaderFloat64", core.features.shader_float64;
+    ShaderInt64 => "shaderInt64", core.features.shader_int64;
+    ShaderInt16 => "shaderInt16", core.features.shader_int16;
+    ShaderStorageImageExtendedFormats => "shaderStorageImageExtendedFormats", core.features.shader_storage_image_extended_formats;
+    ShaderStorageImageReadWithoutFormat => "shaderStorageImageReadWithoutFormat", core.features.shader_storage_image_read_without_format;
+    ShaderStorageImageWriteWithoutFormat => "shaderStorageImageWriteWithoutFormat", core.features.shader_storage_image_write_without_format;
+    StorageBuffer16BitAccess => "storageBuffer16BitAccess", vk11.storage_buffer16_bit_access;
+    UniformAndStorageBuffer16BitAccess => "uniformAndStorageBuffer16BitAccess", vk11.uniform_and_storage_buffer16_bit_access;
+    StoragePushConstant16 => "storagePushConstant16", vk11.storage_push_constant16;
+    VariablePointersStorageBuffer => "variablePointersStorageBuffer", vk11.variable_pointers_storage_buffer;
+    VariablePointers => "variablePointers", vk11.variable_pointers;
+    StorageBuffer8BitAccess => "storageBuffer8BitAccess", vk12.storage_buffer8_bit_access;
+    UniformAndStorageBuffer8BitAccess => "uniformAndStorageBuffer8BitAccess", vk12.uniform_and_storage_buffer8_bit_access;
+    StoragePushConstant8 => "storagePushConstant8", vk12.storage_push_constant8;
+    ShaderBufferInt64Atomics => "shaderBufferInt64Atomics", vk12.shader_buffer_int64_atomics;
+    ShaderSharedInt64Atomics => "shaderSharedInt64Atomics", vk12.shader_shared_int64_atomics;
+    ShaderFloat16 => "shaderFloat16", vk12.shader_float16;
+    ShaderInt8 => "shaderInt8", vk12.shader_int8;
+    DescriptorIndexing => "descriptorIndexing", vk12.descriptor_indexing;
+    RuntimeDescriptorArray => "runtimeDescriptorArray", vk12.runtime_descriptor_array;
+    ScalarBlockLayout => "scalarBlockLayout", vk12.scalar_block_layout;
+    ShaderSubgroupExtendedTypes => "shaderSubgroupExtendedTypes", vk12.shader_subgroup_extended_types;
+    HostQueryReset => "hostQueryReset", vk12.host_query_reset;
+    TimelineSemaphore => "timelineSemaphore", vk12.timeline_semaphore;
+    BufferDeviceAddress => "bufferDeviceAddress", vk12.buffer_device_address;
+    VulkanMemoryModel => "vulkanMemoryModel", vk12.vulkan_memory_model;
+    VulkanMemoryModelDeviceScope => "vulkanMemoryModelDeviceScope", vk12.vulkan_memory_model_device_scope;
+    ShaderBufferFloat32Atomics => "shaderBufferFloat32Atomics", atomic_float.shader_buffer_float32_atomics;
+    ShaderBufferFloat32AtomicAdd => "shaderBufferFloat32AtomicAdd", atomic_float.shader_buffer_float32_atomic_add;
+    ShaderBufferFloat64Atomics => "shaderBufferFloat64Atomics", atomic_float.shader_buffer_float64_atomics;
+    ShaderBufferFloat64AtomicAdd => "shaderBufferFloat64AtomicAdd", atomic_float.shader_buffer_float64_atomic_add;
+    ShaderSharedFloat32Atomics => "shaderSharedFloat32Atomics", atomic_float.shader_shared_float32_atomics;
+    ShaderSharedFloat32AtomicAdd => "shaderSharedFloat32AtomicAdd", atomic_float.shader_shared_float32_atomic_add;
+    ShaderSharedFloat64Atomics => "shaderSharedFloat64Atomics", atomic_float.shader_shared_float64_atomics;
+    ShaderSharedFloat64AtomicAdd => "shaderSharedFloat64AtomicAdd", atomic_float.shader_shared_float64_atomic_add;
+    ShaderImageFloat32Atomics => "shaderImageFloat32Atomics", atomic_float.shader_image_float32_atomics;
+    ShaderImageFloat32AtomicAdd => "shaderImageFloat32AtomicAdd", atomic_float.shader_image_float32_atomic_add;
+}
+
+impl Feature {
+    /// The device extension the feature comes from, if it is not core in Vulkan 1.2.
+    pub fn extension(self) -> Option<&'static str> {
+        match self.group() {
+            "atomic_float" => Some(SHADER_ATOMIC_FLOAT),
+            _ => None,
+        }
+    }
+
+    /// Can the feature be queried and enabled on a device supporting Vulkan 1.2 or not,
+    /// and its extension or not? The Vulkan 1.1 features are only queried through the
+    /// struct Vulkan 1.2 introduced for them.
+    pub fn is_available(self, vulkan_12: bool, extension: bool) -> bool {
+        match self.group() {
+            "core" => true,
+            "vk11" | "vk12" => vulkan_12,
+            _ => extension,
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The `VkPhysicalDeviceFeatures2` chain of every struct holding a `Feature`.
+/// It is boxed so the pointers between its structs stay valid.
+pub struct FeatureChain {
+    core: vk::PhysicalDeviceFeatures2,
+    vk11: vk::PhysicalDeviceVulkan11Features,
+    vk12: vk::PhysicalDeviceVulkan12Features,
+    atomic_float: AtomicFloatFeatures,
+}
+
+impl FeatureChain {
+    /// A chain with every feature disabled. The Vulkan 1.1 and 1.2 structs are only chained
+    /// on Vulkan 1.2 devices, the atomic float one only when its extension is enabled.
+    pub fn new(vulkan_12: bool, atomic_float: bool) -> Box<FeatureChain> {
+        let mut chain = Box::new(FeatureChain {
+            core: vk::PhysicalDeviceFeatures2::default(),
+            vk11: vk::PhysicalDeviceVulkan11Features::default(),
+            vk12: vk::PhysicalDeviceVulkan12Features::default(),
+            atomic_float: AtomicFloatFeatures::default(),
+        });
+        let mut next: *mut c_void = std::ptr::null_mut();
+        if atomic_float {
+            next = &mut chain.atomic_float as *mut AtomicFloatFeatures as *mut c_void;
+        }
+        if vulkan_12 {
+            chain.vk12.p_next = next;
+            chain.vk11.p_next = &mut chain.vk12 as *mut vk::PhysicalDeviceVulkan12Features as _;
+            next = &mut chain.vk11 as *mut vk::PhysicalDeviceVulkan11Features as *mut c_void;
+        }
+        chain.core.p_next = next;
+        chain
+    }
+
+    /// The features `physical` supports. `vkGetPhysicalDeviceFeatures2` needs a Vulkan 1.1
+    /// device, a Vulkan 1.0 one only reports its core features.
+    pub fn query(
+        instance: &Instance,
+        physical: vk::PhysicalDevice,
+        vulkan_11: bool,
+        vulkan_12: bool,
+        atomic_float: bool,
+    ) -> Box<FeatureChain> {
+        let mut chain = FeatureChain::new(vulkan_12, atomic_float);
+        if vulkan_11 {
+            unsafe { instance.get_physical_device_features2(physical, &mut chain.core) };
+        } else {
+            chain.core.features = unsafe { instance.get_physical_device_features(physical) };
+        }
+        chain
+    }
+
+    pub fn get(&mut self, feature: Feature) -> bool {
+        *self.field(feature) == vk::TRUE
+    }
+
+    pub fn enable(&mut self, feature: Feature) {
+        *self.field(feature) = vk::TRUE;
+    }
+
+    /// The core features, to give as the `p_enabled_features` of a `DeviceCreateInfo` on
+    /// Vulkan 1.0 devices, which can not take the chain.
+    pub fn core_features(&self) -> &vk::PhysicalDeviceFeatures {
+        &self.core.features
+    }
+
+    /// The head of the chain, to give as the `p_next` of a `DeviceCreateInfo`.
+    pub fn head(&self) -> *const c_void {
+        &self.core as *const vk::PhysicalDeviceFeatures2 as *const c_void
+    }
+}
+
+/// The device extensions `physical` supports.
+pub fn supported_extensions(instance: &Instance, physical: vk::PhysicalDevice) -> Vec<String> {
+    unsafe { instance.enumerate_device_extension_properties(physical) }
+        .unwrap_or_default()
+        .iter()
+        .map(|extension| {
+            unsafe { std::ffi::CStr::from_ptr(extension.extension_name.as_ptr()) }
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect()
+}
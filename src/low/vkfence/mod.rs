@@ -1,10 +1,17 @@
 use ash::version::DeviceV1_0;
 use ash::vk;
-use std::rc::Rc;
+use std::marker::PhantomData;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::Waker;
+use std::thread::{self, JoinHandle};
 
 use crate::low::vkstate::VulkanState;
 
-#[derive(Eq, PartialEq, Debug)]
+/// How long the waiter thread blocks on its fences before looking for new ones, in nanoseconds.
+const WAITER_POLL_TIMEOUT: u64 = 1000 * 1000;
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
 pub enum FenceStates {
     SIGNALED,
     UNSIGNALED,
@@ -14,11 +21,11 @@ pub enum FenceStates {
 
 pub struct VkFence {
     pub fence: vk::Fence,
-    state: Rc<VulkanState>,
+    state: Arc<VulkanState>,
 }
 
 impl VkFence {
-    pub fn new(state: Rc<VulkanState>, signaled: bool) -> Self {
+    pub fn new(state: Arc<VulkanState>, signaled: bool) -> Self {
         let mut fence_info = vk::FenceCreateInfo::builder();
         if signaled {
             fence_info = fence_info.flags(vk::FenceCreateFlags::SIGNALED);
@@ -58,18 +65,19 @@ impl VkFence {
         }
     }
 
-    pub fn wait(&self, timeout: u64) -> FenceStates {
+    /// Block until the fence is signaled or `timeout` nanoseconds have passed.
+    /// Returns `SUCCESS`, `TIMEOUT`, or the error of the wait, e.g. `ERROR_DEVICE_LOST`.
+    pub fn wait(&self, timeout: u64) -> vk::Result {
         let res = unsafe {
             self.state
                 .device
                 .wait_for_fences(&[self.fence], true, timeout)
         };
 
-        if res.is_ok() {
-            return FenceStates::SIGNALED;
+        match res {
+            Ok(()) => vk::Result::SUCCESS,
+            Err(err) => err,
         }
-
-        FenceStates::UNSIGNALED
     }
 }
 
@@ -80,3 +88,152 @@ impl Drop for VkFence {
         }
     }
 }
+
+/// What the waiter thread and the owner of a watch share.
+struct Watch {
+    fence: vk::Fence,
+    state: Mutex<WatchState>,
+    released: Condvar,
+}
+
+struct WatchState {
+    /// The state of the fence, once it is not UNSIGNALED anymore.
+    result: Option<FenceStates>,
+    waker: Option<Waker>,
+    cancelled: bool,
+    /// The waiter thread won't use the fence anymore.
+    released: bool,
+}
+
+/// A thread waiting on fences for async code, so no executor thread has to block on them.
+/// There is one per `VulkanState`, see `VulkanState::fence_waiter`.
+pub struct FenceWaiter {
+    sender: Option<Sender<Arc<Watch>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FenceWaiter {
+    pub fn new(device: ash::Device) -> FenceWaiter {
+        let (sender, receiver) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name("wyzoid-fence-waiter".to_string())
+            .spawn(move || wait_fences(device, receiver))
+            .expect("[ERR] Could not start the fence waiter thread.");
+
+        FenceWaiter {
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+
+    /// Start watching `fence`. The fence can't be destroyed before the returned watch is dropped.
+    pub fn watch<'f>(&self, fence: &'f VkFence) -> FenceWatch<'f> {
+        let watch = Arc::new(Watch {
+            fence: fence.fence,
+            state: Mutex::new(WatchState {
+                result: None,
+                waker: None,
+                cancelled: false,
+                released: false,
+            }),
+            released: Condvar::new(),
+        });
+        self.sender
+            .as_ref()
+            .unwrap()
+            .send(watch.clone())
+            .expect("[ERR] The fence waiter thread is gone.");
+
+        FenceWatch {
+            watch,
+            fence: PhantomData,
+        }
+    }
+}
+
+impl Drop for FenceWaiter {
+    fn drop(&mut self) {
+        // Closing the channel stops the thread once it has no fence left to wait on.
+        self.sender = None;
+        if let Some(thread) = self.thread.take() {
+            thread
+                .join()
+                .expect("[ERR] The fence waiter thread panicked.");
+        }
+    }
+}
+
+/// A fence watched by a `FenceWaiter`.
+pub struct FenceWatch<'f> {
+    watch: Arc<Watch>,
+    fence: PhantomData<&'f VkFence>,
+}
+
+impl<'f> FenceWatch<'f> {
+    /// The state of the fence once it is signaled (or the device is lost).
+    /// Until then, `waker` is woken when it happens.
+    pub fn poll(&self, waker: &Waker) -> Option<FenceStates> {
+        let mut state = self.watch.state.lock().unwrap();
+        if state.result.is_none() {
+            state.waker = Some(waker.clone());
+        }
+        state.result
+    }
+}
+
+impl<'f> Drop for FenceWatch<'f> {
+    fn drop(&mut self) {
+        // The fence may be destroyed right after, so we wait for the waiter thread to let it go.
+        let mut state = self.watch.state.lock().unwrap();
+        state.cancelled = true;
+        while !state.released {
+            state = self.watch.released.wait(state).unwrap();
+        }
+    }
+}
+
+fn wait_fences(device: ash::Device, receiver: Receiver<Arc<Watch>>) {
+    let mut pending: Vec<Arc<Watch>> = Vec::new();
+    loop {
+        if pending.is_empty() {
+            match receiver.recv() {
+                Ok(watch) => pending.push(watch),
+                Err(_) => return,
+            }
+        }
+        pending.extend(receiver.try_iter());
+
+        // We wake up as soon as one fence is signaled, or after a short time to pick up new fences.
+        let fences: Vec<vk::Fence> = pending.iter().map(|watch| watch.fence).collect();
+        unsafe {
+            let _ = device.wait_for_fences(&fences, false, WAITER_POLL_TIMEOUT);
+        }
+
+        pending.retain(|watch| {
+            let mut state = watch.state.lock().unwrap();
+            let result = if state.cancelled {
+                None
+            } else {
+                let status = unsafe {
+                    device
+                        .fp_v1_0()
+                        .get_fence_status(device.handle(), watch.fence)
+                };
+                match status {
+                    vk::Result::NOT_READY => return true,
+                    vk::Result::SUCCESS => Some(FenceStates::SIGNALED),
+                    vk::Result::ERROR_DEVICE_LOST => Some(FenceStates::LOST),
+                    _ => Some(FenceStates::UNKNOWN),
+                }
+            };
+
+            state.result = result;
+            state.released = true;
+            watch.released.notify_all();
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+            false
+        });
+    }
+}
@@ -0,0 +1,360 @@
+use ash::version::{DeviceV1_0, InstanceV1_0};
+use ash::vk;
+use std::sync::Arc;
+
+use crate::low::vkcmd::VkCmdPool;
+use crate::low::vkmem::{VkBuffer, VkMem};
+use crate::low::vkstate::VulkanState;
+
+/// A 2D image with one mip level and one layer, in its own device memory.
+pub struct VkImage {
+    pub image: vk::Image,
+    pub format: vk::Format,
+    pub width: u32,
+    pub height: u32,
+    pub memory: VkMem,
+    state: Arc<VulkanState>,
+}
+
+impl VkImage {
+    /// Create an image in optimal tiling, in device local memory if possible.
+    /// The device must support `usage` for `format`, e.g. `STORAGE` for `imageLoad`/`imageStore`.
+    pub fn new(
+        state: Arc<VulkanState>,
+        width: u32,
+        height: u32,
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+    ) -> Self {
+        let features = unsafe {
+            state
+                .instance
+                .get_physical_device_format_properties(state.physical_device, format)
+        }
+        .optimal_tiling_features;
+        let needed = format_features(usage);
+        if !features.contains(needed) {
+            panic!(
+                "[ERR] The device does not support {:?} images of format {:?}.",
+                usage, format
+            );
+        }
+
+        let queue_indices = &[state.queue_family_index];
+        let image_create_info = vk::ImageCreateInfo::builder()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(vk::Extent3D {
+                width,
+                height,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .queue_family_indices(queue_indices)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+        let image = unsafe {
+            state
+                .device
+                .create_image(&image_create_info, None)
+                .expect("[ERR] Could not create image.")
+        };
+
+        let requirements = unsafe { state.device.get_image_memory_requirements(image) };
+        let memory = VkMem::find_mem_for(
+            state.clone(),
+            requirements,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        )
+        .expect("[ERR] Could not find a memory type fitting the image.");
+        unsafe {
+            state
+                .device
+                .bind_image_memory(image, memory.mem, 0)
+                .expect("[ERR] Could not bind image memory.")
+        };
+
+        VkImage {
+            image,
+            format,
+            width,
+            height,
+            memory,
+            state,
+        }
+    }
+
+    /// Size of the pixels of the image, tightly packed.
+    pub fn size(&self) -> u64 {
+        self.width as u64 * self.height as u64 * format_size(self.format)
+    }
+
+    /// Record a barrier moving the image from layout `old` to `new`.
+    /// The access masks and stages are deduced from the layouts.
+    pub fn transition(
+        &self,
+        cmd_pool: &mut VkCmdPool,
+        old: vk::ImageLayout,
+        new: vk::ImageLayout,
+        cmd_buffer_index: usize,
+    ) {
+        let (src_access, src_stage) = layout_access(old);
+        let (dst_access, dst_stage) = layout_access(new);
+        let barrier = vk::ImageMemoryBarrier::builder()
+            .src_access_mask(src_access)
+            .dst_access_mask(dst_access)
+            .old_layout(old)
+            .new_layout(new)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(self.image)
+            .subresource_range(color_range())
+            .build();
+        cmd_pool.image_barriers(src_stage, dst_stage, &[barrier], cmd_buffer_index);
+    }
+
+    /// Record a copy of the tightly packed pixels in `buffer` to the image,
+    /// which must be in `TRANSFER_DST_OPTIMAL`.
+    pub fn copy_from_buffer(
+        &self,
+        cmd_pool: &mut VkCmdPool,
+        buffer: &VkBuffer,
+        cmd_buffer_index: usize,
+    ) {
+        cmd_pool.copy_buffer_to_image(
+            buffer.buffer,
+            self.image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[self.copy_region()],
+            cmd_buffer_index,
+        );
+    }
+
+    /// Record a copy of the image, which must be in `TRANSFER_SRC_OPTIMAL`,
+    /// to tightly packed pixels in `buffer`.
+    pub fn copy_to_buffer(
+        &self,
+        cmd_pool: &mut VkCmdPool,
+        buffer: &VkBuffer,
+        cmd_buffer_index: usize,
+    ) {
+        cmd_pool.copy_image_to_buffer(
+            self.image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            buffer.buffer,
+            &[self.copy_region()],
+            cmd_buffer_index,
+        );
+    }
+
+    fn copy_region(&self) -> vk::BufferImageCopy {
+        vk::BufferImageCopy::builder()
+            .buffer_offset(0)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_extent(vk::Extent3D {
+                width: self.width,
+                height: self.height,
+                depth: 1,
+            })
+            .build()
+    }
+}
+
+impl Drop for VkImage {
+    fn drop(&mut self) {
+        unsafe {
+            self.state.device.destroy_image(self.image, None);
+        }
+    }
+}
+
+pub struct VkImageView {
+    pub view: vk::ImageView,
+    state: Arc<VulkanState>,
+}
+
+impl VkImageView {
+    pub fn new(state: Arc<VulkanState>, image: &VkImage) -> Self {
+        let view_create_info = vk::ImageViewCreateInfo::builder()
+            .image(image.image)
+            .view_type(vk::ImageViewType::TYPE_2D)
+            .format(image.format)
+            .subresource_range(color_range());
+        let view = unsafe {
+            state
+                .device
+                .create_image_view(&view_create_info, None)
+                .expect("[ERR] Could not create image view.")
+        };
+        VkImageView { view, state }
+    }
+}
+
+impl Drop for VkImageView {
+    fn drop(&mut self) {
+        unsafe {
+            self.state.device.destroy_image_view(self.view, None);
+        }
+    }
+}
+
+/// How a sampled image is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplerConfig {
+    pub filter: vk::Filter,
+    pub address_mode: vk::SamplerAddressMode,
+    /// Coordinates are in [0, 1] instead of in pixels.
+    pub normalized_coordinates: bool,
+}
+
+impl Default for SamplerConfig {
+    /// Bilinear filtering, clamped to the edges, with normalized coordinates.
+    fn default() -> Self {
+        SamplerConfig {
+            filter: vk::Filter::LINEAR,
+            address_mode: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            normalized_coordinates: true,
+        }
+    }
+}
+
+pub struct VkSampler {
+    pub sampler: vk::Sampler,
+    state: Arc<VulkanState>,
+}
+
+impl VkSampler {
+    pub fn new(state: Arc<VulkanState>, config: &SamplerConfig) -> Self {
+        let sampler_create_info = vk::SamplerCreateInfo::builder()
+            .mag_filter(config.filter)
+            .min_filter(config.filter)
+            .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+            .address_mode_u(config.address_mode)
+            .address_mode_v(config.address_mode)
+            .address_mode_w(config.address_mode)
+            .max_lod(0.0)
+            .unnormalized_coordinates(!config.normalized_coordinates);
+        let sampler = unsafe {
+            state
+                .device
+                .create_sampler(&sampler_create_info, None)
+                .expect("[ERR] Could not create sampler.")
+        };
+        VkSampler { sampler, state }
+    }
+}
+
+impl Drop for VkSampler {
+    fn drop(&mut self) {
+        unsafe {
+            self.state.device.destroy_sampler(self.sampler, None);
+        }
+    }
+}
+
+/// Size of a pixel of `format` in bytes, for the uncompressed color formats.
+pub fn format_size(format: vk::Format) -> u64 {
+    match format {
+        vk::Format::R8_UNORM | vk::Format::R8_SNORM | vk::Format::R8_UINT | vk::Format::R8_SINT => {
+            1
+        }
+        vk::Format::R8G8_UNORM
+        | vk::Format::R8G8_UINT
+        | vk::Format::R16_SFLOAT
+        | vk::Format::R16_UNORM
+        | vk::Format::R16_UINT
+        | vk::Format::R16_SINT => 2,
+        vk::Format::R8G8B8A8_UNORM
+        | vk::Format::R8G8B8A8_SNORM
+        | vk::Format::R8G8B8A8_SRGB
+        | vk::Format::R8G8B8A8_UINT
+        | vk::Format::R8G8B8A8_SINT
+        | vk::Format::B8G8R8A8_UNORM
+        | vk::Format::B8G8R8A8_SRGB
+        | vk::Format::R16G16_SFLOAT
+        | vk::Format::R16G16_UNORM
+        | vk::Format::R32_SFLOAT
+        | vk::Format::R32_UINT
+        | vk::Format::R32_SINT => 4,
+        vk::Format::R16G16B16A16_SFLOAT
+        | vk::Format::R16G16B16A16_UNORM
+        | vk::Format::R16G16B16A16_UINT
+        | vk::Format::R32G32_SFLOAT
+        | vk::Format::R32G32_UINT
+        | vk::Format::R32G32_SINT => 8,
+        vk::Format::R32G32B32A32_SFLOAT
+        | vk::Format::R32G32B32A32_UINT
+        | vk::Format::R32G32B32A32_SINT => 16,
+        _ => panic!("[ERR] Unsupported image format {:?}.", format),
+    }
+}
+
+/// The format features an image needs for `usage`.
+fn format_features(usage: vk::ImageUsageFlags) -> vk::FormatFeatureFlags {
+    let mut features = vk::FormatFeatureFlags::empty();
+    if usage.contains(vk::ImageUsageFlags::STORAGE) {
+        features |= vk::FormatFeatureFlags::STORAGE_IMAGE;
+    }
+    if usage.contains(vk::ImageUsageFlags::SAMPLED) {
+        features |= vk::FormatFeatureFlags::SAMPLED_IMAGE;
+    }
+    features
+}
+
+/// Can the device filter images of `format` linearly?
+pub fn supports_linear_filter(state: &VulkanState, format: vk::Format) -> bool {
+    unsafe {
+        state
+            .instance
+            .get_physical_device_format_properties(state.physical_device, format)
+    }
+    .optimal_tiling_features
+    .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
+}
+
+/// The accesses to synchronize, and where they happen, for an image in `layout`.
+fn layout_access(layout: vk::ImageLayout) -> (vk::AccessFlags, vk::PipelineStageFlags) {
+    match layout {
+        vk::ImageLayout::UNDEFINED => (
+            vk::AccessFlags::empty(),
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+        ),
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL => (
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL => (
+            vk::AccessFlags::TRANSFER_READ,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL => (
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+        ),
+        _ => (
+            vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+        ),
+    }
+}
+
+fn color_range() -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level: 0,
+        level_count: 1,
+        base_array_layer: 0,
+        layer_count: 1,
+    }
+}
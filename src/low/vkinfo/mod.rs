@@ -0,0 +1,442 @@
+use ash::version::{InstanceV1_0, InstanceV1_1};
+use ash::vk;
+use ash::Instance;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ffi::CStr;
+use std::fmt;
+use std::os::raw::{c_char, c_void};
+
+/// What a physical device is and what it can do, queried once when the vulkan state is created.
+///
+/// With the `serde` feature, it can be serialized to record which device produced a result.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeviceInfo {
+    pub name: String,
+    pub vendor_id: u32,
+    pub vendor: String,
+    pub device_id: u32,
+    pub device_type: String,
+    /// Vulkan version supported by the device, as "major.minor.patch".
+    pub api_version: String,
+    /// Driver version, decoded the way its vendor encodes it.
+    pub driver_version: String,
+    /// Name and description of the driver, empty when the device does not support Vulkan 1.2.
+    pub driver_name: String,
+    pub driver_info: String,
+    /// UUID of the device, as 32 hexadecimal digits.
+    pub uuid: String,
+    pub queue_families: Vec<QueueFamilyInfo>,
+    pub memory_heaps: Vec<MemoryHeapInfo>,
+    pub memory_types: Vec<MemoryTypeInfo>,
+    pub limits: ComputeLimits,
+    pub subgroup: SubgroupInfo,
+    /// Names of the supported Vulkan 1.1 features, e.g. "storage_buffer16_bit_access".
+    /// Both feature lists are empty when the device does not support Vulkan 1.2.
+    pub features_11: Vec<String>,
+    /// Names of the supported Vulkan 1.2 features, e.g. "timeline_semaphore".
+    pub features_12: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct QueueFamilyInfo {
+    pub queue_count: u32,
+    /// e.g. ["GRAPHICS", "COMPUTE", "TRANSFER"].
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MemoryHeapInfo {
+    pub size: u64,
+    pub device_local: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MemoryTypeInfo {
+    pub heap_index: u32,
+    /// e.g. ["HOST_VISIBLE", "HOST_COHERENT"].
+    pub flags: Vec<String>,
+}
+
+/// The limits of the device that matter for compute shaders.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ComputeLimits {
+    pub max_compute_work_group_count: [u32; 3],
+    pub max_compute_work_group_size: [u32; 3],
+    pub max_compute_work_group_invocations: u32,
+    pub max_compute_shared_memory_size: u32,
+    pub max_storage_buffer_range: u32,
+    pub max_per_stage_descriptor_storage_buffers: u32,
+    pub max_push_constants_size: u32,
+    pub max_bound_descriptor_sets: u32,
+    pub max_memory_allocation_count: u32,
+    pub min_storage_buffer_offset_alignment: u64,
+    /// Nanoseconds per timestamp tick.
+    pub timestamp_period: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SubgroupInfo {
+    pub size: u32,
+    /// Shader stages with subgroup operations, e.g. ["COMPUTE"].
+    pub supported_stages: Vec<String>,
+    /// e.g. ["BASIC", "VOTE", "ARITHMETIC", "BALLOT"].
+    pub supported_operations: Vec<String>,
+    pub quad_operations_in_all_stages: bool,
+}
+
+impl DeviceInfo {
+    /// Query everything about `physical`. The instance must be created for Vulkan 1.1 or later.
+    /// The subgroup properties and UUID are left empty on Vulkan 1.0 devices, which can not
+    /// be queried with `vkGetPhysicalDeviceProperties2`.
+    pub fn query(instance: &Instance, physical: vk::PhysicalDevice) -> DeviceInfo {
+        let properties = unsafe { instance.get_physical_device_properties(physical) };
+        let supports_11 = vk::version_major(properties.api_version) > 1
+            || vk::version_minor(properties.api_version) >= 1;
+        let supports_12 = vk::version_major(properties.api_version) > 1
+            || vk::version_minor(properties.api_version) >= 2;
+
+        // The Vulkan11 and Vulkan12 structs can only be chained on Vulkan 1.2 devices.
+        let mut properties_12 = vk::PhysicalDeviceVulkan12Properties::default();
+        let mut id_properties = vk::PhysicalDeviceIDProperties {
+            p_next: if supports_12 {
+                &mut properties_12 as *mut vk::PhysicalDeviceVulkan12Properties as *mut c_void
+            } else {
+                std::ptr::null_mut()
+            },
+            ..Default::default()
+        };
+        let mut subgroup = vk::PhysicalDeviceSubgroupProperties {
+            p_next: &mut id_properties as *mut vk::PhysicalDeviceIDProperties as *mut c_void,
+            ..Default::default()
+        };
+        let mut properties2 = vk::PhysicalDeviceProperties2 {
+            p_next: &mut subgroup as *mut vk::PhysicalDeviceSubgroupProperties as *mut c_void,
+            ..Default::default()
+        };
+        if supports_11 {
+            unsafe { instance.get_physical_device_properties2(physical, &mut properties2) };
+        }
+
+        let mut features_12 = vk::PhysicalDeviceVulkan12Features::default();
+        let mut features_11 = vk::PhysicalDeviceVulkan11Features {
+            p_next: &mut features_12 as *mut vk::PhysicalDeviceVulkan12Features as *mut c_void,
+            ..Default::default()
+        };
+        if supports_12 {
+            let mut features2 = vk::PhysicalDeviceFeatures2 {
+                p_next: &mut features_11 as *mut vk::PhysicalDeviceVulkan11Features as *mut c_void,
+                ..Default::default()
+            };
+            unsafe { instance.get_physical_device_features2(physical, &mut features2) };
+        }
+
+        let memory = unsafe { instance.get_physical_device_memory_properties(physical) };
+        let families = unsafe { instance.get_physical_device_queue_family_properties(physical) };
+        let limits = properties.limits;
+
+        DeviceInfo {
+            name: c_string(&properties.device_name),
+            vendor_id: properties.vendor_id,
+            vendor: vendor_name(properties.vendor_id),
+            device_id: properties.device_id,
+            device_type: format!("{:?}", properties.device_type),
+            api_version: version_string(properties.api_version),
+            driver_version: driver_version_string(properties.vendor_id, properties.driver_version),
+            driver_name: c_string(&properties_12.driver_name),
+            driver_info: c_string(&properties_12.driver_info),
+            uuid: id_properties
+                .device_uuid
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            queue_families: families
+                .iter()
+                .map(|family| QueueFamilyInfo {
+                    queue_count: family.queue_count,
+                    flags: flag_names(family.queue_flags),
+                })
+                .collect(),
+            memory_heaps: memory.memory_heaps[..memory.memory_heap_count as usize]
+                .iter()
+                .map(|heap| MemoryHeapInfo {
+                    size: heap.size,
+                    device_local: heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL),
+                })
+                .collect(),
+            memory_types: memory.memory_types[..memory.memory_type_count as usize]
+                .iter()
+                .map(|memory_type| MemoryTypeInfo {
+                    heap_index: memory_type.heap_index,
+                    flags: flag_names(memory_type.property_flags),
+                })
+                .collect(),
+            limits: ComputeLimits {
+                max_compute_work_group_count: limits.max_compute_work_group_count,
+                max_compute_work_group_size: limits.max_compute_work_group_size,
+                max_compute_work_group_invocations: limits.max_compute_work_group_invocations,
+                max_compute_shared_memory_size: limits.max_compute_shared_memory_size,
+                max_storage_buffer_range: limits.max_storage_buffer_range,
+                max_per_stage_descriptor_storage_buffers: limits
+                    .max_per_stage_descriptor_storage_buffers,
+                max_push_constants_size: limits.max_push_constants_size,
+                max_bound_descriptor_sets: limits.max_bound_descriptor_sets,
+                max_memory_allocation_count: limits.max_memory_allocation_count,
+                min_storage_buffer_offset_alignment: limits.min_storage_buffer_offset_alignment,
+                timestamp_period: limits.timestamp_period,
+            },
+            subgroup: SubgroupInfo {
+                size: subgroup.subgroup_size,
+                supported_stages: flag_names(subgroup.supported_stages),
+                supported_operations: flag_names(subgroup.supported_operations),
+                quad_operations_in_all_stages: subgroup.quad_operations_in_all_stages == vk::TRUE,
+            },
+            features_11: supported_features_11(&features_11),
+            features_12: supported_features_12(&features_12),
+        }
+    }
+
+    /// Is the Vulkan 1.1 or 1.2 feature called `name` supported?
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.features_11
+            .iter()
+            .chain(self.features_12.iter())
+            .any(|feature| feature == name)
+    }
+}
+
+impl fmt::Display for DeviceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({}, {})", self.name, self.vendor, self.device_type)?;
+        writeln!(f, "\t* Vulkan: {}", self.api_version)?;
+        writeln!(
+            f,
+            "\t* Driver: {} {} {}",
+            self.driver_name, self.driver_version, self.driver_info
+        )?;
+        writeln!(f, "\t* UUID: {}", self.uuid)?;
+        for (i, family) in self.queue_families.iter().enumerate() {
+            writeln!(
+                f,
+                "\t* Queue family {}: {} queues, {}",
+                i,
+                family.queue_count,
+                family.flags.join(" | ")
+            )?;
+        }
+        for (i, heap) in self.memory_heaps.iter().enumerate() {
+            writeln!(
+                f,
+                "\t* Memory heap {}: {:.0} Mio{}",
+                i,
+                heap.size as f64 / 1024.0 / 1024.0,
+                if heap.device_local {
+                    ", device local"
+                } else {
+                    ""
+                }
+            )?;
+        }
+        for (i, memory_type) in self.memory_types.iter().enumerate() {
+            writeln!(
+                f,
+                "\t* Memory type {}: heap {}, {}",
+                i,
+                memory_type.heap_index,
+                memory_type.flags.join(" | ")
+            )?;
+        }
+        let limits = &self.limits;
+        writeln!(
+            f,
+            "\t* Max work group count: {:?}",
+            limits.max_compute_work_group_count
+        )?;
+        writeln!(
+            f,
+            "\t* Max work group size: {:?}, {} invocations",
+            limits.max_compute_work_group_size, limits.max_compute_work_group_invocations
+        )?;
+        writeln!(
+            f,
+            "\t* Max shared memory: {} bytes",
+            limits.max_compute_shared_memory_size
+        )?;
+        writeln!(
+            f,
+            "\t* Max storage buffer range: {} bytes, {} per stage",
+            limits.max_storage_buffer_range, limits.max_per_stage_descriptor_storage_buffers
+        )?;
+        writeln!(
+            f,
+            "\t* Max push constants: {} bytes",
+            limits.max_push_constants_size
+        )?;
+        writeln!(
+            f,
+            "\t* Min storage buffer offset alignment: {} bytes",
+            limits.min_storage_buffer_offset_alignment
+        )?;
+        writeln!(
+            f,
+            "\t* Subgroup: {} invocations, {} in {}",
+            self.subgroup.size,
+            self.subgroup.supported_operations.join(" | "),
+            self.subgroup.supported_stages.join(" | ")
+        )?;
+        writeln!(
+            f,
+            "\t* Vulkan 1.1 features: {}",
+            self.features_11.join(", ")
+        )?;
+        write!(
+            f,
+            "\t* Vulkan 1.2 features: {}",
+            self.features_12.join(", ")
+        )
+    }
+}
+
+fn c_string(chars: &[c_char]) -> String {
+    unsafe { CStr::from_ptr(chars.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The names of the bits set in some vulkan flags, from their debug output "A | B".
+fn flag_names<F: fmt::Debug>(flags: F) -> Vec<String> {
+    let names = format!("{:?}", flags);
+    if names == "(empty)" || names.is_empty() {
+        return Vec::new();
+    }
+    names.split(" | ").map(String::from).collect()
+}
+
+fn version_string(version: u32) -> String {
+    format!(
+        "{}.{}.{}",
+        vk::version_major(version),
+        vk::version_minor(version),
+        vk::version_patch(version)
+    )
+}
+
+fn vendor_name(vendor_id: u32) -> String {
+    match vendor_id {
+        0x1002 => "AMD",
+        0x1010 => "ImgTec",
+        0x10de => "NVIDIA",
+        0x13b5 => "ARM",
+        0x5143 => "Qualcomm",
+        0x8086 => "Intel",
+        0x10005 => "Mesa",
+        _ => "Unknown vendor",
+    }
+    .to_string()
+}
+
+fn driver_version_string(vendor_id: u32, version: u32) -> String {
+    match vendor_id {
+        // NVIDIA packs its version in 10.8.8.6 bits.
+        0x10de => format!(
+            "{}.{}.{}.{}",
+            version >> 22,
+            (version >> 14) & 0xff,
+            (version >> 6) & 0xff,
+            version & 0x3f
+        ),
+        // Intel on Windows packs it in 18.14 bits.
+        0x8086 if cfg!(windows) => format!("{}.{}", version >> 14, version & 0x3fff),
+        _ => version_string(version),
+    }
+}
+
+/// Name the fields of a features struct which are set to `vk::TRUE`.
+macro_rules! supported_features {
+    ($features:expr, $($field:ident),+ $(,)?) => {{
+        let mut supported = Vec::new();
+        $(
+            if $features.$field == vk::TRUE {
+                supported.push(stringify!($field).to_string());
+            }
+        )+
+        supported
+    }};
+}
+
+fn supported_features_11(features: &vk::PhysicalDeviceVulkan11Features) -> Vec<String> {
+    supported_features!(
+        features,
+        storage_buffer16_bit_access,
+        uniform_and_storage_buffer16_bit_access,
+        storage_push_constant16,
+        storage_input_output16,
+        multiview,
+        multiview_geometry_shader,
+        multiview_tessellation_shader,
+        variable_pointers_storage_buffer,
+        variable_pointers,
+        protected_memory,
+        sampler_ycbcr_conversion,
+        shader_draw_parameters,
+    )
+}
+
+fn supported_features_12(features: &vk::PhysicalDeviceVulkan12Features) -> Vec<String> {
+    supported_features!(
+        features,
+        sampler_mirror_clamp_to_edge,
+        draw_indirect_count,
+        storage_buffer8_bit_access,
+        uniform_and_storage_buffer8_bit_access,
+        storage_push_constant8,
+        shader_buffer_int64_atomics,
+        shader_shared_int64_atomics,
+        shader_float16,
+        shader_int8,
+        descriptor_indexing,
+        shader_input_attachment_array_dynamic_indexing,
+        shader_uniform_texel_buffer_array_dynamic_indexing,
+        shader_storage_texel_buffer_array_dynamic_indexing,
+        shader_uniform_buffer_array_non_uniform_indexing,
+        shader_sampled_image_array_non_uniform_indexing,
+        shader_storage_buffer_array_non_uniform_indexing,
+        shader_storage_image_array_non_uniform_indexing,
+        shader_input_attachment_array_non_uniform_indexing,
+        shader_uniform_texel_buffer_array_non_uniform_indexing,
+        shader_storage_texel_buffer_array_non_uniform_indexing,
+        descriptor_binding_uniform_buffer_update_after_bind,
+        descriptor_binding_sampled_image_update_after_bind,
+        descriptor_binding_storage_image_update_after_bind,
+        descriptor_binding_storage_buffer_update_after_bind,
+        descriptor_binding_uniform_texel_buffer_update_after_bind,
+        descriptor_binding_storage_texel_buffer_update_after_bind,
+        descriptor_binding_update_unused_while_pending,
+        descriptor_binding_partially_bound,
+        descriptor_binding_variable_descriptor_count,
+        runtime_descriptor_array,
+        sampler_filter_minmax,
+        scalar_block_layout,
+        imageless_framebuffer,
+        uniform_buffer_standard_layout,
+        shader_subgroup_extended_types,
+        separate_depth_stencil_layouts,
+        host_query_reset,
+        timeline_semaphore,
+        buffer_device_address,
+        buffer_device_address_capture_replay,
+        buffer_device_address_multi_device,
+        vulkan_memory_model,
+        vulkan_memory_model_device_scope,
+        vulkan_memory_model_availability_visibility_chains,
+        shader_output_viewport_index,
+        shader_output_layer,
+        subgroup_broadcast_dynamic_id,
+    )
+}
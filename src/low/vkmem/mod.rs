@@ -4,14 +4,16 @@ use ash::vk::DeviceMemory;
 
 use crate::low::vkstate::VulkanState;
 use log::info;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 pub struct VkMem {
     pub size: u64,
     pub index: u32,
     pub mem: DeviceMemory,
 
-    state: Rc<VulkanState>,
+    /// Mapping a memory must be externally synchronized, so only one thread maps it at a time.
+    map_lock: Mutex<()>,
+    state: Arc<VulkanState>,
 }
 
 /// For the moment, I am going to assume that 1 MemAlloc = 1 Buffer.
@@ -20,15 +22,21 @@ pub struct VkBuffer {
     pub size: u64,
     pub offset: u64,
     pub buffer: vk::Buffer,
-    state: Rc<VulkanState>,
+    state: Arc<VulkanState>,
 }
 
 impl VkBuffer {
-    pub fn new(vkstate: Rc<VulkanState>, size: u64) -> Self {
+    /// A storage buffer which can be copied from and to.
+    pub fn new(vkstate: Arc<VulkanState>, size: u64) -> Self {
+        VkBuffer::with_usage(vkstate, size, vk::BufferUsageFlags::STORAGE_BUFFER)
+    }
+
+    /// A buffer bound as `usage`, which can be copied from and to.
+    pub fn with_usage(vkstate: Arc<VulkanState>, size: u64, usage: vk::BufferUsageFlags) -> Self {
         let queue_indices = &[vkstate.queue_family_index];
         let buffer_create_info = vk::BufferCreateInfo::builder()
             .size(size)
-            .usage(vk::BufferUsageFlags::STORAGE_BUFFER)
+            .usage(usage | vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::TRANSFER_DST)
             .sharing_mode(vk::SharingMode::EXCLUSIVE)
             .queue_family_indices(queue_indices);
 
@@ -74,6 +82,62 @@ impl VkBuffer {
     }
 }
 
+/// A view of a buffer as an array of texels of some format, for texel buffer descriptors.
+pub struct VkBufferView {
+    pub view: vk::BufferView,
+    pub format: vk::Format,
+    state: Arc<VulkanState>,
+}
+
+impl VkBufferView {
+    /// View the whole `buffer` as texels of `format`. The buffer must have been created with
+    /// the texel buffer usage of `feature`, which the format must support.
+    pub fn new(
+        vkstate: Arc<VulkanState>,
+        buffer: &VkBuffer,
+        format: vk::Format,
+        feature: vk::FormatFeatureFlags,
+    ) -> Self {
+        let properties = unsafe {
+            vkstate
+                .instance
+                .get_physical_device_format_properties(vkstate.physical_device, format)
+        };
+        if !properties.buffer_features.contains(feature) {
+            panic!(
+                "[ERR] The device does not support {:?} texel buffers of format {:?}.",
+                feature, format
+            );
+        }
+
+        let view_create_info = vk::BufferViewCreateInfo::builder()
+            .buffer(buffer.buffer)
+            .format(format)
+            .offset(0)
+            .range(vk::WHOLE_SIZE);
+        let view = unsafe {
+            vkstate
+                .device
+                .create_buffer_view(&view_create_info, None)
+                .expect("[ERR] Could not create buffer view.")
+        };
+
+        VkBufferView {
+            view,
+            format,
+            state: vkstate,
+        }
+    }
+}
+
+impl Drop for VkBufferView {
+    fn drop(&mut self) {
+        unsafe {
+            self.state.device.destroy_buffer_view(self.view, None);
+        }
+    }
+}
+
 /// Return (minimum memory size needed, buffers offsets)
 pub fn compute_non_overlapping_buffer_alignment(buffers: &Vec<VkBuffer>) -> (u64, Vec<u64>) {
     let mut min_size = 0;
@@ -99,6 +163,29 @@ pub fn compute_non_overlapping_buffer_alignment(buffers: &Vec<VkBuffer>) -> (u64
     (min_size, offsets)
 }
 
+/// The requirements of a memory of `size` bytes holding all the `buffers`: the memory
+/// types every buffer accepts, and the largest of their alignments.
+pub fn combined_requirements<'b, I>(buffers: I, size: u64) -> vk::MemoryRequirements
+where
+    I: IntoIterator<Item = &'b VkBuffer>,
+{
+    buffers.into_iter().fold(
+        vk::MemoryRequirements {
+            size,
+            alignment: 1,
+            memory_type_bits: !0,
+        },
+        |all, buffer| {
+            let requirements = buffer.get_buffer_memory_requirements();
+            vk::MemoryRequirements {
+                alignment: all.alignment.max(requirements.alignment),
+                memory_type_bits: all.memory_type_bits & requirements.memory_type_bits,
+                ..all
+            }
+        },
+    )
+}
+
 impl Drop for VkBuffer {
     fn drop(&mut self) {
         unsafe {
@@ -108,7 +195,20 @@ impl Drop for VkBuffer {
 }
 
 impl VkMem {
-    pub fn find_mem(vkstate: Rc<VulkanState>, size: u64) -> Option<Self> {
+    pub fn find_mem(vkstate: Arc<VulkanState>, size: u64) -> Option<Self> {
+        VkMem::find_mem_with_flags(
+            vkstate,
+            size,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )
+    }
+
+    /// Allocate `size` bytes in a memory type having all the `flags`.
+    pub fn find_mem_with_flags(
+        vkstate: Arc<VulkanState>,
+        size: u64,
+        flags: vk::MemoryPropertyFlags,
+    ) -> Option<Self> {
         let mem_props = unsafe {
             vkstate
                 .instance
@@ -123,23 +223,14 @@ impl VkMem {
                 i,
                 buffer_max_size as f64 / 1024.0 / 1024.0
             );
-            if mem_type_props
-                .property_flags
-                .contains(vk::MemoryPropertyFlags::HOST_VISIBLE)
-                && mem_type_props
-                    .property_flags
-                    .contains(vk::MemoryPropertyFlags::HOST_COHERENT)
+            if mem_type_props.property_flags.contains(flags)
                 && mem_props.memory_heaps[mem_type_props.heap_index as usize].size > size
             {
                 mem_index = Some(i);
             }
         }
 
-        if mem_index.is_none() {
-            return None;
-        }
-
-        let mem_index = mem_index.unwrap();
+        let mem_index = mem_index?;
         let allocate_nfo = vk::MemoryAllocateInfo::builder()
             .allocation_size(size)
             .memory_type_index(mem_index)
@@ -152,16 +243,68 @@ impl VkMem {
         };
 
         let mem_struct: VkMem = VkMem {
-            size: size,
+            size,
             index: mem_index,
             mem: vulkan_mem,
+            map_lock: Mutex::new(()),
             state: vkstate,
         };
         Some(mem_struct)
     }
 
-    pub fn map_memory<T>(&self, data: &Vec<T>, offset: u64) {
-        let size = (data.len() * std::mem::size_of::<T>()) as u64;
+    /// Allocate memory for a resource with `requirements`, in a memory type having all
+    /// the `flags` if there is one, or in any memory type the resource accepts otherwise.
+    pub fn find_mem_for(
+        vkstate: Arc<VulkanState>,
+        requirements: vk::MemoryRequirements,
+        flags: vk::MemoryPropertyFlags,
+    ) -> Option<Self> {
+        let mem_props = unsafe {
+            vkstate
+                .instance
+                .get_physical_device_memory_properties(vkstate.physical_device)
+        };
+        let accepted = |i: &u32| requirements.memory_type_bits & (1 << i) != 0;
+        let with_flags = (0..mem_props.memory_type_count).filter(accepted).find(|i| {
+            mem_props.memory_types[*i as usize]
+                .property_flags
+                .contains(flags)
+        });
+        let mem_index = with_flags.or_else(|| (0..mem_props.memory_type_count).find(accepted))?;
+
+        let allocate_nfo = vk::MemoryAllocateInfo::builder()
+            .allocation_size(requirements.size)
+            .memory_type_index(mem_index)
+            .build();
+        let vulkan_mem = unsafe {
+            vkstate
+                .device
+                .allocate_memory(&allocate_nfo, None)
+                .expect("[ERR] Could not allocate memory in device.")
+        };
+
+        Some(VkMem {
+            size: requirements.size,
+            index: mem_index,
+            mem: vulkan_mem,
+            map_lock: Mutex::new(()),
+            state: vkstate,
+        })
+    }
+
+    /// The properties of the memory type this memory was allocated in.
+    pub fn property_flags(&self) -> vk::MemoryPropertyFlags {
+        let mem_props = unsafe {
+            self.state
+                .instance
+                .get_physical_device_memory_properties(self.state.physical_device)
+        };
+        mem_props.memory_types[self.index as usize].property_flags
+    }
+
+    pub fn map_memory<T>(&self, data: &[T], offset: u64) {
+        let _mapped = self.map_lock.lock().unwrap();
+        let size = std::mem::size_of_val(data) as u64;
         let buffer: *mut T = unsafe {
             self.state
                 .device
@@ -178,7 +321,8 @@ impl VkMem {
         }
     }
 
-    pub fn map_buffer<T>(&self, data: &Vec<T>, buffer: &VkBuffer) {
+    pub fn map_buffer<T>(&self, data: &[T], buffer: &VkBuffer) {
+        let _mapped = self.map_lock.lock().unwrap();
         let pp_data: *mut T = unsafe {
             self.state
                 .device
@@ -201,6 +345,7 @@ impl VkMem {
     }
 
     pub fn get_memory<T>(&self, capacity: usize, offset: u64) -> Vec<T> {
+        let _mapped = self.map_lock.lock().unwrap();
         let mut output: Vec<T> = Vec::with_capacity(capacity);
         let size = (capacity * std::mem::size_of::<T>()) as u64;
         let buffer: *mut T = unsafe {
@@ -223,6 +368,7 @@ impl VkMem {
     }
 
     pub fn get_buffer<T>(&self, buffer: &VkBuffer) -> Vec<T> {
+        let _mapped = self.map_lock.lock().unwrap();
         let capacity: usize = (buffer.size as usize) / std::mem::size_of::<T>();
         let mut output: Vec<T> = Vec::with_capacity(capacity);
         let pp_data: *mut T = unsafe {
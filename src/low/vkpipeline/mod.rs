@@ -3,15 +3,15 @@ use crate::low::vkstate::VulkanState;
 
 use crate::ash::version::DeviceV1_0;
 use ash::vk;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct VkComputePipeline {
     pub pipeline: vk::Pipeline,
-    state: Rc<VulkanState>,
+    state: Arc<VulkanState>,
 }
 
 impl VkComputePipeline {
-    pub fn new(state: Rc<VulkanState>, shader: &VkShader) -> Self {
+    pub fn new(state: Arc<VulkanState>, shader: &VkShader) -> Self {
         let stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
             .module(shader.module)
             .stage(vk::ShaderStageFlags::COMPUTE)
@@ -31,7 +31,7 @@ impl VkComputePipeline {
 
         VkComputePipeline {
             pipeline: compute_pipeline,
-            state: state,
+            state,
         }
     }
 }
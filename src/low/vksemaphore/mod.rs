@@ -0,0 +1,105 @@
+use ash::version::{DeviceV1_0, DeviceV1_2};
+use ash::vk;
+use std::sync::Arc;
+
+use crate::low::vkstate::VulkanState;
+
+/// A binary semaphore, signaled by a submission and waited on by another one.
+pub struct VkSemaphore {
+    pub semaphore: vk::Semaphore,
+    state: Arc<VulkanState>,
+}
+
+impl VkSemaphore {
+    pub fn new(state: Arc<VulkanState>) -> Self {
+        let semaphore_info = vk::SemaphoreCreateInfo::builder();
+        let semaphore = unsafe {
+            state
+                .device
+                .create_semaphore(&semaphore_info, None)
+                .expect("[ERR] Could not create semaphore.")
+        };
+
+        VkSemaphore { semaphore, state }
+    }
+}
+
+impl Drop for VkSemaphore {
+    fn drop(&mut self) {
+        unsafe {
+            self.state.device.destroy_semaphore(self.semaphore, None);
+        }
+    }
+}
+
+/// A Vulkan 1.2 timeline semaphore: a counter signaled by the GPU or the host,
+/// that submissions or the host can wait on until it reaches a value.
+pub struct VkTimelineSemaphore {
+    pub semaphore: vk::Semaphore,
+    state: Arc<VulkanState>,
+}
+
+impl VkTimelineSemaphore {
+    pub fn new(state: Arc<VulkanState>, initial_value: u64) -> Self {
+        let mut type_info = vk::SemaphoreTypeCreateInfo::builder()
+            .semaphore_type(vk::SemaphoreType::TIMELINE)
+            .initial_value(initial_value);
+        let semaphore_info = vk::SemaphoreCreateInfo::builder().push_next(&mut type_info);
+
+        let semaphore = unsafe {
+            state
+                .device
+                .create_semaphore(&semaphore_info, None)
+                .expect("[ERR] Could not create timeline semaphore.")
+        };
+
+        VkTimelineSemaphore { semaphore, state }
+    }
+
+    /// Current value of the counter.
+    pub fn value(&self) -> u64 {
+        unsafe {
+            self.state
+                .device
+                .get_semaphore_counter_value(self.semaphore)
+                .expect("[ERR] Could not get the timeline semaphore value.")
+        }
+    }
+
+    /// Set the counter to `value` from the host. It can only go up.
+    pub fn signal(&self, value: u64) {
+        let signal_info = vk::SemaphoreSignalInfo::builder()
+            .semaphore(self.semaphore)
+            .value(value);
+        unsafe {
+            self.state
+                .device
+                .signal_semaphore(&signal_info)
+                .expect("[ERR] Could not signal timeline semaphore.");
+        }
+    }
+
+    /// Block until the counter reaches `value` or `timeout` nanoseconds have passed.
+    /// Returns `SUCCESS`, `TIMEOUT`, or the error of the wait, e.g. `ERROR_DEVICE_LOST`.
+    pub fn wait(&self, value: u64, timeout: u64) -> vk::Result {
+        let semaphores = [self.semaphore];
+        let values = [value];
+        let wait_info = vk::SemaphoreWaitInfo::builder()
+            .semaphores(&semaphores)
+            .values(&values);
+        let res = unsafe { self.state.device.wait_semaphores(&wait_info, timeout) };
+
+        match res {
+            Ok(()) => vk::Result::SUCCESS,
+            Err(err) => err,
+        }
+    }
+}
+
+impl Drop for VkTimelineSemaphore {
+    fn drop(&mut self) {
+        unsafe {
+            self.state.device.destroy_semaphore(self.semaphore, None);
+        }
+    }
+}
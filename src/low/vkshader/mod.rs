@@ -1,8 +1,9 @@
 use ash::version::DeviceV1_0;
 use ash::vk;
 use std::ffi::CString;
+use std::io::Cursor;
 use std::path::PathBuf;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::low::vkstate::VulkanState;
 use crate::utils::{load_file, to_vec32};
@@ -11,16 +12,37 @@ pub struct VkShader {
     pub bytecode: Vec<u32>,
     pub module: vk::ShaderModule,
     pub layouts_bindings: Vec<vk::DescriptorSetLayoutBinding>,
+    pub push_constant_ranges: Vec<vk::PushConstantRange>,
     pub layout: Vec<vk::DescriptorSetLayout>,
     pub pipeline: Option<vk::PipelineLayout>,
     pub entry_point: CString,
-    state: Rc<VulkanState>,
+    state: Arc<VulkanState>,
 }
 
+// The only pointer in a shader is the immutable samplers of its layout bindings,
+// which are never set, so sharing a shader between threads is fine.
+unsafe impl Send for VkShader {}
+unsafe impl Sync for VkShader {}
+
 impl VkShader {
-    pub fn new(state: Rc<VulkanState>, path: &PathBuf, entry_point: CString) -> Self {
+    pub fn new(state: Arc<VulkanState>, path: &PathBuf, entry_point: CString) -> Self {
         let shader_bytecode = to_vec32(load_file(path).expect("[ERR] Could not load shader file."));
+        VkShader::from_bytecode(state, shader_bytecode, entry_point)
+    }
+
+    /// Create a shader from SPIR-V bytes that are not necessarily aligned on 4 bytes,
+    /// e.g. a shader embedded in the binary with `include_bytes!`.
+    pub fn from_spirv(state: Arc<VulkanState>, spirv: &[u8], entry_point: CString) -> Self {
+        let shader_bytecode =
+            ash::util::read_spv(&mut Cursor::new(spirv)).expect("[ERR] Invalid SPIR-V bytecode.");
+        VkShader::from_bytecode(state, shader_bytecode, entry_point)
+    }
 
+    pub fn from_bytecode(
+        state: Arc<VulkanState>,
+        shader_bytecode: Vec<u32>,
+        entry_point: CString,
+    ) -> Self {
         let shader_module_create_info = vk::ShaderModuleCreateInfo::builder()
             .code(&shader_bytecode)
             .build();
@@ -35,6 +57,7 @@ impl VkShader {
             bytecode: shader_bytecode,
             module: shader_module,
             layouts_bindings: Vec::new(),
+            push_constant_ranges: Vec::new(),
             layout: Vec::new(),
             pipeline: None,
             entry_point,
@@ -58,6 +81,14 @@ impl VkShader {
             .push(descriptor_layout_binding_info.build());
     }
 
+    pub fn add_push_constant_range(&mut self, offset: u32, size: u32, stage: vk::ShaderStageFlags) {
+        let push_constant_range = vk::PushConstantRange::builder()
+            .offset(offset)
+            .size(size)
+            .stage_flags(stage);
+        self.push_constant_ranges.push(push_constant_range.build());
+    }
+
     pub fn create_pipeline_layout(&mut self) {
         let descriptor_layout_create_info =
             vk::DescriptorSetLayoutCreateInfo::builder().bindings(&self.layouts_bindings);
@@ -70,8 +101,9 @@ impl VkShader {
         };
 
         self.layout.push(descriptor_layout);
-        let pipeline_layout_create_info =
-            vk::PipelineLayoutCreateInfo::builder().set_layouts(&self.layout);
+        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo::builder()
+            .set_layouts(&self.layout)
+            .push_constant_ranges(&self.push_constant_ranges);
         let pipeline_layout = unsafe {
             self.state
                 .device
@@ -81,6 +113,105 @@ impl VkShader {
 
         self.pipeline = Some(pipeline_layout);
     }
+
+    /// Size of a work group of the shader, see `local_size`.
+    pub fn local_size(&self) -> Option<[u32; 3]> {
+        local_size(&self.bytecode, &self.entry_point.to_string_lossy())
+    }
+}
+
+// SPIR-V opcodes, decorations and execution modes we look for.
+const OP_ENTRY_POINT: u32 = 15;
+const OP_EXECUTION_MODE: u32 = 16;
+const OP_CONSTANT: u32 = 43;
+const OP_CONSTANT_COMPOSITE: u32 = 44;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_SPEC_CONSTANT_COMPOSITE: u32 = 51;
+const OP_DECORATE: u32 = 71;
+const OP_EXECUTION_MODE_ID: u32 = 331;
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+const EXECUTION_MODE_LOCAL_SIZE_ID: u32 = 38;
+const DECORATION_BUILT_IN: u32 = 11;
+const BUILT_IN_WORKGROUP_SIZE: u32 = 25;
+
+/// Size of a work group of the compute entry point `entry_point`, read from its SPIR-V.
+///
+/// The `LocalSize` and `LocalSizeId` execution modes are understood, and a constant decorated
+/// with the `WorkgroupSize` built-in overrides them, as the specification says.
+/// Specialization constants are taken with their default value.
+pub fn local_size(bytecode: &[u32], entry_point: &str) -> Option<[u32; 3]> {
+    let mut entry_id = None;
+    let mut modes: Vec<(u32, u32, [u32; 3])> = Vec::new();
+    let mut constants: Vec<(u32, u32)> = Vec::new();
+    let mut composites: Vec<(u32, [u32; 3])> = Vec::new();
+    let mut workgroup_size = None;
+
+    // The header is 5 words long, then each instruction starts with its length and opcode.
+    let mut i = 5;
+    while i < bytecode.len() {
+        let count = (bytecode[i] >> 16) as usize;
+        let opcode = bytecode[i] & 0xffff;
+        if count == 0 || i + count > bytecode.len() {
+            return None;
+        }
+        let operands = &bytecode[i + 1..i + count];
+        match opcode {
+            OP_ENTRY_POINT if operands.len() > 2 => {
+                let name: Vec<u8> = operands[2..]
+                    .iter()
+                    .flat_map(|word| word.to_le_bytes())
+                    .take_while(|byte| *byte != 0)
+                    .collect();
+                if name == entry_point.as_bytes() {
+                    entry_id = Some(operands[1]);
+                }
+            }
+            OP_EXECUTION_MODE | OP_EXECUTION_MODE_ID if operands.len() == 5 => {
+                modes.push((
+                    operands[0],
+                    operands[1],
+                    [operands[2], operands[3], operands[4]],
+                ));
+            }
+            OP_CONSTANT | OP_SPEC_CONSTANT if operands.len() == 3 => {
+                constants.push((operands[1], operands[2]));
+            }
+            OP_CONSTANT_COMPOSITE | OP_SPEC_CONSTANT_COMPOSITE if operands.len() == 5 => {
+                composites.push((operands[1], [operands[2], operands[3], operands[4]]));
+            }
+            OP_DECORATE
+                if operands.len() == 3
+                    && operands[1] == DECORATION_BUILT_IN
+                    && operands[2] == BUILT_IN_WORKGROUP_SIZE =>
+            {
+                workgroup_size = Some(operands[0]);
+            }
+            _ => {}
+        }
+        i += count;
+    }
+
+    let constant = |id: u32| {
+        constants
+            .iter()
+            .find(|(constant, _)| *constant == id)
+            .map(|(_, value)| *value)
+    };
+    let from_ids = |ids: [u32; 3]| Some([constant(ids[0])?, constant(ids[1])?, constant(ids[2])?]);
+
+    if let Some(id) = workgroup_size {
+        let ids = composites.iter().find(|(composite, _)| *composite == id)?.1;
+        return from_ids(ids);
+    }
+    let entry_id = entry_id?;
+    modes
+        .iter()
+        .filter(|(entry, _, _)| *entry == entry_id)
+        .find_map(|(_, mode, size)| match *mode {
+            EXECUTION_MODE_LOCAL_SIZE => Some(*size),
+            EXECUTION_MODE_LOCAL_SIZE_ID => from_ids(*size),
+            _ => None,
+        })
 }
 
 impl Drop for VkShader {
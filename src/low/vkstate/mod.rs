@@ -1,15 +1,17 @@
-pub use ash::version::{DeviceV1_0, EntryV1_0, InstanceV1_0};
+pub use ash::version::{DeviceV1_0, EntryV1_0, InstanceV1_0, InstanceV1_1};
 use ash::vk::PhysicalDevice;
 use ash::{vk, Device, Entry, Instance};
 
-use crate::utils::{cstr2string, tick};
+use crate::low::vkfeatures::{self, Feature, FeatureChain};
+use crate::low::vkfence::FenceWaiter;
+use crate::low::vkinfo::DeviceInfo;
 
 use std::ffi::{CStr, CString};
 use std::io::{self, BufRead};
 use std::os::raw::{c_char, c_void};
 
 use ash::extensions::ext::DebugReport;
-use std::rc::Rc;
+use std::sync::{Mutex, OnceLock};
 
 use log::{info, warn};
 
@@ -19,12 +21,82 @@ pub struct VulkanState {
     pub physical_device: PhysicalDevice,
     pub device: Device,
     pub queue_family_index: u32,
+    /// The compute queues, all from `queue_family_index`.
+    /// Submissions must be externally synchronized, so hold the lock to submit.
+    pub compute_queues: Vec<Mutex<vk::Queue>>,
+    /// A queue from a family only doing transfers, if one was asked for and the device has one.
+    pub transfer_queue: Option<TransferQueue>,
+    /// Timeline semaphores are enabled, the device supports Vulkan 1.2 and its `timelineSemaphore` feature.
+    pub timeline_semaphore: bool,
+    /// The features enabled on the device, see `DeviceBuilder`.
+    pub features: Vec<Feature>,
+    /// The extensions enabled on the device.
+    pub extensions: Vec<String>,
+    /// Everything about the physical device, see `DeviceInfo`.
+    pub info: DeviceInfo,
     pub debug_report_loader: ash::extensions::ext::DebugReport,
     pub debug_callback: vk::DebugReportCallbackEXT,
+    fence_waiter: OnceLock<FenceWaiter>,
+}
+
+/// A queue from a dedicated transfer family.
+pub struct TransferQueue {
+    pub family_index: u32,
+    pub queue: Mutex<vk::Queue>,
+}
+
+/// The queues `DeviceBuilder` tries to create.
+#[derive(Debug, Clone, Copy)]
+pub struct QueueConfig {
+    /// Number of compute queues, limited to what the compute family offers.
+    pub compute_queues: u32,
+    /// Create a queue from a family which only does transfers, when the device has one,
+    /// so uploads and downloads can overlap with dispatches.
+    pub transfer_queue: bool,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        QueueConfig {
+            compute_queues: 1,
+            transfer_queue: false,
+        }
+    }
+}
+
+impl VulkanState {
+    /// The `index`-th compute queue.
+    pub fn compute_queue(&self, index: usize) -> &Mutex<vk::Queue> {
+        self.compute_queues.get(index).unwrap_or_else(|| {
+            panic!(
+                "[ERR] There is no compute queue {}, the device has {} of them.",
+                index,
+                self.compute_queues.len()
+            )
+        })
+    }
+
+    /// Is `feature` enabled on the device?
+    pub fn has_feature(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+
+    /// Is the device extension `name` enabled?
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|extension| extension == name)
+    }
+
+    /// The thread waiting on fences for async jobs, started the first time it is needed.
+    pub fn fence_waiter(&self) -> &FenceWaiter {
+        self.fence_waiter
+            .get_or_init(|| FenceWaiter::new(self.device.clone()))
+    }
 }
 
 impl Drop for VulkanState {
     fn drop(&mut self) {
+        // The waiter thread must be gone before the device is destroyed.
+        self.fence_waiter.take();
         unsafe {
             self.device.device_wait_idle().unwrap();
             self.device.destroy_device(None);
@@ -35,36 +107,6 @@ impl Drop for VulkanState {
     }
 }
 
-pub fn print_work_limits(vulkan: Rc<VulkanState>) {
-    let physical_device_props = unsafe {
-        vulkan
-            .instance
-            .get_physical_device_properties(vulkan.physical_device)
-    };
-
-    let physical_limits = physical_device_props.limits;
-    let work_group_count = physical_limits.max_compute_work_group_count;
-    let work_group_size = physical_limits.max_compute_work_group_size;
-    let work_group_invocation = physical_limits.max_compute_work_group_invocations;
-
-    info!(
-        "Device max work group count: [{}, {}, {}]",
-        work_group_count[0], work_group_count[1], work_group_count[2]
-    );
-    info!(
-        "Device max work group size: [{}, {}, {}]",
-        work_group_size[0], work_group_size[1], work_group_size[2]
-    );
-    info!(
-        "Device max work group invocation: {}",
-        work_group_invocation
-    );
-    info!(
-        "minStorageBufferOffset: {}",
-        physical_limits.min_storage_buffer_offset_alignment
-    );
-}
-
 unsafe extern "system" fn vulkan_debug_callback(
     _: vk::DebugReportFlagsEXT,
     _: vk::DebugReportObjectTypeEXT,
@@ -84,6 +126,148 @@ fn extension_names() -> Vec<*const i8> {
 }
 
 pub fn init_vulkan() -> VulkanState {
+    DeviceBuilder::new().build()
+}
+
+pub fn init_vulkan_with_queues(queue_config: QueueConfig) -> VulkanState {
+    DeviceBuilder::new().queues(queue_config).build()
+}
+
+/// Create the vulkan state with some queues, features and extensions.
+///
+/// A device missing a required feature or extension is an error, a missing optional one
+/// is only a warning: check `VulkanState::has_feature` before using it.
+/// Timeline semaphores are always optional.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceBuilder {
+    queues: QueueConfig,
+    required_features: Vec<Feature>,
+    optional_features: Vec<Feature>,
+    required_extensions: Vec<String>,
+    optional_extensions: Vec<String>,
+}
+
+impl DeviceBuilder {
+    pub fn new() -> DeviceBuilder {
+        DeviceBuilder::default()
+    }
+
+    pub fn queues(mut self, queues: QueueConfig) -> DeviceBuilder {
+        self.queues = queues;
+        self
+    }
+
+    /// Fail to create the device if it does not support `feature`.
+    /// The extension the feature comes from, if any, is required too.
+    pub fn require_feature(mut self, feature: Feature) -> DeviceBuilder {
+        self.required_features.push(feature);
+        self
+    }
+
+    /// Enable `feature` if the device supports it.
+    pub fn optional_feature(mut self, feature: Feature) -> DeviceBuilder {
+        self.optional_features.push(feature);
+        self
+    }
+
+    /// Fail to create the device if it does not support the extension `name`.
+    pub fn require_extension(mut self, name: &str) -> DeviceBuilder {
+        self.required_extensions.push(name.to_string());
+        self
+    }
+
+    /// Enable the extension `name` if the device supports it.
+    pub fn optional_extension(mut self, name: &str) -> DeviceBuilder {
+        self.optional_extensions.push(name.to_string());
+        self
+    }
+
+    pub fn build(self) -> VulkanState {
+        create_state(self)
+    }
+}
+
+/// The extensions and features of `builder` which `physical` supports,
+/// with every required one, or a panic.
+fn select_features(
+    builder: &DeviceBuilder,
+    instance: &Instance,
+    physical: PhysicalDevice,
+    vulkan_11: bool,
+    vulkan_12: bool,
+) -> (Vec<String>, Vec<Feature>) {
+    let supported_extensions = vkfeatures::supported_extensions(instance, physical);
+    let mut extensions: Vec<String> = Vec::new();
+    let mut select_extension = |name: &str, required: bool| {
+        if extensions.iter().any(|extension| extension == name) {
+            return;
+        }
+        if supported_extensions
+            .iter()
+            .any(|extension| extension == name)
+        {
+            extensions.push(name.to_string());
+        } else if required {
+            panic!(
+                "[ERR] The device does not support the required extension {}.",
+                name
+            );
+        } else {
+            warn!("The device does not support the extension {}.", name);
+        }
+    };
+    for name in &builder.required_extensions {
+        select_extension(name, true);
+    }
+    for feature in &builder.required_features {
+        if let Some(name) = feature.extension() {
+            select_extension(name, true);
+        }
+    }
+    for name in &builder.optional_extensions {
+        select_extension(name, false);
+    }
+    for feature in &builder.optional_features {
+        if let Some(name) = feature.extension() {
+            select_extension(name, false);
+        }
+    }
+
+    let atomic_float = extensions
+        .iter()
+        .any(|extension| extension == vkfeatures::SHADER_ATOMIC_FLOAT);
+    let mut supported = FeatureChain::query(instance, physical, vulkan_11, vulkan_12, atomic_float);
+    let mut features: Vec<Feature> = Vec::new();
+    let optional = builder
+        .optional_features
+        .iter()
+        .chain(std::iter::once(&Feature::TimelineSemaphore));
+    let wanted = builder
+        .required_features
+        .iter()
+        .map(|feature| (feature, true))
+        .chain(optional.map(|feature| (feature, false)));
+    for (feature, required) in wanted {
+        if features.contains(feature) {
+            continue;
+        }
+        let available = feature.is_available(vulkan_12, atomic_float) && supported.get(*feature);
+        if available {
+            features.push(*feature);
+        } else if required {
+            panic!(
+                "[ERR] The device does not support the required feature {}.",
+                feature
+            );
+        } else {
+            warn!("The device does not support the feature {}.", feature);
+        }
+    }
+    (extensions, features)
+}
+
+fn create_state(builder: DeviceBuilder) -> VulkanState {
+    let queue_config = builder.queues;
     let layer_names = [CString::new("VK_LAYER_KHRONOS_validation").unwrap()];
     let layers_names_raw: Vec<*const i8> = layer_names
         .iter()
@@ -123,47 +307,18 @@ pub fn init_vulkan() -> VulkanState {
     let phy_count = unsafe { instance.enumerate_physical_devices().unwrap() };
     if phy_count.len() == 1 {
         physical = phy_count[0];
-        let properties = unsafe { instance.get_physical_device_properties(physical) };
-
-        let phy_name = cstr2string(properties.device_name.to_vec());
-        info!("Only one physical device ({}) defaulting to it.", phy_name);
+        let info = DeviceInfo::query(&instance, physical);
+        info!("Only one physical device ({}) defaulting to it.", info.name);
     } else {
         // We don't use the logger here because we need user
         // feedback so we need whatever we print to be visible in all cases.
         println!("Physical device:");
-        let mut i = 0;
-        for dev in phy_count.clone() {
-            let properties = unsafe { instance.get_physical_device_properties(dev) };
-            let dev_name = cstr2string(properties.device_name.to_vec());
-            let (mut dev_graphics, mut dev_compute, mut dev_transfer, mut dev_sparse) =
-                (false, false, false, false);
-            unsafe {
-                instance
-                    .get_physical_device_queue_family_properties(dev)
-                    .iter()
-                    .for_each(|nfo| {
-                        if !dev_graphics && nfo.queue_flags.contains(vk::QueueFlags::GRAPHICS) {
-                            dev_graphics = true;
-                        }
-                        if !dev_compute && nfo.queue_flags.contains(vk::QueueFlags::COMPUTE) {
-                            dev_compute = true;
-                        }
-                        if !dev_transfer && nfo.queue_flags.contains(vk::QueueFlags::TRANSFER) {
-                            dev_transfer = true;
-                        }
-                        if !dev_sparse && nfo.queue_flags.contains(vk::QueueFlags::SPARSE_BINDING) {
-                            dev_sparse = true;
-                        }
-                    });
-            }
-
-            println!("- [{}] {}:", i, dev_name);
-            println!("\t* GRAPHICS: {}", tick(dev_graphics));
-            println!("\t* COMPUTE: {}", tick(dev_compute));
-            println!("\t* TRANSFER: {}", tick(dev_transfer));
-            println!("\t* SPARSE OPS: {}", tick(dev_sparse));
-
-            i += 1;
+        for (i, dev) in phy_count.iter().enumerate() {
+            let info = DeviceInfo::query(&instance, *dev);
+            println!(
+                "- [{}] {} ({}, {}, Vulkan {})",
+                i, info.name, info.vendor, info.device_type, info.api_version
+            );
         }
 
         println!("Use: ");
@@ -175,56 +330,136 @@ pub fn init_vulkan() -> VulkanState {
             .parse::<usize>()
             .expect("[ERR] Please write a number.");
         physical = phy_count[phy_id];
-        let properties = unsafe { instance.get_physical_device_properties(physical) };
-        let phy_name = cstr2string(properties.device_name.to_vec());
-        info!("Using device {}.", phy_name);
     }
+    let info = DeviceInfo::query(&instance, physical);
+    info!("Using device {}", info);
 
     // Get queue family:
-    let queue_index = unsafe {
-        instance
-            .get_physical_device_queue_family_properties(physical)
+    let families = unsafe { instance.get_physical_device_queue_family_properties(physical) };
+    let queue_index = families
+        .iter()
+        .enumerate()
+        .filter_map(|(index, nfo)| {
+            let support_compute = nfo.queue_flags.contains(vk::QueueFlags::COMPUTE);
+            let support_transfer = nfo.queue_flags.contains(vk::QueueFlags::TRANSFER);
+            if support_compute && support_transfer {
+                Some(index)
+            } else {
+                None
+            }
+        })
+        .next()
+        .expect("[ERR] Could not find a valid queue.") as u32;
+    let compute_queue_count = queue_config
+        .compute_queues
+        .clamp(1, families[queue_index as usize].queue_count);
+
+    // A dedicated transfer family does neither graphics nor compute.
+    let transfer_index = if queue_config.transfer_queue {
+        families
             .iter()
-            .enumerate()
-            .filter_map(|(index, ref nfo)| {
-                let support_compute = nfo.queue_flags.contains(vk::QueueFlags::COMPUTE);
-                let support_transfer = nfo.queue_flags.contains(vk::QueueFlags::TRANSFER);
-                if support_compute && support_transfer {
-                    Some(index)
-                } else {
-                    None
-                }
+            .position(|nfo| {
+                nfo.queue_flags.contains(vk::QueueFlags::TRANSFER)
+                    && !nfo
+                        .queue_flags
+                        .intersects(vk::QueueFlags::COMPUTE | vk::QueueFlags::GRAPHICS)
             })
-            .nth(0)
-            .expect("[ERR] Could not find a valid queue.") as u32
+            .map(|index| index as u32)
+    } else {
+        None
     };
+    match transfer_index {
+        Some(index) => info!("Using queue family {} for transfers.", index),
+        None if queue_config.transfer_queue => {
+            warn!("The device has no dedicated transfer queue family.")
+        }
+        None => {}
+    }
 
-    let features = vk::PhysicalDeviceFeatures {
-        ..Default::default()
-    };
+    let api_version = unsafe { instance.get_physical_device_properties(physical) }.api_version;
+    let vulkan_11 = vk::version_major(api_version) > 1 || vk::version_minor(api_version) >= 1;
+    let vulkan_12 = vk::version_major(api_version) > 1 || vk::version_minor(api_version) >= 2;
+    let (extensions, features) =
+        select_features(&builder, &instance, physical, vulkan_11, vulkan_12);
+    let atomic_float = extensions
+        .iter()
+        .any(|extension| extension == vkfeatures::SHADER_ATOMIC_FLOAT);
+    let mut enabled = FeatureChain::new(vulkan_12, atomic_float);
+    for feature in &features {
+        enabled.enable(*feature);
+    }
+    if !features.is_empty() {
+        info!(
+            "Enabled features: {}",
+            features
+                .iter()
+                .map(|feature| feature.name())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+    }
+    // Timeline semaphores are used to order jobs on the GPU, when the device has them.
+    let timeline_semaphore = features.contains(&Feature::TimelineSemaphore);
 
-    let queue_create_info = [vk::DeviceQueueCreateInfo::builder()
+    let compute_priorities = vec![1.0; compute_queue_count as usize];
+    let mut queue_create_info = vec![vk::DeviceQueueCreateInfo::builder()
         .queue_family_index(queue_index)
-        .queue_priorities(&[1.0])
+        .queue_priorities(&compute_priorities)
         .build()];
+    if let Some(index) = transfer_index {
+        queue_create_info.push(
+            vk::DeviceQueueCreateInfo::builder()
+                .queue_family_index(index)
+                .queue_priorities(&[1.0])
+                .build(),
+        );
+    }
 
-    let device_create_info_builder = vk::DeviceCreateInfo::builder()
+    let extension_names: Vec<CString> = extensions
+        .iter()
+        .map(|name| CString::new(name.as_str()).unwrap())
+        .collect();
+    let extension_names_raw: Vec<*const c_char> =
+        extension_names.iter().map(|name| name.as_ptr()).collect();
+    // The features are given through the chain, so `enabled_features` stays null,
+    // unless the device is too old to take the chain.
+    let mut device_create_info = vk::DeviceCreateInfo::builder()
         .queue_create_infos(&queue_create_info)
-        .enabled_features(&features)
-        .enabled_extension_names(&[]);
+        .enabled_extension_names(&extension_names_raw)
+        .build();
+    if vulkan_11 {
+        device_create_info.p_next = enabled.head();
+    } else {
+        device_create_info.p_enabled_features = enabled.core_features();
+    }
     let device: Device = unsafe {
         instance
-            .create_device(physical, &device_create_info_builder, None)
+            .create_device(physical, &device_create_info, None)
             .unwrap()
     };
 
+    let compute_queues = (0..compute_queue_count)
+        .map(|i| Mutex::new(unsafe { device.get_device_queue(queue_index, i) }))
+        .collect();
+    let transfer_queue = transfer_index.map(|index| TransferQueue {
+        family_index: index,
+        queue: Mutex::new(unsafe { device.get_device_queue(index, 0) }),
+    });
+
     VulkanState {
         entry,
         instance,
         physical_device: physical,
         device,
         queue_family_index: queue_index,
+        compute_queues,
+        transfer_queue,
+        timeline_semaphore,
+        features,
+        extensions,
+        info,
         debug_callback,
         debug_report_loader,
+        fence_waiter: OnceLock::new(),
     }
 }
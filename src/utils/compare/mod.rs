@@ -0,0 +1,329 @@
+use std::fmt;
+
+/// Number of mismatches a `CompareReport` keeps, the first ones in the buffers.
+pub const REPORTED_MISMATCHES: usize = 10;
+
+/// The types we compare: floats, and `u32`/`i32`, which are never NaN and whose ULP
+/// distance is the difference of the integers, so an exact tolerance compares them exactly.
+pub trait Element: Copy + PartialEq + fmt::Debug + fmt::Display {
+    fn to_f64(self) -> f64;
+
+    fn is_nan(self) -> bool;
+
+    /// The bits of the float mapped to integers in the same order as the floats,
+    /// so that two adjacent floats are one apart, and -0.0 is 0.0.
+    fn ordered_bits(self) -> i64;
+}
+
+impl Element for f32 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn is_nan(self) -> bool {
+        f32::is_nan(self)
+    }
+
+    fn ordered_bits(self) -> i64 {
+        let bits = self.to_bits() as i32;
+        (if bits < 0 { i32::MIN - bits } else { bits }) as i64
+    }
+}
+
+impl Element for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn is_nan(self) -> bool {
+        f64::is_nan(self)
+    }
+
+    fn ordered_bits(self) -> i64 {
+        let bits = self.to_bits() as i64;
+        if bits < 0 {
+            i64::MIN - bits
+        } else {
+            bits
+        }
+    }
+}
+
+impl Element for u32 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn is_nan(self) -> bool {
+        false
+    }
+
+    fn ordered_bits(self) -> i64 {
+        self as i64
+    }
+}
+
+impl Element for i32 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn is_nan(self) -> bool {
+        false
+    }
+
+    fn ordered_bits(self) -> i64 {
+        self as i64
+    }
+}
+
+/// |a - b|, infinite when only one of them is NaN or infinite, and 0 when they are equal.
+pub fn abs_error<T: Element>(a: T, b: T) -> f64 {
+    if a == b || (a.is_nan() && b.is_nan()) {
+        return 0.0;
+    }
+    let error = (a.to_f64() - b.to_f64()).abs();
+    if error.is_nan() {
+        f64::INFINITY
+    } else {
+        error
+    }
+}
+
+/// |a - b| / max(|a|, |b|), which is 0 when they are equal and at most 2 otherwise,
+/// or infinite like `abs_error`.
+pub fn rel_error<T: Element>(a: T, b: T) -> f64 {
+    let error = abs_error(a, b);
+    if error == 0.0 || error.is_infinite() {
+        return error;
+    }
+    error / a.to_f64().abs().max(b.to_f64().abs())
+}
+
+/// Number of floats between `a` and `b`: 0 when they are equal, 1 when they are adjacent.
+/// It is `u64::MAX` when only one of them is NaN.
+pub fn ulp_distance<T: Element>(a: T, b: T) -> u64 {
+    if a.is_nan() || b.is_nan() {
+        return if a.is_nan() && b.is_nan() {
+            0
+        } else {
+            u64::MAX
+        };
+    }
+    (a.ordered_bits() as i128 - b.ordered_bits() as i128).unsigned_abs() as u64
+}
+
+/// When two floats are considered equal. They are if they are equal, or within any of the
+/// tolerances: `Tolerance::abs(1e-6).or_ulps(4)` accepts an error of 1e-6 or of 4 ULPs.
+/// Infinities only match themselves, and NaN matches NaN unless `nan_equal(false)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    pub abs: f64,
+    pub rel: f64,
+    pub ulps: u64,
+    pub nan_equal: bool,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance::exact()
+    }
+}
+
+impl Tolerance {
+    /// Only equal values match.
+    pub fn exact() -> Tolerance {
+        Tolerance {
+            abs: 0.0,
+            rel: 0.0,
+            ulps: 0,
+            nan_equal: true,
+        }
+    }
+
+    pub fn abs(abs: f64) -> Tolerance {
+        Tolerance::exact().or_abs(abs)
+    }
+
+    pub fn rel(rel: f64) -> Tolerance {
+        Tolerance::exact().or_rel(rel)
+    }
+
+    pub fn ulps(ulps: u64) -> Tolerance {
+        Tolerance::exact().or_ulps(ulps)
+    }
+
+    pub fn or_abs(mut self, abs: f64) -> Tolerance {
+        self.abs = abs;
+        self
+    }
+
+    pub fn or_rel(mut self, rel: f64) -> Tolerance {
+        self.rel = rel;
+        self
+    }
+
+    pub fn or_ulps(mut self, ulps: u64) -> Tolerance {
+        self.ulps = ulps;
+        self
+    }
+
+    pub fn nan_equal(mut self, nan_equal: bool) -> Tolerance {
+        self.nan_equal = nan_equal;
+        self
+    }
+
+    pub fn matches<T: Element>(&self, expected: T, actual: T) -> bool {
+        if expected.is_nan() || actual.is_nan() {
+            return self.nan_equal && expected.is_nan() && actual.is_nan();
+        }
+        let error = abs_error(expected, actual);
+        error == 0.0
+            || (error.is_finite()
+                && (error <= self.abs
+                    || error <= self.rel * expected.to_f64().abs().max(actual.to_f64().abs())
+                    || ulp_distance(expected, actual) <= self.ulps))
+    }
+}
+
+/// Are `a` and `b` equal within `tolerance`?
+pub fn approx_eq<T: Element>(a: T, b: T, tolerance: Tolerance) -> bool {
+    tolerance.matches(a, b)
+}
+
+/// An element of two buffers which doesn't match.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mismatch<T> {
+    pub index: usize,
+    pub expected: T,
+    pub actual: T,
+    pub abs_error: f64,
+    pub rel_error: f64,
+    pub ulps: u64,
+}
+
+impl<T: Element> fmt::Display for Mismatch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] expected {}, got {} (abs {:e}, rel {:e}, {} ulps)",
+            self.index, self.expected, self.actual, self.abs_error, self.rel_error, self.ulps
+        )
+    }
+}
+
+/// What `compare_buffers` found. The errors are over every element, matching or not.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompareReport<T> {
+    pub expected_len: usize,
+    pub actual_len: usize,
+    pub tolerance: Tolerance,
+    /// Elements out of tolerance, among the ones both buffers have.
+    pub mismatches: usize,
+    pub max_abs_error: f64,
+    /// Mean of the finite absolute errors.
+    pub mean_abs_error: f64,
+    pub max_rel_error: f64,
+    pub max_ulps: u64,
+    /// The element with the largest absolute error, if any differs.
+    pub worst: Option<Mismatch<T>>,
+    /// The first `REPORTED_MISMATCHES` mismatches.
+    pub first_mismatches: Vec<Mismatch<T>>,
+}
+
+impl<T: Element> CompareReport<T> {
+    /// The buffers have the same length and every element matches.
+    pub fn passed(&self) -> bool {
+        self.mismatches == 0 && self.expected_len == self.actual_len
+    }
+
+    /// Panic with the report when the comparison failed.
+    pub fn assert(&self) {
+        if !self.passed() {
+            panic!("[ERR] The buffers differ.\n{}", self);
+        }
+    }
+}
+
+impl<T: Element> fmt::Display for CompareReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.expected_len != self.actual_len {
+            writeln!(
+                f,
+                "length: expected {}, got {}",
+                self.expected_len, self.actual_len
+            )?;
+        }
+        writeln!(
+            f,
+            "{} of {} elements out of tolerance {:?}",
+            self.mismatches,
+            self.expected_len.min(self.actual_len),
+            self.tolerance
+        )?;
+        writeln!(
+            f,
+            "max abs error: {:e}, mean abs error: {:e}, max rel error: {:e}, max ulps: {}",
+            self.max_abs_error, self.mean_abs_error, self.max_rel_error, self.max_ulps
+        )?;
+        if let Some(worst) = &self.worst {
+            writeln!(f, "worst: {}", worst)?;
+        }
+        for mismatch in &self.first_mismatches {
+            writeln!(f, "  {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare `actual`, e.g. the output of a job, to `expected`, e.g. computed on the CPU.
+pub fn compare_buffers<T: Element>(
+    expected: &[T],
+    actual: &[T],
+    tolerance: Tolerance,
+) -> CompareReport<T> {
+    let mut report = CompareReport {
+        expected_len: expected.len(),
+        actual_len: actual.len(),
+        tolerance,
+        mismatches: 0,
+        max_abs_error: 0.0,
+        mean_abs_error: 0.0,
+        max_rel_error: 0.0,
+        max_ulps: 0,
+        worst: None,
+        first_mismatches: Vec::new(),
+    };
+    let mut sum = 0.0;
+    let mut finite = 0usize;
+    for (index, (e, a)) in expected.iter().zip(actual).enumerate() {
+        let mismatch = Mismatch {
+            index,
+            expected: *e,
+            actual: *a,
+            abs_error: abs_error(*e, *a),
+            rel_error: rel_error(*e, *a),
+            ulps: ulp_distance(*e, *a),
+        };
+        if mismatch.abs_error.is_finite() {
+            sum += mismatch.abs_error;
+            finite += 1;
+        }
+        report.max_rel_error = report.max_rel_error.max(mismatch.rel_error);
+        report.max_ulps = report.max_ulps.max(mismatch.ulps);
+        if mismatch.abs_error > report.max_abs_error {
+            report.max_abs_error = mismatch.abs_error;
+            report.worst = Some(mismatch);
+        }
+        if !tolerance.matches(*e, *a) {
+            report.mismatches += 1;
+            if report.first_mismatches.len() < REPORTED_MISMATCHES {
+                report.first_mismatches.push(mismatch);
+            }
+        }
+    }
+    if finite > 0 {
+        report.mean_abs_error = sum / finite as f64;
+    }
+    report
+}
@@ -0,0 +1,285 @@
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// How the columns of a table are laid out in a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// One column per buffer, with the names in the first line.
+    Columns,
+    /// One line per buffer, starting with its name.
+    Rows,
+}
+
+/// Named columns of values, kept as text. Columns may have different lengths.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub names: Vec<String>,
+    pub columns: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new() -> Table {
+        Table::default()
+    }
+
+    /// Add a column. Floats are written with the fewest digits reading back the same value.
+    pub fn push<T: Display>(&mut self, name: &str, values: &[T]) -> &mut Table {
+        self.names.push(name.to_string());
+        self.columns
+            .push(values.iter().map(|v| v.to_string()).collect());
+        self
+    }
+
+    /// Add a column of floats with `digits` digits after the decimal point.
+    pub fn push_fixed<T: Display>(
+        &mut self,
+        name: &str,
+        values: &[T],
+        digits: usize,
+    ) -> &mut Table {
+        self.names.push(name.to_string());
+        self.columns
+            .push(values.iter().map(|v| format!("{:.*}", digits, v)).collect());
+        self
+    }
+
+    /// The values of the column `name`.
+    pub fn column<T: FromStr>(&self, name: &str) -> io::Result<Vec<T>> {
+        let index = self.names.iter().position(|n| n == name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no column {} in the table", name),
+            )
+        })?;
+        self.column_at(index)
+    }
+
+    /// The values of the column at `index`, for tables without header.
+    pub fn column_at<T: FromStr>(&self, index: usize) -> io::Result<Vec<T>> {
+        let column = self.columns.get(index).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("the table has no column {}", index),
+            )
+        })?;
+        column
+            .iter()
+            .enumerate()
+            .map(|(row, cell)| {
+                cell.trim().parse().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "\"{}\" (column {}, value {}) is not valid",
+                            cell, index, row
+                        ),
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+/// Options of the CSV and TSV files a `Table` is written to and read from.
+/// Cells holding the delimiter, quotes or line breaks are quoted as in RFC 4180.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Csv {
+    delimiter: char,
+    header: bool,
+    layout: Layout,
+}
+
+impl Default for Csv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Csv {
+    /// Comma separated columns, with a header.
+    pub fn new() -> Csv {
+        Csv {
+            delimiter: ',',
+            header: true,
+            layout: Layout::Columns,
+        }
+    }
+
+    /// Tab separated columns, with a header.
+    pub fn tsv() -> Csv {
+        Csv::new().delimiter('\t')
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> Csv {
+        assert!(
+            delimiter != '"' && delimiter != '\n' && delimiter != '\r',
+            "[ERR] {:?} can not separate values.",
+            delimiter
+        );
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Write the names of the columns, and read them back. Without it, columns are only
+    /// known by their index.
+    pub fn header(mut self, header: bool) -> Csv {
+        self.header = header;
+        self
+    }
+
+    pub fn layout(mut self, layout: Layout) -> Csv {
+        self.layout = layout;
+        self
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W, table: &Table) -> io::Result<()> {
+        match self.layout {
+            Layout::Columns => {
+                if self.header {
+                    self.write_line(writer, table.names.iter().map(String::as_str))?;
+                }
+                let rows = table.columns.iter().map(Vec::len).max().unwrap_or(0);
+                for row in 0..rows {
+                    let cells = table
+                        .columns
+                        .iter()
+                        .map(|column| column.get(row).map(String::as_str).unwrap_or(""));
+                    self.write_line(writer, cells)?;
+                }
+            }
+            Layout::Rows => {
+                for (name, column) in table.names.iter().zip(&table.columns) {
+                    let name = Some(name.as_str()).filter(|_| self.header);
+                    let cells = name.into_iter().chain(column.iter().map(String::as_str));
+                    self.write_line(writer, cells)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, table: &Table) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer, table)?;
+        writer.flush()
+    }
+
+    fn write_line<'c, W: Write, I: Iterator<Item = &'c str>>(
+        &self,
+        writer: &mut W,
+        cells: I,
+    ) -> io::Result<()> {
+        let mut line = String::new();
+        for (i, cell) in cells.enumerate() {
+            if i > 0 {
+                line.push(self.delimiter);
+            }
+            if cell.contains([self.delimiter, '"', '\n', '\r']) {
+                line.push('"');
+                line.push_str(&cell.replace('"', "\"\""));
+                line.push('"');
+            } else {
+                line.push_str(cell);
+            }
+        }
+        line.push('\n');
+        writer.write_all(line.as_bytes())
+    }
+
+    /// Read a table written with the same options. Empty cells at the end of columns are
+    /// dropped, so columns of different lengths read back the same.
+    pub fn read<R: Read>(&self, reader: &mut R) -> io::Result<Table> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let lines = self.parse(&text)?;
+        let mut table = Table::new();
+        match self.layout {
+            Layout::Columns => {
+                let mut lines = lines.into_iter();
+                let width = if self.header {
+                    table.names = lines.next().unwrap_or_default();
+                    table.names.len()
+                } else {
+                    0
+                };
+                let rows: Vec<Vec<String>> = lines.collect();
+                let width = rows.iter().map(Vec::len).max().unwrap_or(0).max(width);
+                table.columns = vec![Vec::new(); width];
+                for row in rows {
+                    for (column, cell) in table.columns.iter_mut().zip(row) {
+                        column.push(cell);
+                    }
+                }
+                for column in table.columns.iter_mut() {
+                    while column.last().is_some_and(String::is_empty) {
+                        column.pop();
+                    }
+                }
+            }
+            Layout::Rows => {
+                for mut line in lines {
+                    if self.header {
+                        table.names.push(line.remove(0));
+                    }
+                    while line.last().is_some_and(String::is_empty) {
+                        line.pop();
+                    }
+                    table.columns.push(line);
+                }
+            }
+        }
+        Ok(table)
+    }
+
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> io::Result<Table> {
+        self.read(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// Split `text` in lines of cells, handling quoted cells. A blank line is one empty cell,
+    /// and the line break ending the text does not start another line.
+    fn parse(&self, text: &str) -> io::Result<Vec<Vec<String>>> {
+        let mut lines: Vec<Vec<String>> = Vec::new();
+        let mut line: Vec<String> = Vec::new();
+        let mut cell = String::new();
+        let mut quoted = false;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if quoted {
+                match c {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        cell.push('"');
+                    }
+                    '"' => quoted = false,
+                    _ => cell.push(c),
+                }
+            } else if c == '"' && cell.is_empty() {
+                quoted = true;
+            } else if c == self.delimiter {
+                line.push(std::mem::take(&mut cell));
+            } else if c == '\n' || c == '\r' {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                line.push(std::mem::take(&mut cell));
+                lines.push(std::mem::take(&mut line));
+            } else {
+                cell.push(c);
+            }
+        }
+        if quoted {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "a quoted cell is not closed",
+            ));
+        }
+        if !line.is_empty() || !cell.is_empty() {
+            line.push(cell);
+            lines.push(line);
+        }
+        Ok(lines)
+    }
+}
@@ -0,0 +1,737 @@
+use ash::vk;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Channels of the pixels of an image, stored interleaved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channels {
+    R,
+    Rgb,
+    Rgba,
+}
+
+impl Channels {
+    pub fn count(self) -> usize {
+        match self {
+            Channels::R => 1,
+            Channels::Rgb => 3,
+            Channels::Rgba => 4,
+        }
+    }
+}
+
+/// The type of one channel of a pixel.
+pub trait Pixel: Copy + Default + PartialOrd {
+    /// Bits per channel in the PNG and PPM/PGM files we save.
+    const DEPTH: u8;
+
+    fn to_f32(self) -> f32;
+
+    fn from_f32(value: f32) -> Self;
+
+    /// The value in [0, 1] for the natural range of the type: [0, 255] for `u8`,
+    /// [0, 65535] for `u16`, and [0, 1] for `f32`, which is not clamped.
+    fn to_unit(self) -> f32;
+
+    fn from_unit(value: f32) -> Self;
+}
+
+impl Pixel for u8 {
+    const DEPTH: u8 = 8;
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value.round().clamp(0.0, 255.0) as u8
+    }
+
+    fn to_unit(self) -> f32 {
+        self as f32 / 255.0
+    }
+
+    fn from_unit(value: f32) -> Self {
+        u8::from_f32(value * 255.0)
+    }
+}
+
+impl Pixel for u16 {
+    const DEPTH: u8 = 16;
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value.round().clamp(0.0, 65535.0) as u16
+    }
+
+    fn to_unit(self) -> f32 {
+        self as f32 / 65535.0
+    }
+
+    fn from_unit(value: f32) -> Self {
+        u16::from_f32(value * 65535.0)
+    }
+}
+
+impl Pixel for f32 {
+    const DEPTH: u8 = 16;
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+
+    fn to_unit(self) -> f32 {
+        self
+    }
+
+    fn from_unit(value: f32) -> Self {
+        value
+    }
+}
+
+/// How the values of an image are brought to [0, 1] when it is saved or color mapped.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Normalize {
+    /// Values are written as they are in the file, e.g. 200.0 is 200 in a 8-bit file,
+    /// so they are expected in 0..=255 for 8-bit files and 0..=65535 for 16-bit ones,
+    /// not in [0, 1].
+    None,
+    /// The natural range of the type, see `Pixel::to_unit`, clamped to [0, 1].
+    #[default]
+    Clamp,
+    /// Map [min, max] to [0, 1], and clamp.
+    Range(f32, f32),
+    /// Map the smallest and largest value of the image to 0 and 1.
+    MinMax,
+    /// Map the smallest and largest value of each channel to 0 and 1.
+    PerChannel,
+}
+
+/// Maps a value in [0, 1] to a color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMap {
+    Grayscale,
+    Viridis,
+    Inferno,
+    /// Diverging blue to red, for signed data centered on the middle of the range.
+    Coolwarm,
+}
+
+impl ColorMap {
+    /// Evenly spaced colors of the map, linearly interpolated.
+    fn stops(self) -> &'static [[u8; 3]] {
+        match self {
+            ColorMap::Grayscale => &[[0, 0, 0], [255, 255, 255]],
+            ColorMap::Viridis => &[
+                [68, 1, 84],
+                [71, 45, 123],
+                [59, 82, 139],
+                [44, 114, 142],
+                [33, 145, 140],
+                [40, 174, 128],
+                [94, 201, 98],
+                [173, 220, 48],
+                [253, 231, 37],
+            ],
+            ColorMap::Inferno => &[
+                [0, 0, 4],
+                [31, 12, 72],
+                [85, 15, 109],
+                [136, 34, 106],
+                [186, 54, 85],
+                [227, 89, 51],
+                [249, 140, 10],
+                [249, 201, 50],
+                [252, 255, 164],
+            ],
+            ColorMap::Coolwarm => &[
+                [59, 76, 192],
+                [141, 176, 254],
+                [221, 221, 221],
+                [244, 154, 123],
+                [180, 4, 38],
+            ],
+        }
+    }
+
+    /// The RGB color of `value`, in [0, 1]. Values outside are clamped, NaN is black.
+    pub fn color(self, value: f32) -> [f32; 3] {
+        if value.is_nan() {
+            return [0.0; 3];
+        }
+        let stops = self.stops();
+        let position = value.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let low = (position.floor() as usize).min(stops.len() - 2);
+        let t = position - low as f32;
+        let mut color = [0.0; 3];
+        for (c, out) in color.iter_mut().enumerate() {
+            let a = stops[low][c] as f32 / 255.0;
+            let b = stops[low + 1][c] as f32 / 255.0;
+            *out = a + (b - a) * t;
+        }
+        color
+    }
+}
+
+/// An image as interleaved pixels, row after row from the top, the way the jobs see them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image<T> {
+    pub width: usize,
+    pub height: usize,
+    pub channels: Channels,
+    pub pixels: Vec<T>,
+}
+
+impl<T: Pixel> Image<T> {
+    pub fn new(width: usize, height: usize, channels: Channels, pixels: Vec<T>) -> Image<T> {
+        let count = checked_product(&[width, height, channels.count()]).unwrap_or_else(|| {
+            panic!(
+                "[ERR] A {}x{} image of {:?} is too large.",
+                width, height, channels
+            )
+        });
+        assert_eq!(
+            pixels.len(),
+            count,
+            "[ERR] A {}x{} image of {:?} needs {} values.",
+            width,
+            height,
+            channels,
+            count
+        );
+        Image {
+            width,
+            height,
+            channels,
+            pixels,
+        }
+    }
+
+    /// Load a PNG (with the `png` feature), PPM, PGM or PFM file, by its extension.
+    /// Integer files are scaled to the natural range of `T`, see `Pixel::from_unit`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Image<T>> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path)?);
+        let decoded = match extension(path).as_str() {
+            "png" => read_png(reader)?,
+            "ppm" | "pgm" | "pnm" => read_pnm(reader)?,
+            "pfm" => read_pfm(reader)?,
+            other => return Err(unsupported(other)),
+        };
+        Ok(decoded.into_image())
+    }
+
+    /// Load an image and convert it to `channels`.
+    pub fn load_as<P: AsRef<Path>>(path: P, channels: Channels) -> io::Result<Image<T>> {
+        Ok(Image::load(path)?.to_channels(channels))
+    }
+
+    /// Save the image as PNG (with the `png` feature), PPM, PGM or PFM, by the extension of
+    /// `path`. PGM files are grayscale and PPM files RGB, so the image is converted first.
+    /// PFM files keep the values of `Pixel::to_unit` as they are, without normalization.
+    pub fn save<P: AsRef<Path>>(&self, path: P, normalize: Normalize) -> io::Result<()> {
+        let path = path.as_ref();
+        let format = extension(path);
+        if !["png", "ppm", "pgm", "pnm", "pfm"].contains(&format.as_str()) {
+            return Err(unsupported(&format));
+        }
+        if format == "png" && !cfg!(feature = "png") {
+            return Err(png_disabled());
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        match format.as_str() {
+            "png" => write_png(&mut writer, self, normalize)?,
+            "pgm" => self
+                .to_channels(Channels::R)
+                .write_pnm(&mut writer, normalize)?,
+            "pfm" => write_pfm(&mut writer, self)?,
+            _ => self
+                .to_channels(Channels::Rgb)
+                .write_pnm(&mut writer, normalize)?,
+        }
+        writer.flush()
+    }
+
+    /// Write the image as a binary PGM (P5) when it has one channel, or PPM (P6) when it
+    /// has three, with a maximum of 255 or 65535 depending on `Pixel::DEPTH`.
+    pub fn write_pnm<W: Write>(&self, writer: &mut W, normalize: Normalize) -> io::Result<()> {
+        self.write_pnm_depth(writer, normalize, T::DEPTH)
+    }
+
+    /// Write the image as a binary PGM or PPM file of 8 or 16 bits per channel.
+    pub fn write_pnm_depth<W: Write>(
+        &self,
+        writer: &mut W,
+        normalize: Normalize,
+        depth: u8,
+    ) -> io::Result<()> {
+        let magic = match self.channels {
+            Channels::R => "P5",
+            Channels::Rgb => "P6",
+            Channels::Rgba => return Err(invalid("PPM files can not have an alpha channel")),
+        };
+        if depth != 8 && depth != 16 {
+            return Err(invalid(format!("PPM files can not have {} bits", depth)));
+        }
+        let max = max_value(depth);
+        write!(
+            writer,
+            "{}\n{} {}\n{}\n",
+            magic, self.width, self.height, max
+        )?;
+        writer.write_all(&quantize(&self.normalized(normalize, max), depth))
+    }
+
+    /// Read an ASCII (P2, P3) or binary (P5, P6) PGM or PPM file.
+    pub fn read_pnm<R: BufRead>(reader: R) -> io::Result<Image<T>> {
+        Ok(read_pnm(reader)?.into_image())
+    }
+
+    /// The values of the image in [0, 1], for a file whose maximum value is `max`.
+    pub fn normalized(&self, normalize: Normalize, max: f32) -> Vec<f32> {
+        let channels = self.channels.count();
+        let ranges: Vec<(f32, f32)> = match normalize {
+            Normalize::None => vec![(0.0, max)],
+            Normalize::Clamp => {
+                return self
+                    .pixels
+                    .iter()
+                    .map(|v| clamp_unit(v.to_unit()))
+                    .collect()
+            }
+            Normalize::Range(min, max) => vec![(min, max)],
+            Normalize::MinMax => vec![value_range(self.pixels.iter())],
+            Normalize::PerChannel => (0..channels)
+                .map(|c| value_range(self.pixels.iter().skip(c).step_by(channels)))
+                .collect(),
+        };
+        self.pixels
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let (min, max) = ranges[(i % channels) % ranges.len()];
+                // A constant image has no range, so it is mapped to 0.
+                if max > min {
+                    clamp_unit((v.to_f32() - min) / (max - min))
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    /// Convert the image to `channels`. Color becomes grayscale with the Rec. 709 luma,
+    /// grayscale is repeated in each color channel, and an added alpha is opaque.
+    pub fn to_channels(&self, channels: Channels) -> Image<T> {
+        if channels == self.channels {
+            return self.clone();
+        }
+        let from = self.channels.count();
+        let opaque = T::from_unit(1.0);
+        let mut pixels: Vec<T> = Vec::with_capacity(self.width * self.height * channels.count());
+        for pixel in self.pixels.chunks_exact(from) {
+            let rgb = if from == 1 {
+                [pixel[0]; 3]
+            } else {
+                [pixel[0], pixel[1], pixel[2]]
+            };
+            match channels {
+                Channels::R => pixels.push(T::from_unit(
+                    0.2126 * rgb[0].to_unit()
+                        + 0.7152 * rgb[1].to_unit()
+                        + 0.0722 * rgb[2].to_unit(),
+                )),
+                Channels::Rgb => pixels.extend_from_slice(&rgb),
+                Channels::Rgba => {
+                    pixels.extend_from_slice(&rgb);
+                    pixels.push(if from == 4 { pixel[3] } else { opaque });
+                }
+            }
+        }
+        Image::new(self.width, self.height, channels, pixels)
+    }
+
+    /// Color a one channel image with `map`, after bringing its values to [0, 1].
+    pub fn colormap(&self, map: ColorMap, normalize: Normalize) -> Image<f32> {
+        assert_eq!(
+            self.channels,
+            Channels::R,
+            "[ERR] Only one channel images can be color mapped."
+        );
+        let pixels = self
+            .normalized(normalize, max_value(T::DEPTH))
+            .iter()
+            .flat_map(|v| map.color(*v).to_vec())
+            .collect();
+        Image::new(self.width, self.height, Channels::Rgb, pixels)
+    }
+
+    /// The size of the image, for `JobBuilder::add_storage_image` and friends.
+    pub fn extent(&self) -> (u32, u32) {
+        (self.width as u32, self.height as u32)
+    }
+}
+
+macro_rules! image_formats {
+    ($($t:ty => $r:ident, $rgba:ident;)+) => {
+        $(impl Image<$t> {
+            /// The Vulkan format of the pixels. Three channel formats are seldom supported for
+            /// images, so RGB images have none: convert them to RGBA first.
+            pub fn format(&self) -> Option<vk::Format> {
+                match self.channels {
+                    Channels::R => Some(vk::Format::$r),
+                    Channels::Rgb => None,
+                    Channels::Rgba => Some(vk::Format::$rgba),
+                }
+            }
+        })+
+    };
+}
+
+image_formats! {
+    u8 => R8_UNORM, R8G8B8A8_UNORM;
+    u16 => R16_UNORM, R16G16B16A16_UNORM;
+    f32 => R32_SFLOAT, R32G32B32A32_SFLOAT;
+}
+
+/// Pixels read from a file, in [0, 1] for the integer formats.
+struct Decoded {
+    width: usize,
+    height: usize,
+    channels: Channels,
+    values: Vec<f32>,
+}
+
+impl Decoded {
+    fn into_image<T: Pixel>(self) -> Image<T> {
+        let pixels = self.values.into_iter().map(T::from_unit).collect();
+        Image::new(self.width, self.height, self.channels, pixels)
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn unsupported(format: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("\"{}\" images are not supported", format),
+    )
+}
+
+fn max_value(depth: u8) -> f32 {
+    if depth == 16 {
+        65535.0
+    } else {
+        255.0
+    }
+}
+
+fn clamp_unit(value: f32) -> f32 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    }
+}
+
+/// Smallest and largest value, ignoring NaN.
+fn value_range<'a, T: Pixel + 'a, I: Iterator<Item = &'a T>>(values: I) -> (f32, f32) {
+    values
+        .map(|v| v.to_f32())
+        .filter(|v| !v.is_nan())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        })
+}
+
+/// Values in [0, 1] as big endian samples of `depth` bits, as PNG and PPM store them.
+fn quantize(values: &[f32], depth: u8) -> Vec<u8> {
+    let max = max_value(depth);
+    if depth == 16 {
+        values
+            .iter()
+            .flat_map(|v| ((v * max).round() as u16).to_be_bytes().to_vec())
+            .collect()
+    } else {
+        values.iter().map(|v| (v * max).round() as u8).collect()
+    }
+}
+
+/// Big endian samples of a file whose maximum is `max`, in [0, 1].
+fn dequantize(bytes: &[u8], max: u32) -> Vec<f32> {
+    if max > 255 {
+        bytes
+            .chunks_exact(2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as f32 / max as f32)
+            .collect()
+    } else {
+        bytes.iter().map(|b| *b as f32 / max as f32).collect()
+    }
+}
+
+/// Read the next token of a PNM header, skipping whitespace and comments.
+fn pnm_token<R: BufRead>(reader: &mut R) -> io::Result<String> {
+    let mut token = String::new();
+    let mut byte = [0u8];
+    loop {
+        if reader.read(&mut byte)? == 0 {
+            break;
+        }
+        match byte[0] {
+            b'#' if token.is_empty() => {
+                let mut comment = Vec::new();
+                reader.read_until(b'\n', &mut comment)?;
+            }
+            b if b.is_ascii_whitespace() => {
+                if !token.is_empty() {
+                    break;
+                }
+            }
+            b => token.push(b as char),
+        }
+    }
+    if token.is_empty() {
+        return Err(invalid("truncated PNM header"));
+    }
+    Ok(token)
+}
+
+fn pnm_number<R: BufRead>(reader: &mut R) -> io::Result<u32> {
+    let token = pnm_token(reader)?;
+    token
+        .parse()
+        .map_err(|_| invalid(format!("\"{}\" is not a number", token)))
+}
+
+/// The product of `factors`, or `None` when it overflows.
+fn checked_product(factors: &[usize]) -> Option<usize> {
+    factors
+        .iter()
+        .try_fold(1usize, |product, &f| product.checked_mul(f))
+}
+
+/// The number of values, or bytes, of an image with the size of a file header.
+fn file_size(factors: &[usize]) -> io::Result<usize> {
+    checked_product(factors).ok_or_else(|| invalid("image too large"))
+}
+
+/// Read `len` bytes, growing the buffer as they come, so a header announcing more than
+/// the file holds fails on the missing bytes instead of allocating all of them first.
+fn read_bytes<R: Read>(reader: &mut R, len: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "the file is shorter than its header says",
+        ));
+    }
+    Ok(bytes)
+}
+
+/// The width and height of a PNM or PFM header, which can not be 0.
+fn pnm_size<R: BufRead>(reader: &mut R) -> io::Result<(usize, usize)> {
+    let width = pnm_number(reader)? as usize;
+    let height = pnm_number(reader)? as usize;
+    if width == 0 || height == 0 {
+        return Err(invalid(format!(
+            "{}x{} is not a valid image size",
+            width, height
+        )));
+    }
+    Ok((width, height))
+}
+
+/// Read an ASCII (P2, P3) or binary (P5, P6) PGM or PPM file.
+fn read_pnm<R: BufRead>(mut reader: R) -> io::Result<Decoded> {
+    let magic = pnm_token(&mut reader)?;
+    let (channels, binary) = match magic.as_str() {
+        "P2" => (Channels::R, false),
+        "P3" => (Channels::Rgb, false),
+        "P5" => (Channels::R, true),
+        "P6" => (Channels::Rgb, true),
+        _ => return Err(invalid(format!("{} is not a PGM or PPM file", magic))),
+    };
+    let (width, height) = pnm_size(&mut reader)?;
+    let max = pnm_number(&mut reader)?;
+    if max == 0 || max > 65535 {
+        return Err(invalid(format!("{} is not a valid maximum value", max)));
+    }
+
+    let count = file_size(&[width, height, channels.count()])?;
+    let values = if binary {
+        // The single whitespace after the maximum was read with it.
+        let len = file_size(&[count, if max > 255 { 2 } else { 1 }])?;
+        let bytes = read_bytes(&mut reader, len)?;
+        dequantize(&bytes, max)
+    } else {
+        (0..count)
+            .map(|_| pnm_number(&mut reader).map(|v| v as f32 / max as f32))
+            .collect::<io::Result<Vec<f32>>>()?
+    };
+    Ok(Decoded {
+        width,
+        height,
+        channels,
+        values,
+    })
+}
+
+/// Read a PFM file: raw floats, rows from the bottom, in the byte order of the scale sign.
+fn read_pfm<R: BufRead>(mut reader: R) -> io::Result<Decoded> {
+    let channels = match pnm_token(&mut reader)?.as_str() {
+        "Pf" => Channels::R,
+        "PF" => Channels::Rgb,
+        magic => return Err(invalid(format!("{} is not a PFM file", magic))),
+    };
+    let (width, height) = pnm_size(&mut reader)?;
+    let scale_token = pnm_token(&mut reader)?;
+    let scale: f32 = scale_token
+        .parse()
+        .map_err(|_| invalid(format!("\"{}\" is not a PFM scale", scale_token)))?;
+
+    let row = file_size(&[width, channels.count()])?;
+    let bytes = read_bytes(&mut reader, file_size(&[row, height, 4])?)?;
+    let floats: Vec<f32> = bytes
+        .chunks_exact(4)
+        .map(|b| {
+            let b = [b[0], b[1], b[2], b[3]];
+            if scale < 0.0 {
+                f32::from_le_bytes(b)
+            } else {
+                f32::from_be_bytes(b)
+            }
+        })
+        .collect();
+    let values = floats.chunks_exact(row).rev().flatten().copied().collect();
+    Ok(Decoded {
+        width,
+        height,
+        channels,
+        values,
+    })
+}
+
+/// Write the `Pixel::to_unit` values of the image as a little endian PFM file.
+/// PFM has no alpha, so RGBA images lose it.
+fn write_pfm<W: Write, T: Pixel>(writer: &mut W, image: &Image<T>) -> io::Result<()> {
+    let image = match image.channels {
+        Channels::Rgba => image.to_channels(Channels::Rgb),
+        _ => image.clone(),
+    };
+    let magic = if image.channels == Channels::R {
+        "Pf"
+    } else {
+        "PF"
+    };
+    write!(
+        writer,
+        "{}\n{} {}\n-1.0\n",
+        magic, image.width, image.height
+    )?;
+    let row = image.width * image.channels.count();
+    for line in image.pixels.chunks_exact(row.max(1)).rev() {
+        for v in line {
+            writer.write_all(&v.to_unit().to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "png")]
+fn read_png<R: Read>(reader: R) -> io::Result<Decoded> {
+    let mut decoder = png::Decoder::new(reader);
+    // Palettes, transparency and bit depths under 8 become plain 8-bit samples.
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(invalid)?;
+    let mut bytes = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut bytes).map_err(invalid)?;
+    bytes.truncate(info.buffer_size());
+
+    let max = if info.bit_depth == png::BitDepth::Sixteen {
+        65535
+    } else {
+        255
+    };
+    let mut values = dequantize(&bytes, max);
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => Channels::R,
+        png::ColorType::Rgb => Channels::Rgb,
+        png::ColorType::Rgba => Channels::Rgba,
+        png::ColorType::GrayscaleAlpha => {
+            values = values
+                .chunks_exact(2)
+                .flat_map(|p| vec![p[0], p[0], p[0], p[1]])
+                .collect();
+            Channels::Rgba
+        }
+        png::ColorType::Indexed => return Err(invalid("PNG palette was not expanded")),
+    };
+    Ok(Decoded {
+        width: info.width as usize,
+        height: info.height as usize,
+        channels,
+        values,
+    })
+}
+
+#[cfg(not(feature = "png"))]
+fn read_png<R: Read>(_reader: R) -> io::Result<Decoded> {
+    Err(png_disabled())
+}
+
+#[cfg(feature = "png")]
+fn write_png<W: Write, T: Pixel>(
+    writer: &mut W,
+    image: &Image<T>,
+    normalize: Normalize,
+) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(writer, image.width as u32, image.height as u32);
+    encoder.set_color(match image.channels {
+        Channels::R => png::ColorType::Grayscale,
+        Channels::Rgb => png::ColorType::Rgb,
+        Channels::Rgba => png::ColorType::Rgba,
+    });
+    encoder.set_depth(if T::DEPTH == 16 {
+        png::BitDepth::Sixteen
+    } else {
+        png::BitDepth::Eight
+    });
+    let values = image.normalized(normalize, max_value(T::DEPTH));
+    encoder
+        .write_header()
+        .and_then(|mut png| png.write_image_data(&quantize(&values, T::DEPTH)))
+        .map_err(io::Error::other)
+}
+
+#[cfg(not(feature = "png"))]
+fn write_png<W: Write, T: Pixel>(
+    _writer: &mut W,
+    _image: &Image<T>,
+    _normalize: Normalize,
+) -> io::Result<()> {
+    Err(png_disabled())
+}
+
+fn png_disabled() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "PNG support needs the \"png\" feature of wyzoid",
+    )
+}
@@ -1,12 +1,20 @@
-use ash::extensions::khr::Display;
 use log::error;
 use rand::Rng;
 use std::ffi::CString;
-use std::fmt::Display as FmtDisplay;
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
 use std::ops::{Add, Div, Mul, Sub};
 use std::path::PathBuf;
 use std::time::Duration;
 
+pub mod compare;
+pub mod csv;
+pub mod image;
+pub mod npy;
+pub mod random;
+
+use image::{Channels, Image, Normalize};
+
 pub fn to_vec32(vecin: Vec<u8>) -> Vec<u32> {
     unsafe { vecin.align_to::<u32>().1.to_vec() }
 }
@@ -43,16 +51,30 @@ pub fn get_fract_s(date: Duration) -> String {
     format!("{}", tot)
 }
 
-// Bad f32 comparison with a epsilon
+/// Are `a` and `b` within `epsilon` of each other?
+#[deprecated(
+    since = "0.1.3",
+    note = "use `utils::compare::approx_eq` with a `Tolerance`"
+)]
 pub fn f32_cmp(a: f32, b: f32, epsilon: f32) -> bool {
-    (a + epsilon) > b && b > (a - epsilon)
+    compare::approx_eq(a, b, compare::Tolerance::abs(epsilon as f64))
 }
 
+/// Uniform values in [low, high), from an unknown seed. Use `random::Generator` for data
+/// which can be generated again.
 pub fn rand_vec<T>(len: usize, low: T, high: T) -> Vec<T>
 where
     T: rand::distributions::uniform::SampleUniform + Copy + PartialOrd,
 {
-    let mut rng = rand::thread_rng();
+    rand_vec_with(&mut rand::thread_rng(), len, low, high)
+}
+
+/// Uniform values in [low, high) from `rng`, e.g. a `random::Generator`.
+pub fn rand_vec_with<R, T>(rng: &mut R, len: usize, low: T, high: T) -> Vec<T>
+where
+    R: Rng + ?Sized,
+    T: rand::distributions::uniform::SampleUniform + Copy + PartialOrd,
+{
     let mut output: Vec<T> = Vec::with_capacity(len);
 
     for _ in 0..len {
@@ -71,12 +93,12 @@ pub fn min_max<T: PartialOrd + Copy>(data: &[T]) -> Option<(T, T)> {
 
     let mut min_local: T = data[0];
     let mut max_local: T = data[0];
-    for i in 1..data.len() {
-        if min_local > data[i] {
-            min_local = data[i];
+    for &value in &data[1..] {
+        if min_local > value {
+            min_local = value;
         }
-        if max_local < data[i] {
-            max_local = data[i];
+        if max_local < value {
+            max_local = value;
         }
     }
 
@@ -90,37 +112,49 @@ where
     map_min + (x - origin_min) * (map_max - map_min) / (origin_max - origin_min)
 }
 
-pub fn to_ppm(data: &Vec<f32>, width: usize, height: usize) -> Option<String> {
-    if width * height * 3 != data.len() {
-        return None;
-    }
-
-    let (min, max) = min_max(data).unwrap();
-    let mut ppm = String::new();
-    ppm.push_str("P3\n");
-    ppm.push_str(&format!("{} {}\n", width, height));
-    ppm.push_str("255\n");
+/// Write `width` x `height` pixels of `data` as a binary 8-bit PGM (P5) file when
+/// `channels` is `R`, or PPM (P6) file when it is `Rgb`, to `writer`.
+/// With `Normalize::None` the values are expected in 0..=255, not in [0, 1].
+/// One line of `values` starting with `label`, separated by `;`.
+#[deprecated(since = "0.1.3", note = "use `utils::csv::Csv` with `Layout::Rows`")]
+#[allow(clippy::ptr_arg)]
+pub fn to_csv<T: Display>(label: &str, values: &Vec<T>) -> String {
+    let mut table = csv::Table::new();
+    table.push(label, values);
+    let mut line = Vec::new();
+    csv::Csv::new()
+        .delimiter(';')
+        .layout(csv::Layout::Rows)
+        .write(&mut line, &table)
+        .expect("[ERR] Could not write the CSV line.");
+    String::from_utf8(line).expect("[ERR] The CSV line is not UTF-8.")
+}
 
-    for i in 0..data.len() {
-        ppm.push_str(&format!("{} ", remap(data[i], min, max, 0.0, 255.0) as u8));
-        if i % width == 0 {
-            ppm.push_str("\n");
-        }
+pub fn to_ppm<W: Write>(
+    writer: &mut W,
+    data: &[f32],
+    width: usize,
+    height: usize,
+    channels: Channels,
+    normalize: Normalize,
+) -> io::Result<()> {
+    if width * height * channels.count() != data.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} values are not {}x{} pixels of {:?}",
+                data.len(),
+                width,
+                height,
+                channels
+            ),
+        ));
     }
-
-    Some(ppm)
+    let image = Image::new(width, height, channels, data.to_vec());
+    image.write_pnm_depth(writer, normalize, 8)
 }
 
-pub fn to_csv<T>(label: &str, values: &Vec<T>) -> String
-where
-    T: FmtDisplay,
-{
-    let mut csv = String::new();
-    csv.push_str(label);
-    csv.push(';');
-    for val in values {
-        csv.push_str(&format!("{};", val));
-    }
-    csv.push('\n');
-    return csv;
+/// Read a PGM or PPM file written by `to_ppm`, or any other one, with its values in [0, 1].
+pub fn from_ppm<R: BufRead>(reader: R) -> io::Result<Image<f32>> {
+    Image::read_pnm(reader)
 }
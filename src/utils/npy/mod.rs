@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// A type NumPy arrays can hold, with its `dtype` description in little endian.
+pub trait NpyElement: Copy {
+    /// The `descr` of the type in the header of a `.npy` file, e.g. "<f4".
+    const DESCR: &'static str;
+
+    fn from_bytes(bytes: &[u8], little_endian: bool) -> Self;
+
+    fn write_le<W: Write>(self, writer: &mut W) -> io::Result<()>;
+}
+
+macro_rules! npy_elements {
+    ($($t:ty => $descr:literal;)+) => {
+        $(impl NpyElement for $t {
+            const DESCR: &'static str = $descr;
+
+            fn from_bytes(bytes: &[u8], little_endian: bool) -> Self {
+                let mut raw = [0u8; std::mem::size_of::<$t>()];
+                raw.copy_from_slice(bytes);
+                if little_endian {
+                    <$t>::from_le_bytes(raw)
+                } else {
+                    <$t>::from_be_bytes(raw)
+                }
+            }
+
+            fn write_le<W: Write>(self, writer: &mut W) -> io::Result<()> {
+                writer.write_all(&self.to_le_bytes())
+            }
+        })+
+    };
+}
+
+npy_elements! {
+    f32 => "<f4";
+    f64 => "<f8";
+    i32 => "<i4";
+    u32 => "<u4";
+    u8 => "|u1";
+}
+
+/// An n-dimensional array, in C order: the last index varies the fastest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Array<T> {
+    pub shape: Vec<usize>,
+    pub data: Vec<T>,
+}
+
+impl<T: NpyElement> Array<T> {
+    pub fn new(shape: Vec<usize>, data: Vec<T>) -> Array<T> {
+        assert_eq!(
+            shape.iter().product::<usize>(),
+            data.len(),
+            "[ERR] An array of shape {:?} can not hold {} values.",
+            shape,
+            data.len()
+        );
+        Array { shape, data }
+    }
+
+    /// A one dimension array.
+    pub fn from_vec(data: Vec<T>) -> Array<T> {
+        Array::new(vec![data.len()], data)
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Write `data`, of `shape`, as a version 1.0 `.npy` file.
+pub fn write_npy<W: Write, T: NpyElement>(
+    writer: &mut W,
+    data: &[T],
+    shape: &[usize],
+) -> io::Result<()> {
+    if shape.iter().product::<usize>() != data.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} values are not an array of shape {:?}",
+                data.len(),
+                shape
+            ),
+        ));
+    }
+    let dims: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+    // A one dimension tuple needs its trailing comma in Python.
+    let shape = match dims.len() {
+        1 => format!("({},)", dims[0]),
+        _ => format!("({})", dims.join(", ")),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        T::DESCR,
+        shape
+    );
+    // The data starts on a multiple of 64 bytes, and the header ends with a newline.
+    let unpadded = MAGIC.len() + 2 + 2 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+    if header.len() > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the shape does not fit in a .npy header",
+        ));
+    }
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for value in data {
+        value.write_le(writer)?;
+    }
+    Ok(())
+}
+
+/// Read a `.npy` file holding values of type `T`. Fortran ordered arrays are reordered
+/// in C order, and big endian files are swapped.
+pub fn read_npy<R: Read, T: NpyElement>(reader: &mut R) -> io::Result<Array<T>> {
+    let mut preamble = [0u8; 8];
+    reader.read_exact(&mut preamble)?;
+    if &preamble[..6] != MAGIC {
+        return Err(invalid("not a .npy file"));
+    }
+    let header_len = match preamble[6] {
+        1 => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        version => return Err(invalid(format!(".npy version {} is unknown", version))),
+    };
+    let header = read_bytes(reader, header_len)?;
+    let header = String::from_utf8_lossy(&header);
+
+    let descr = header_value(&header, "descr")?
+        .trim_matches(['\'', '"'])
+        .to_string();
+    // '|' has no byte order, '=' is the native one.
+    let little_endian = match descr.chars().next() {
+        Some('>') => false,
+        Some('<') | Some('|') => true,
+        _ => cfg!(target_endian = "little"),
+    };
+    if descr.get(1..) != T::DESCR.get(1..) {
+        return Err(invalid(format!(
+            "the array holds {}, not {}",
+            descr,
+            T::DESCR
+        )));
+    }
+    let fortran_order = header_value(&header, "fortran_order")? == "True";
+    let shape: Vec<usize> = header_value(&header, "shape")?
+        .trim_matches(['(', ')'])
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| {
+            d.parse()
+                .map_err(|_| invalid(format!("bad dimension {}", d)))
+        })
+        .collect::<io::Result<Vec<usize>>>()?;
+
+    let size = std::mem::size_of::<T>();
+    let len = shape
+        .iter()
+        .try_fold(size, |len, &d| len.checked_mul(d))
+        .ok_or_else(|| invalid("shape too large"))?;
+    let bytes = read_bytes(reader, len)?;
+    let mut data: Vec<T> = bytes
+        .chunks_exact(size)
+        .map(|b| T::from_bytes(b, little_endian))
+        .collect();
+    if fortran_order && shape.len() > 1 {
+        data = fortran_to_c(&data, &shape);
+    }
+    Ok(Array { shape, data })
+}
+
+/// Read `len` bytes, growing the buffer as they come, so a header announcing more than
+/// the file holds fails on the missing bytes instead of allocating all of them first.
+fn read_bytes<R: Read>(reader: &mut R, len: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "the file is shorter than its header says",
+        ));
+    }
+    Ok(bytes)
+}
+
+/// The text of `key` in the header dictionary, e.g. "(3, 4)" for 'shape'.
+fn header_value<'h>(header: &'h str, key: &str) -> io::Result<&'h str> {
+    let quoted = [format!("'{}'", key), format!("\"{}\"", key)];
+    let start = quoted
+        .iter()
+        .find_map(|k| header.find(k.as_str()).map(|i| i + k.len()))
+        .ok_or_else(|| invalid(format!("the header has no {}", key)))?;
+    let value = header[start..]
+        .trim_start()
+        .trim_start_matches(':')
+        .trim_start();
+    let end = if value.starts_with('(') {
+        value.find(')').map(|i| i + 1)
+    } else {
+        value.find([',', '}'])
+    }
+    .ok_or_else(|| invalid(format!("the value of {} is not closed", key)))?;
+    Ok(value[..end].trim())
+}
+
+/// Reorder an array from Fortran order, where the first index varies the fastest, to C order.
+fn fortran_to_c<T: Copy>(data: &[T], shape: &[usize]) -> Vec<T> {
+    let mut output = Vec::with_capacity(data.len());
+    let mut index = vec![0usize; shape.len()];
+    for _ in 0..data.len() {
+        let mut offset = 0;
+        let mut stride = 1;
+        for (i, dim) in index.iter().zip(shape) {
+            offset += i * stride;
+            stride *= dim;
+        }
+        output.push(data[offset]);
+        // Next index in C order.
+        for d in (0..shape.len()).rev() {
+            index[d] += 1;
+            if index[d] < shape[d] {
+                break;
+            }
+            index[d] = 0;
+        }
+    }
+    output
+}
+
+pub fn save_npy<P: AsRef<Path>, T: NpyElement>(
+    path: P,
+    data: &[T],
+    shape: &[usize],
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_npy(&mut writer, data, shape)?;
+    writer.flush()
+}
+
+pub fn load_npy<P: AsRef<Path>, T: NpyElement>(path: P) -> io::Result<Array<T>> {
+    read_npy(&mut BufReader::new(File::open(path)?))
+}
+
+/// CRC-32 of the zip format.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// A file of the central directory of a zip archive.
+struct ZipEntry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Writes a `.npz` archive: `.npy` files in an uncompressed zip archive, like `numpy.savez`.
+pub struct NpzWriter<W: Write> {
+    writer: W,
+    offset: u64,
+    entries: Vec<ZipEntry>,
+}
+
+impl NpzWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<NpzWriter<BufWriter<File>>> {
+        Ok(NpzWriter::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> NpzWriter<W> {
+    pub fn new(writer: W) -> NpzWriter<W> {
+        NpzWriter {
+            writer,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add the array `name`, which NumPy sees as `archive[name]`.
+    pub fn add<T: NpyElement>(
+        &mut self,
+        name: &str,
+        data: &[T],
+        shape: &[usize],
+    ) -> io::Result<()> {
+        let mut npy = Vec::new();
+        write_npy(&mut npy, data, shape)?;
+        let name = format!("{}.npy", name);
+        let too_large = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "npz archives are limited to 4 GiB",
+            )
+        };
+        let size = u32::try_from(npy.len()).map_err(|_| too_large())?;
+        let offset = u32::try_from(self.offset).map_err(|_| too_large())?;
+        let crc = crc32(&npy);
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        header.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        header.extend_from_slice(&0u16.to_le_bytes()); // flags
+        header.extend_from_slice(&0u16.to_le_bytes()); // stored
+        header.extend_from_slice(&0u16.to_le_bytes()); // time
+        header.extend_from_slice(&0x21u16.to_le_bytes()); // date: 1980-01-01
+        header.extend_from_slice(&crc.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // extra field
+        header.extend_from_slice(name.as_bytes());
+        self.writer.write_all(&header)?;
+        self.writer.write_all(&npy)?;
+
+        self.offset += (header.len() + npy.len()) as u64;
+        self.entries.push(ZipEntry {
+            name,
+            crc,
+            size,
+            offset,
+        });
+        Ok(())
+    }
+
+    /// Write the central directory of the archive, and give back the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let start = self.offset;
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            directory.extend_from_slice(&20u16.to_le_bytes()); // version made by
+            directory.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            directory.extend_from_slice(&0u16.to_le_bytes()); // flags
+            directory.extend_from_slice(&0u16.to_le_bytes()); // stored
+            directory.extend_from_slice(&0u16.to_le_bytes()); // time
+            directory.extend_from_slice(&0x21u16.to_le_bytes()); // date
+            directory.extend_from_slice(&entry.crc.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // Extra field, comment, disk, internal and external attributes.
+            directory.extend_from_slice(&[0u8; 12]);
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let start = u32::try_from(start).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "npz archives are limited to 4 GiB",
+            )
+        })?;
+        let count = self.entries.len() as u16;
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        end.extend_from_slice(&[0u8; 4]); // disks
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        end.extend_from_slice(&start.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes()); // comment
+        self.writer.write_all(&directory)?;
+        self.writer.write_all(&end)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads the arrays of a `.npz` archive, written by `numpy.savez` or `numpy.savez_compressed`.
+pub struct NpzReader {
+    bytes: Vec<u8>,
+    /// Name of each array, and (compression method, CRC, compressed size, local header offset).
+    entries: HashMap<String, (u16, u32, usize, usize)>,
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> io::Result<u16> {
+    bytes
+        .get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid("truncated zip archive"))
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> io::Result<u32> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("truncated zip archive"))
+}
+
+impl NpzReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<NpzReader> {
+        NpzReader::from_bytes(std::fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> io::Result<NpzReader> {
+        // The end of central directory record is last, before a comment of up to 64 KiB.
+        let end = (0..bytes.len().saturating_sub(21))
+            .rev()
+            .take(22 + u16::MAX as usize)
+            .find(|i| bytes[*i..].starts_with(&0x0605_4b50u32.to_le_bytes()))
+            .ok_or_else(|| invalid("not a zip archive"))?;
+        let count = le_u16(&bytes, end + 10)? as usize;
+        let mut at = le_u32(&bytes, end + 16)? as usize;
+        if at == u32::MAX as usize {
+            return Err(invalid("zip64 archives are not supported"));
+        }
+
+        let mut entries = HashMap::with_capacity(count);
+        for _ in 0..count {
+            if le_u32(&bytes, at)? != 0x0201_4b50 {
+                return Err(invalid("corrupted zip central directory"));
+            }
+            let method = le_u16(&bytes, at + 10)?;
+            let crc = le_u32(&bytes, at + 16)?;
+            let size = le_u32(&bytes, at + 20)? as usize;
+            let name_len = le_u16(&bytes, at + 28)? as usize;
+            let extra_len = le_u16(&bytes, at + 30)? as usize;
+            let comment_len = le_u16(&bytes, at + 32)? as usize;
+            let offset = le_u32(&bytes, at + 42)? as usize;
+            if size == u32::MAX as usize || offset == u32::MAX as usize {
+                return Err(invalid("zip64 archives are not supported"));
+            }
+            let name = bytes
+                .get(at + 46..at + 46 + name_len)
+                .ok_or_else(|| invalid("truncated zip archive"))?;
+            let name = String::from_utf8_lossy(name);
+            let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+            entries.insert(name, (method, crc, size, offset));
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(NpzReader { bytes, entries })
+    }
+
+    /// Names of the arrays in the archive.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.entries.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// The array `name`, which must hold values of type `T`.
+    pub fn get<T: NpyElement>(&self, name: &str) -> io::Result<Array<T>> {
+        let (method, crc, size, offset) = *self.entries.get(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no array {} in the archive", name),
+            )
+        })?;
+        if le_u32(&self.bytes, offset)? != 0x0403_4b50 {
+            return Err(invalid("corrupted zip local header"));
+        }
+        let start = offset
+            + 30
+            + le_u16(&self.bytes, offset + 26)? as usize
+            + le_u16(&self.bytes, offset + 28)? as usize;
+        let data = self
+            .bytes
+            .get(start..start + size)
+            .ok_or_else(|| invalid("truncated zip archive"))?;
+        let npy = match method {
+            0 => data.to_vec(),
+            8 => miniz_oxide::inflate::decompress_to_vec(data)
+                .map_err(|e| invalid(format!("could not inflate {}: {:?}", name, e)))?,
+            _ => {
+                return Err(invalid(format!(
+                    "zip compression {} is not supported",
+                    method
+                )))
+            }
+        };
+        if crc32(&npy) != crc {
+            return Err(invalid(format!("the CRC of {} does not match", name)));
+        }
+        read_npy(&mut &npy[..])
+    }
+}
@@ -0,0 +1,216 @@
+use crate::utils::compare::Element;
+use log::info;
+use rand::distributions::uniform::SampleUniform;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::f64::consts::PI;
+
+/// The environment variable `Generator::from_env` reads its seed from.
+pub const SEED_VAR: &str = "WYZOID_SEED";
+
+/// The float types the generators produce, with the values shaders tend to get wrong.
+pub trait RandomFloat: Element + SampleUniform + PartialOrd {
+    fn from_f64(value: f64) -> Self;
+
+    /// ±0, ±Inf, NaN, the smallest and largest denormals, the smallest normal and the
+    /// largest finite values.
+    fn specials() -> Vec<Self>;
+}
+
+impl RandomFloat for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn specials() -> Vec<Self> {
+        vec![
+            0.0,
+            -0.0,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NAN,
+            f32::from_bits(1),
+            -f32::from_bits(1),
+            f32::from_bits(0x007f_ffff),
+            f32::MIN_POSITIVE,
+            f32::MAX,
+            f32::MIN,
+        ]
+    }
+}
+
+impl RandomFloat for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn specials() -> Vec<Self> {
+        vec![
+            0.0,
+            -0.0,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NAN,
+            f64::from_bits(1),
+            -f64::from_bits(1),
+            f64::from_bits(0x000f_ffff_ffff_ffff),
+            f64::MIN_POSITIVE,
+            f64::MAX,
+            f64::MIN,
+        ]
+    }
+}
+
+/// A seeded random generator: the same seed gives the same data, so a failing input can be
+/// generated again. It implements `RngCore`, and can be used with anything from `rand`.
+pub struct Generator {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Generator {
+        Generator {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Seed with the `WYZOID_SEED` environment variable, or a random seed when it is not set.
+    /// The seed is logged, so that a run can be done again with the same data.
+    pub fn from_env() -> Generator {
+        let seed = match std::env::var(SEED_VAR) {
+            Ok(seed) => seed
+                .trim()
+                .parse()
+                .expect("[ERR] WYZOID_SEED is not a valid u64."),
+            Err(_) => rand::thread_rng().gen(),
+        };
+        info!(
+            "Random data seeded with {}, set {}={} to generate it again.",
+            seed, SEED_VAR, seed
+        );
+        Generator::new(seed)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Uniform values in [low, high).
+    pub fn uniform<T>(&mut self, len: usize, low: T, high: T) -> Vec<T>
+    where
+        T: SampleUniform + Copy + PartialOrd,
+    {
+        super::rand_vec_with(self, len, low, high)
+    }
+
+    /// Normally distributed values, from the Box-Muller transform.
+    pub fn normal<T: RandomFloat>(&mut self, len: usize, mean: f64, std_dev: f64) -> Vec<T> {
+        let mut output: Vec<T> = Vec::with_capacity(len);
+        while output.len() < len {
+            // 1 - [0, 1) is never 0, which has no logarithm.
+            let radius = (-2.0 * (1.0 - self.gen::<f64>()).ln()).sqrt();
+            let angle = 2.0 * PI * self.gen::<f64>();
+            output.push(T::from_f64(mean + std_dev * radius * angle.cos()));
+            if output.len() < len {
+                output.push(T::from_f64(mean + std_dev * radius * angle.sin()));
+            }
+        }
+        output
+    }
+
+    /// Exponentially distributed values, of mean 1 / `lambda`.
+    pub fn exponential<T: RandomFloat>(&mut self, len: usize, lambda: f64) -> Vec<T> {
+        (0..len)
+            .map(|_| T::from_f64(-(1.0 - self.gen::<f64>()).ln() / lambda))
+            .collect()
+    }
+
+    /// Uniform values in [low, high), in increasing order.
+    pub fn sorted<T>(&mut self, len: usize, low: T, high: T) -> Vec<T>
+    where
+        T: SampleUniform + Copy + PartialOrd,
+    {
+        let mut output = self.uniform(len, low, high);
+        output.sort_by(|a, b| {
+            a.partial_cmp(b)
+                .expect("[ERR] Uniform values can not be NaN.")
+        });
+        output
+    }
+
+    /// Uniform values in [low, high), in decreasing order.
+    pub fn reverse_sorted<T>(&mut self, len: usize, low: T, high: T) -> Vec<T>
+    where
+        T: SampleUniform + Copy + PartialOrd,
+    {
+        let mut output = self.sorted(len, low, high);
+        output.reverse();
+        output
+    }
+
+    /// Sorted values where `swaps` random pairs of neighbours were swapped.
+    pub fn nearly_sorted<T>(&mut self, len: usize, low: T, high: T, swaps: usize) -> Vec<T>
+    where
+        T: SampleUniform + Copy + PartialOrd,
+    {
+        let mut output = self.sorted(len, low, high);
+        if len > 1 {
+            for _ in 0..swaps {
+                let i = self.gen_range(0..len - 1);
+                output.swap(i, i + 1);
+            }
+        }
+        output
+    }
+
+    /// Values picked among `distinct` uniform values in [low, high).
+    pub fn duplicates<T>(&mut self, len: usize, distinct: usize, low: T, high: T) -> Vec<T>
+    where
+        T: SampleUniform + Copy + PartialOrd,
+    {
+        assert!(distinct > 0, "[ERR] There must be at least one value.");
+        let values = self.uniform(distinct, low, high);
+        (0..len)
+            .map(|_| values[self.gen_range(0..distinct)])
+            .collect()
+    }
+
+    /// Uniform values in [low, high), each replaced by one of `RandomFloat::specials`
+    /// with the probability `ratio`.
+    pub fn with_specials<T: RandomFloat>(
+        &mut self,
+        len: usize,
+        low: T,
+        high: T,
+        ratio: f64,
+    ) -> Vec<T> {
+        let specials = T::specials();
+        let mut output = self.uniform(len, low, high);
+        for value in output.iter_mut() {
+            if self.gen_bool(ratio) {
+                *value = specials[self.gen_range(0..specials.len())];
+            }
+        }
+        output
+    }
+}
+
+impl RngCore for Generator {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
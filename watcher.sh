@@ -1,3 +1,4 @@
 #!/bin/bash
 
 shdrr -rv -d examples/shaders/src -o examples/shaders/bin 
+shdrr -rv -d shaders/src -o shaders/bin